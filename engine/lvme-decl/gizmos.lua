@@ -0,0 +1,45 @@
+gizmos = {}
+
+--- Draw a line in world space. The colour is a table of {r, g, b, a} between 0 and 1 and defaults to white.
+--- Without a duration (in seconds), the line is only drawn for the current frame.
+---@param x1 number
+---@param y1 number
+---@param x2 number
+---@param y2 number
+---@param color table|nil
+---@param duration number|nil
+function gizmos.line(x1, y1, x2, y2, color, duration) end
+
+--- Draw the outline of a rectangle centered on (x, y)
+---@param x number
+---@param y number
+---@param width number
+---@param height number
+---@param color table|nil
+---@param duration number|nil
+function gizmos.rect(x, y, width, height, color, duration) end
+
+--- Draw the outline of a circle centered on (x, y)
+---@param x number
+---@param y number
+---@param radius number
+---@param color table|nil
+---@param duration number|nil
+function gizmos.circle(x, y, radius, color, duration) end
+
+--- Draw an arrow pointing from (x1, y1) to (x2, y2)
+---@param x1 number
+---@param y1 number
+---@param x2 number
+---@param y2 number
+---@param color table|nil
+---@param duration number|nil
+function gizmos.arrow(x1, y1, x2, y2, color, duration) end
+
+--- Draw a cross centered on (x, y)
+---@param x number
+---@param y number
+---@param size number
+---@param color table|nil
+---@param duration number|nil
+function gizmos.cross(x, y, size, color, duration) end
@@ -0,0 +1,24 @@
+palettes = {}
+
+--- Load a palette texture from the asset archive under a name. Every row of the texture is a palette, every
+--- sprite with a PaletteSwap using the name is recolored with it from the next frame on, replacing the texture
+--- the name had before.
+---@param name string
+---@param asset string
+function palettes.load(name, asset) end
+
+--- Remove the palette with the name, the sprites using it aren't drawn until a palette is loaded under it again.
+--- Returns whether there was a palette with the name.
+---@param name string
+---@return boolean
+function palettes.remove(name) end
+
+--- The amount of palettes (rows) in the palette texture with the name, or nil if there's no palette with the name
+---@param name string
+---@return number|nil
+function palettes.rows(name) end
+
+--- Whether there's a palette with the name
+---@param name string
+---@return boolean
+function palettes.exists(name) end
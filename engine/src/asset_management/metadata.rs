@@ -0,0 +1,38 @@
+use crate::render_engine::components::nine_slice::NineSliceInsets;
+use crate::render_engine::texture::TextureImportSettings;
+use log::warn;
+use serde::Deserialize;
+
+/// Optional information about an asset, stored next to it in the archive as `<asset id>.meta`
+///
+/// The file is written in RON, every field is optional:
+/// ```ron
+/// (
+///     texture: (filter: Linear, address_mode: Repeat, mipmaps: true),
+///     nine_slice: Some((left: 8, right: 8, top: 6, bottom: 10)),
+/// )
+/// ```
+/// The index maps of palette swapped sprites are loaded with `texture: (indexed: true)`.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AssetMetadata {
+    pub texture: TextureImportSettings,
+    pub nine_slice: Option<NineSliceInsets>,
+}
+
+impl AssetMetadata {
+    pub fn metadata_id(id: &str) -> String {
+        format!("{}.meta", id)
+    }
+
+    /// parses the metadata, logging a warning and falling back to the default if it's invalid
+    pub(super) fn parse(id: &str, data: &[u8]) -> Self {
+        match ron::de::from_bytes(data) {
+            Ok(x) => x,
+            Err(e) => {
+                warn!("Failed to parse metadata for {}: {}", id, e);
+                Self::default()
+            }
+        }
+    }
+}
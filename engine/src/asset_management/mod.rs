@@ -1,10 +1,12 @@
 mod cache_clean_job;
 mod gpu_texture_ref;
+mod metadata;
 mod texture_load_job;
 mod uuid;
 
 pub use cache_clean_job::CacheCleanJob;
 pub use gpu_texture_ref::GpuTextureRef;
+pub use metadata::AssetMetadata;
 use texture_load_job::TextureLoadJob;
 pub use uuid::{ToUuid, Uuid};
 
@@ -45,6 +47,9 @@ pub struct AssetLoader {
     pub(crate) archives: DashMap<Uuid, Mutex<Archive<File>>>,
     pub(crate) raw_cache: DashMap<Uuid, Arc<Vec<u8>>>,
     pub(crate) tex_cache: DashMap<Uuid, Arc<GpuTexture>>,
+    /// the asset and format every texture in `tex_cache` was loaded from, to upload them again
+    /// after the device was lost
+    pub(crate) tex_sources: DashMap<Uuid, (String, Option<ImageFormat>)>,
     pub(crate) tex_placeholder: ArcSwap<Option<Arc<GpuTexture>>>,
     pub(crate) tex_placeholder_uuid: ArcSwap<Option<Uuid>>,
     pub(crate) lua_script_cache: DashMap<Uuid, Arc<Vec<u8>>>,
@@ -68,6 +73,7 @@ impl AssetLoader {
             archives: DashMap::new(),
             raw_cache: DashMap::new(),
             tex_cache: DashMap::new(),
+            tex_sources: DashMap::new(),
             tex_placeholder: ArcSwap::new(Arc::new(None)),
             tex_placeholder_uuid: ArcSwap::new(Arc::new(None)),
             lua_script_cache: DashMap::new(),
@@ -92,19 +98,14 @@ impl AssetLoader {
         id: &str,
         format: ImageFormat,
     ) -> Result<()> {
-        let data = Self::get_asset_uncached(id)?;
-        let uuid = Uuid::new_v5(&UUID_NAMESPACE_ASSETS, id.as_bytes());
-        let texture = GpuTexture::new_from_data_with_format(
-            device,
-            queue,
-            data.deref(),
-            format,
-            Some(id),
-            uuid,
-        )?;
+        let texture = Self::create_texture_from_asset(device, queue, id, Some(format))?;
+        let uuid = texture.uuid();
         let atex = Arc::new(texture);
         Self::insert_into_texture_cache(id, Arc::clone(&atex));
         Self::with_loader(|loader| {
+            loader
+                .tex_sources
+                .insert(uuid, (id.to_string(), Some(format)));
             loader.tex_placeholder.store(Arc::new(Some(atex)));
             loader.tex_placeholder_uuid.store(Arc::new(Some(uuid)));
         });
@@ -209,6 +210,15 @@ impl AssetLoader {
         }
     }
 
+    /// loads the `.meta` file belonging to an asset, if there is one
+    pub fn get_metadata(id: &str) -> Option<AssetMetadata> {
+        let meta_id = AssetMetadata::metadata_id(id);
+        match Self::get_asset_uncached(&meta_id) {
+            Ok(data) => Some(AssetMetadata::parse(&meta_id, &data)),
+            Err(_) => None,
+        }
+    }
+
     pub fn get_asset_from_raw_cache(id: &str) -> Option<Arc<Vec<u8>>> {
         let uuid = Uuid::new_v5(&UUID_NAMESPACE_ASSETS, id.as_bytes());
 
@@ -227,6 +237,9 @@ impl AssetLoader {
                 &loader.tex_cache,
                 1 + loader.active_cache_debug_ui.load(Ordering::Relaxed) as usize,
             );
+            loader
+                .tex_sources
+                .retain(|uuid, _| loader.tex_cache.contains_key(uuid));
             count + clean_cache_inner(&loader.raw_cache, 1)
         });
 
@@ -269,6 +282,89 @@ impl AssetLoader {
         Self::load_texture_inner(id, None)
     }
 
+    fn create_texture_from_asset(
+        device: &Device,
+        queue: &Queue,
+        id: &str,
+        format: Option<ImageFormat>,
+    ) -> Result<GpuTexture> {
+        let data = Self::get_asset_uncached(id)?;
+        let uuid = Uuid::new_v5(&UUID_NAMESPACE_ASSETS, id.as_bytes());
+        let metadata = Self::get_metadata(id).unwrap_or_default();
+        match format {
+            Some(format) => GpuTexture::new_from_data_with_format(
+                device,
+                queue,
+                &data,
+                format,
+                Some(id),
+                uuid,
+                metadata,
+            ),
+            None => GpuTexture::new_from_data(device, queue, &data, Some(id), uuid, metadata),
+        }
+    }
+
+    /// uploads every cached texture again from the asset it was loaded from, used after the device
+    /// was lost, returns the textures that weren't loaded from an asset and have to be recreated
+    /// by whoever generated them
+    pub(crate) fn reupload_textures(device: &Device, queue: &Queue) -> Vec<Uuid> {
+        puffin::profile_function!();
+        let uuids: Vec<Uuid> =
+            Self::with_loader(|loader| loader.tex_cache.iter().map(|v| *v.key()).collect());
+
+        let mut generated = Vec::new();
+        for uuid in uuids {
+            let source =
+                Self::with_loader(|loader| loader.tex_sources.get(&uuid).map(|s| s.clone()));
+            let (id, format) = match source {
+                Some(x) => x,
+                None => {
+                    generated.push(uuid);
+                    continue;
+                }
+            };
+
+            match Self::create_texture_from_asset(device, queue, &id, format) {
+                Ok(texture) => Self::replace_cached_texture(&uuid, Arc::new(texture)),
+                Err(e) => warn!("Failed to upload texture {} again: {}", id, e),
+            }
+        }
+
+        info!(
+            "Uploaded the texture cache again, {} generated textures left",
+            generated.len()
+        );
+        generated
+    }
+
+    /// swaps the texture of a cache entry, the references to the old texture move over to the new
+    /// one
+    pub(crate) fn replace_cached_texture(uuid: &Uuid, texture: Arc<GpuTexture>) {
+        Self::with_loader(|loader| {
+            if **loader.tex_placeholder_uuid.load() == Some(*uuid) {
+                // the placeholder holds a real reference, which shouldn't move over
+                loader
+                    .tex_placeholder
+                    .store(Arc::new(Some(Arc::clone(&texture))));
+            }
+
+            let mut entry = match loader.tex_cache.get_mut(uuid) {
+                Some(x) => x,
+                None => return,
+            };
+            // every GpuTextureRef holds a strong count on the cached texture, see clean_cache_inner
+            let refs = Arc::strong_count(entry.value()) - 1;
+            for _ in 0..refs {
+                unsafe { Arc::increment_strong_count(Arc::as_ptr(&texture)) };
+            }
+            let old = std::mem::replace(entry.value_mut(), texture);
+            for _ in 0..refs {
+                unsafe { Arc::decrement_strong_count(Arc::as_ptr(&old)) };
+            }
+        })
+    }
+
     fn load_texture_from_cache(id: &str) -> Option<Arc<GpuTexture>> {
         let uuid = Uuid::new_v5(&UUID_NAMESPACE_ASSETS, id.as_bytes());
 
@@ -278,6 +374,14 @@ impl AssetLoader {
         })
     }
 
+    /// puts a texture that wasn't loaded from an archive into the cache, so it can be used
+    /// through a `GpuTextureRef`
+    pub(crate) fn insert_generated_texture(id: &str, texture: Arc<GpuTexture>) -> GpuTextureRef {
+        let uuid = texture.uuid();
+        Self::insert_into_texture_cache(id, texture);
+        GpuTextureRef::new_shared(uuid)
+    }
+
     fn insert_into_texture_cache(id: &str, texture: Arc<GpuTexture>) {
         let uuid = Uuid::new_v5(&UUID_NAMESPACE_ASSETS, id.as_bytes());
 
@@ -1,5 +1,4 @@
-use crate::asset_management::{AssetLoader, GpuTextureRef, ToUuid, Uuid, UUID_NAMESPACE_ASSETS};
-use crate::render_engine::texture::GpuTexture;
+use crate::asset_management::{AssetLoader, GpuTextureRef, ToUuid};
 use crate::scheduler::{Job, JobFrequency};
 use anyhow::Result;
 use image::ImageFormat;
@@ -20,22 +19,16 @@ impl Job for TextureLoadJob {
     }
 
     fn run(&mut self, device: &Device, queue: &Queue) -> Result<()> {
-        let data = AssetLoader::get_asset_uncached(&self.id)?;
-        let uuid = Uuid::new_v5(&UUID_NAMESPACE_ASSETS, self.id.as_bytes());
-        let texture = match self.format {
-            Some(format) => GpuTexture::new_from_data_with_format(
-                device,
-                queue,
-                &data,
-                format,
-                Some(&self.id),
-                uuid,
-            ),
-            None => GpuTexture::new_from_data(device, queue, &data, Some(&self.id), uuid),
-        }?;
+        let texture = AssetLoader::create_texture_from_asset(device, queue, &self.id, self.format)?;
+        let uuid = texture.uuid();
 
         let cached_texture = Arc::new(texture);
         AssetLoader::insert_into_texture_cache(&self.id, cached_texture);
+        AssetLoader::with_loader(|loader| {
+            loader
+                .tex_sources
+                .insert(uuid, (self.id.clone(), self.format))
+        });
         self.tex.swap(Arc::new(uuid));
         Ok(())
     }
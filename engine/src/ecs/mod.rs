@@ -3,6 +3,7 @@ mod stage;
 pub mod systems;
 
 use crate::ecs::resources::KeyboardInput;
+use crate::render_engine::render_graph::RenderGraph;
 use crate::render_engine::RenderEngineResources;
 use bevy_ecs::prelude::*;
 use bevy_ecs::schedule::ExecutorKind;
@@ -16,6 +17,8 @@ pub struct EcsWorld {
     pub world: World,
     pub schedule: Schedule,
     pub render_schedule: Schedule,
+    /// the passes added to the render schedule before the first frame
+    pub render_graph: RenderGraph,
 }
 
 impl EcsWorld {
@@ -32,6 +35,7 @@ impl EcsWorld {
         world.insert_resource(resources::CursorPosition::default());
         world.insert_resource(resources::KeyboardInput::default());
         world.insert_resource(resources::DeltaTime::default());
+        world.insert_resource(resources::FixedTimestep::default());
         world.insert_resource(resources::delta_time::LastDeltaTimeInstant::default());
 
         // initialize systems
@@ -47,6 +51,7 @@ impl EcsWorld {
             world,
             schedule,
             render_schedule,
+            render_graph: RenderGraph::default(),
         };
 
         world
@@ -56,7 +61,19 @@ impl EcsWorld {
         puffin::profile_function!();
         self.schedule.run(&mut self.world);
         self.schedule.apply_deferred(&mut self.world);
-        self.render_schedule.run(&mut self.world);
+
+        if !self.render_graph.is_built() {
+            if let Err(e) = self
+                .render_graph
+                .build(&mut self.world, &mut self.render_schedule)
+            {
+                panic!("Failed to build the render graph: {}", e);
+            }
+        }
+        // the frame is skipped when there's no surface texture, like when the device was lost
+        if crate::render_engine::ecs::frame_acquired(&self.world) {
+            self.render_schedule.run(&mut self.world);
+        }
     }
 
     pub fn insert_entity<F: FnOnce(EntityWorldMut) -> R, R>(&mut self, f: F) -> R {
@@ -19,6 +19,10 @@ impl Default for DeltaTime {
     }
 }
 
+/// when set, the delta time is this fixed step instead of the time the last frame took
+#[derive(Resource, Default)]
+pub struct FixedTimestep(pub Option<Duration>);
+
 #[derive(Resource)]
 pub(crate) struct LastDeltaTimeInstant(pub Instant);
 
@@ -1,5 +1,5 @@
 pub mod delta_time;
 pub mod input;
 
-pub use delta_time::DeltaTime;
+pub use delta_time::{DeltaTime, FixedTimestep};
 pub use input::{CursorPosition, KeyboardInput};
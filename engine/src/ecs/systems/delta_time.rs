@@ -1,14 +1,15 @@
 use crate::ecs::resources::delta_time::LastDeltaTimeInstant;
-use crate::ecs::resources::DeltaTime;
-use bevy_ecs::system::ResMut;
+use crate::ecs::resources::{DeltaTime, FixedTimestep};
+use bevy_ecs::system::{Res, ResMut};
 use std::time::Instant;
 
 pub fn update_delta_time(
     mut delta_time: ResMut<DeltaTime>,
     mut last_delta_time_instant: ResMut<LastDeltaTimeInstant>,
+    fixed_timestep: Res<FixedTimestep>,
 ) {
     puffin::profile_function!();
     let elapsed = last_delta_time_instant.0.elapsed();
-    delta_time.0 = elapsed;
+    delta_time.0 = fixed_timestep.0.unwrap_or(elapsed);
     last_delta_time_instant.0 = Instant::now();
 }
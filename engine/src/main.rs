@@ -11,19 +11,20 @@ mod ui;
 use crate::asset_management::AssetLoader;
 use crate::ecs::EcsWorld;
 use crate::render_engine::components::{position::Position, texture::Texture};
+use crate::render_engine::frame_limiter::FrameLimiterSettings;
 use crate::scripting::LuaScript;
 use cgmath::{Vector2, Vector3};
 use dialog::DialogBox;
 use log::{error, trace};
 use std::panic::catch_unwind;
+use std::path::PathBuf;
 use winit::{
     event::*,
     event_loop::{ControlFlow, EventLoop},
     window::WindowBuilder,
 };
 
-//const TARGET_FPS: f64 = 144.0;
-//const TARGET_FRAMETIME: f64 = 1000.0 / TARGET_FPS;
+const TARGET_FPS: f64 = 144.0;
 
 fn main() {
     asset_management::KEEP_ASSET_NAMES.store(true, std::sync::atomic::Ordering::Relaxed);
@@ -52,6 +53,23 @@ fn main() {
     }
 }
 
+/// the directory of the `--wgpu-trace <dir>` option, a wgpu API trace of the run is recorded into it
+fn wgpu_trace_arg() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(dir) = arg.strip_prefix("--wgpu-trace=") {
+            return Some(PathBuf::from(dir));
+        }
+        if arg == "--wgpu-trace" {
+            match args.next() {
+                Some(dir) => return Some(PathBuf::from(dir)),
+                None => error!("--wgpu-trace needs a directory, no trace is recorded"),
+            }
+        }
+    }
+    None
+}
+
 fn engine_main() {
     pretty_env_logger::init();
     puffin::set_scopes_on(true);
@@ -68,7 +86,12 @@ fn engine_main() {
     let window = WindowBuilder::new().build(&event_loop).unwrap();
 
     let mut world = EcsWorld::new();
-    render_engine::ecs::init_renderer_resources_in_world(&mut world, window, &event_loop);
+    render_engine::ecs::init_renderer_resources_in_world(
+        &mut world,
+        window,
+        &event_loop,
+        wgpu_trace_arg(),
+    );
     render_engine::ecs::insert_renderer_systems_in_schedule(&mut world);
     demo_sys::initialize_in_world(&mut world);
 
@@ -105,7 +128,14 @@ fn engine_main() {
         e.id()
     });
 
-    let wid = world.get_render_engine(|e| e.window_id());
+    let wid = world.get_render_engine(|mut e| {
+        let limiter = e.frame_limiter_mut();
+        limiter.set_settings(FrameLimiterSettings {
+            target_fps: Some(TARGET_FPS),
+            ..limiter.settings()
+        });
+        e.window_id()
+    });
 
     log::info!("Entering main loop");
     event_loop.run(move |event, _, control_flow| {
@@ -126,6 +156,7 @@ fn engine_main() {
                 } => *control_flow = ControlFlow::Exit,
                 WindowEvent::KeyboardInput { input, .. } => {
                     world.update_keyboard_input(input);
+                    render_engine::capture::process_hotkey(&mut world.world, input);
                 }
                 WindowEvent::Resized(new_size) => {
                     world.get_render_engine(|mut e| e.resize(new_size))
@@ -147,11 +178,14 @@ fn engine_main() {
             },
             Event::RedrawRequested(_) => {
                 puffin::GlobalProfiler::lock().new_frame();
+                world.get_render_engine(|mut e| e.frame_limiter_mut().begin_frame());
                 world.run_schedule();
+                world.get_render_engine(|mut e| e.frame_limiter_mut().end_frame());
             }
-            Event::MainEventsCleared
-            | Event::UserEvent(ui::integration::EguiRequestRedrawEvent::RequestRedraw) => {
-                world.get_render_engine(|e| e.request_redraw());
+            // the frame limiter decides when the next frame is drawn, after the events and
+            // redraws of this iteration have been handled
+            Event::RedrawEventsCleared if *control_flow != ControlFlow::Exit => {
+                *control_flow = world.get_render_engine(|mut e| e.poll_frame_limiter());
             }
             _ => (),
         }
@@ -0,0 +1,72 @@
+use crate::asset_management::AssetLoader;
+use wgpu::*;
+
+/// Copies a frame drawn offscreen into the surface, surfaces can't be a copy destination on
+/// every backend, so it's drawn instead
+pub fn init(device: &Device, format: TextureFormat, layout: &BindGroupLayout) -> RenderPipeline {
+    let raw_shader_source = AssetLoader::get_asset("blit.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Blit SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Blit RPL"),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Blit RP"),
+        layout: Some(&render_pipeline_layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            // the fullscreen triangle is generated from the vertex index
+            buffers: &[],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            }],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Cw,
+            cull_mode: None,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+pub fn build_bind_group_layout(device: &Device, label: &str) -> BindGroupLayout {
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        entries: &[BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                multisampled: false,
+                view_dimension: TextureViewDimension::D2,
+                sample_type: TextureSampleType::Float { filterable: false },
+            },
+            count: None,
+        }],
+        label: Some(&format!("{} Blit BGL", label)),
+    })
+}
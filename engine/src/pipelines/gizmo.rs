@@ -0,0 +1,75 @@
+use crate::asset_management::{AssetLoader, ToUuid};
+use crate::render_engine::buffer::{GpuUniformBuffer, GpuVertexBufferLayout};
+use crate::render_engine::camera::CameraUniform;
+use crate::render_engine::vertex::ColorVertex2;
+use wgpu::*;
+
+/// Draws the debug lines from the `Gizmos` resource
+pub struct GizmoRenderPipeline;
+
+impl ToUuid for GizmoRenderPipeline {}
+
+impl super::RenderPipelineInit for GizmoRenderPipeline {
+    fn init(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        samples: u32,
+    ) -> anyhow::Result<RenderPipeline> {
+        Ok(init(device, format, samples))
+    }
+}
+
+pub fn init(device: &Device, format: TextureFormat, samples: u32) -> RenderPipeline {
+    let raw_shader_source = AssetLoader::get_asset("gizmo.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Gizmo SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Gizmo RPL"),
+        bind_group_layouts: &[&GpuUniformBuffer::<CameraUniform>::bind_group_static(
+            &device,
+            Some("Gizmo RPL Camera BGL"),
+        )],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Gizmo RP"),
+        layout: Some(&render_pipeline_layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[ColorVertex2::layout()],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format,
+                blend: Some(BlendState::ALPHA_BLENDING),
+                write_mask: ColorWrites::ALL,
+            }],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::LineList,
+            strip_index_format: None,
+            front_face: FrontFace::Cw,
+            cull_mode: None,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: MultisampleState {
+            count: samples,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
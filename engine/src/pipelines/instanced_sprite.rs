@@ -0,0 +1,79 @@
+use crate::asset_management::{AssetLoader, ToUuid};
+use crate::render_engine::buffer::{GpuUniformBuffer, GpuVertexBufferLayout};
+use crate::render_engine::camera::CameraUniform;
+use crate::render_engine::texture::GpuTexture;
+use crate::render_engine::vertex::{SpriteInstance, Vertex2};
+use wgpu::*;
+
+/// Draws many sprites sharing a texture in a single draw call, using a `SpriteInstance` buffer
+pub struct InstancedSpriteRenderPipeline;
+
+impl ToUuid for InstancedSpriteRenderPipeline {}
+
+impl super::RenderPipelineInit for InstancedSpriteRenderPipeline {
+    fn init(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        samples: u32,
+    ) -> anyhow::Result<RenderPipeline> {
+        Ok(init(device, format, samples))
+    }
+}
+
+pub fn init(device: &Device, format: TextureFormat, samples: u32) -> RenderPipeline {
+    let raw_shader_source = AssetLoader::get_asset("instanced_sprite.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Instanced sprite SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Instanced sprite RPL"),
+        bind_group_layouts: &[
+            &GpuUniformBuffer::<CameraUniform>::bind_group_static(
+                &device,
+                Some("Instanced sprite RPL Camera BGL"),
+            ),
+            &GpuTexture::build_bind_group_layout(&device, "Instanced sprite RPL Texture BGL"),
+        ],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Instanced sprite RP"),
+        layout: Some(&render_pipeline_layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex2::layout(), SpriteInstance::layout()],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format,
+                blend: Some(BlendState::ALPHA_BLENDING),
+                write_mask: ColorWrites::ALL,
+            }],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Cw,
+            cull_mode: None,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: MultisampleState {
+            count: samples,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
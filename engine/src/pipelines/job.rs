@@ -1,13 +1,14 @@
 use crate::asset_management::{ToUuid, Uuid};
-use crate::pipelines::RenderPipelineInit;
+use crate::pipelines::{ComputePipelineInit, RenderPipelineInit};
 use crate::scheduler::{Job, JobFrequency};
 use ahash::AHashMap;
 use parking_lot::Mutex;
 use std::sync::Arc;
-use wgpu::{Device, Queue, RenderPipeline, TextureFormat};
+use wgpu::{ComputePipeline, Device, Queue, RenderPipeline, TextureFormat};
 
 pub struct InitPipelineJob {
     format: TextureFormat,
+    samples: u32,
     pipeline: &'static dyn RenderPipelineInit,
     map: Arc<Mutex<AHashMap<Uuid, Arc<RenderPipeline>>>>,
 }
@@ -20,7 +21,7 @@ impl Job for InitPipelineJob {
     }
 
     fn run(&mut self, device: &Device, _: &Queue) -> anyhow::Result<()> {
-        let pipeline = Arc::new(self.pipeline.init(device, self.format)?);
+        let pipeline = Arc::new(self.pipeline.init(device, self.format, self.samples)?);
         self.map.lock().insert(self.pipeline.uuid(), pipeline);
         Ok(())
     }
@@ -30,12 +31,42 @@ impl InitPipelineJob {
     pub fn new(
         pipeline: &'static dyn RenderPipelineInit,
         format: TextureFormat,
+        samples: u32,
         map: Arc<Mutex<AHashMap<Uuid, Arc<RenderPipeline>>>>,
     ) -> Self {
         Self {
             pipeline,
             format,
+            samples,
             map,
         }
     }
 }
+
+pub struct InitComputePipelineJob {
+    pipeline: &'static dyn ComputePipelineInit,
+    map: Arc<Mutex<AHashMap<Uuid, Arc<ComputePipeline>>>>,
+}
+
+impl ToUuid for InitComputePipelineJob {}
+
+impl Job for InitComputePipelineJob {
+    fn get_freq(&self) -> JobFrequency {
+        JobFrequency::Once
+    }
+
+    fn run(&mut self, device: &Device, _: &Queue) -> anyhow::Result<()> {
+        let pipeline = Arc::new(self.pipeline.init(device)?);
+        self.map.lock().insert(self.pipeline.uuid(), pipeline);
+        Ok(())
+    }
+}
+
+impl InitComputePipelineJob {
+    pub fn new(
+        pipeline: &'static dyn ComputePipelineInit,
+        map: Arc<Mutex<AHashMap<Uuid, Arc<ComputePipeline>>>>,
+    ) -> Self {
+        Self { pipeline, map }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::asset_management::{AssetLoader, ToUuid};
+use crate::render_engine::buffer::{GpuUniformBuffer, GpuVertexBufferLayout};
+use crate::render_engine::camera::CameraUniform;
+use crate::render_engine::texture::GpuTexture;
+use crate::render_engine::vertex::Vertex2;
+use wgpu::*;
+
+/// the format of the offscreen target the normals of all sprites are rendered into
+pub const NORMAL_TARGET_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+/// Renders the normal maps of sprites into the normal target
+pub struct NormalRenderPipeline;
+
+impl ToUuid for NormalRenderPipeline {}
+
+impl super::RenderPipelineInit for NormalRenderPipeline {
+    fn init(&self, device: &Device, _: TextureFormat, _: u32) -> anyhow::Result<RenderPipeline> {
+        // the normal target is never multisampled, the lights are smooth anyway
+        Ok(init_normal(device))
+    }
+}
+
+/// Composites the lights on top of the sprites that have already been drawn
+pub struct LightingRenderPipeline;
+
+impl ToUuid for LightingRenderPipeline {}
+
+impl super::RenderPipelineInit for LightingRenderPipeline {
+    fn init(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        samples: u32,
+    ) -> anyhow::Result<RenderPipeline> {
+        Ok(init_lighting(device, format, samples))
+    }
+}
+
+pub fn init_normal(device: &Device) -> RenderPipeline {
+    let raw_shader_source = AssetLoader::get_asset("normal.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Normal SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Normal RPL"),
+        bind_group_layouts: &[
+            &GpuUniformBuffer::<CameraUniform>::bind_group_static(
+                &device,
+                Some("Normal RPL Camera BGL"),
+            ),
+            &GpuTexture::build_bind_group_layout(&device, "Normal RPL Color Texture BGL"),
+            &GpuTexture::build_bind_group_layout(&device, "Normal RPL Normal Texture BGL"),
+        ],
+        push_constant_ranges: &[PushConstantRange {
+            // same layout as the sprite pipeline, see SpritePushConstant
+            stages: ShaderStages::VERTEX,
+            range: 0..68,
+        }],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Normal RP"),
+        layout: Some(&render_pipeline_layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex2::layout().to_owned()],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format: NORMAL_TARGET_FORMAT,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            }],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Cw,
+            cull_mode: None,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+pub fn init_lighting(device: &Device, format: TextureFormat, samples: u32) -> RenderPipeline {
+    let raw_shader_source = AssetLoader::get_asset("lighting.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Lighting SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Lighting RPL"),
+        bind_group_layouts: &[&build_bind_group_layout(&device, "Lighting RPL")],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Lighting RP"),
+        layout: Some(&render_pipeline_layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            // the fullscreen triangle is generated from the vertex index
+            buffers: &[],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format,
+                // multiplies the colour that is already in the frame with the light
+                blend: Some(BlendState {
+                    color: BlendComponent {
+                        src_factor: BlendFactor::Dst,
+                        dst_factor: BlendFactor::Zero,
+                        operation: BlendOperation::Add,
+                    },
+                    alpha: BlendComponent {
+                        src_factor: BlendFactor::Zero,
+                        dst_factor: BlendFactor::One,
+                        operation: BlendOperation::Add,
+                    },
+                }),
+                write_mask: ColorWrites::ALL,
+            }],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Cw,
+            cull_mode: None,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: MultisampleState {
+            count: samples,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+pub fn build_bind_group_layout(device: &Device, label: &str) -> BindGroupLayout {
+    let storage_entry = |binding| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // lights
+            storage_entry(1),
+            // occluder edges
+            storage_entry(2),
+            BindGroupLayoutEntry {
+                binding: 3,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2,
+                    sample_type: TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 4,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+        label: Some(&format!("{} Lighting BGL", label)),
+    })
+}
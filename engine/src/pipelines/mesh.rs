@@ -0,0 +1,105 @@
+use crate::asset_management::{AssetLoader, ToUuid};
+use crate::render_engine::buffer::{GpuUniformBuffer, GpuVertexBufferLayout};
+use crate::render_engine::camera::CameraUniform;
+use crate::render_engine::texture::GpuTexture;
+use crate::render_engine::vertex::MeshVertex;
+use cgmath::Matrix4;
+use wgpu::*;
+
+/// Draws a `Mesh2D` with the same camera and texture bind groups as the sprite pipeline
+pub struct MeshRenderPipeline;
+
+impl ToUuid for MeshRenderPipeline {}
+
+impl super::RenderPipelineInit for MeshRenderPipeline {
+    fn init(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        samples: u32,
+    ) -> anyhow::Result<RenderPipeline> {
+        Ok(init(device, format, samples))
+    }
+}
+
+pub fn init(device: &Device, format: TextureFormat, samples: u32) -> RenderPipeline {
+    let raw_shader_source = AssetLoader::get_asset("mesh.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Mesh SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Mesh RPL"),
+        bind_group_layouts: &[
+            &GpuUniformBuffer::<CameraUniform>::bind_group_static(
+                &device,
+                Some("Mesh RPL Camera BGL"),
+            ),
+            &GpuTexture::build_bind_group_layout(&device, "Mesh RPL Texture BGL"),
+        ],
+        push_constant_ranges: &[PushConstantRange {
+            // same layout as the sprite pipeline, see MeshPushConstant
+            stages: ShaderStages::VERTEX,
+            range: 0..68,
+        }],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Mesh RP"),
+        layout: Some(&render_pipeline_layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[MeshVertex::layout().to_owned()],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format,
+                blend: Some(BlendState::ALPHA_BLENDING),
+                write_mask: ColorWrites::ALL,
+            }],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Ccw,
+            // the triangulation doesn't guarantee a winding order
+            cull_mode: None,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: MultisampleState {
+            count: samples,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+/// The model matrix and z-depth of a mesh, unlike `SpritePushConstant` the model isn't scaled
+pub struct MeshPushConstant {
+    model: Matrix4<f32>,
+    z_layer: f32,
+}
+
+impl MeshPushConstant {
+    pub fn new(model: Matrix4<f32>, z_layer: f32) -> Self {
+        Self { model, z_layer }
+    }
+
+    pub fn as_bytes(&self) -> [u8; 68] {
+        let mut bytes = [0u8; 68];
+        let model: [[f32; 4]; 4] = self.model.into();
+        bytes[0..64].copy_from_slice(bytemuck::cast_slice(&model));
+        bytes[64..68].copy_from_slice(&self.z_layer.to_ne_bytes());
+        bytes
+    }
+}
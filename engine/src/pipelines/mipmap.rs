@@ -0,0 +1,83 @@
+use crate::asset_management::AssetLoader;
+use crate::render_engine::texture::GpuTexture;
+use ahash::AHashMap;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use wgpu::*;
+
+lazy_static! {
+    /// the pipelines that were built so far, by the format of the textures they downsample
+    static ref PIPELINES: Mutex<AHashMap<TextureFormat, Arc<RenderPipeline>>> =
+        Mutex::new(AHashMap::new());
+}
+
+/// returns the pipeline for the format, it's built when the first texture in it is loaded
+pub fn get(device: &Device, format: TextureFormat) -> Arc<RenderPipeline> {
+    PIPELINES
+        .lock()
+        .entry(format)
+        .or_insert_with(|| Arc::new(init(device, format)))
+        .clone()
+}
+
+/// the pipelines belong to the device they were built on, so they're dropped when it's recreated
+pub fn clear_cache() {
+    PIPELINES.lock().clear();
+}
+
+/// Downsamples one mip level of a texture into the next one, used when textures are loaded
+/// so it isn't part of the pipelines the engine initializes up front
+pub fn init(device: &Device, format: TextureFormat) -> RenderPipeline {
+    let raw_shader_source = AssetLoader::get_asset("mipmap.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Mipmap SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Mipmap RPL"),
+        bind_group_layouts: &[&GpuTexture::build_bind_group_layout(
+            &device,
+            "Mipmap RPL Texture BGL",
+        )],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Mipmap RP"),
+        layout: Some(&render_pipeline_layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            }],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Cw,
+            cull_mode: None,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
@@ -1,8 +1,18 @@
+pub mod blit;
+pub mod gizmo;
+pub mod instanced_sprite;
 mod job;
+pub mod lighting;
+pub mod mesh;
+pub mod mipmap;
+pub mod palette_sprite;
+pub mod particles;
 pub mod sprite;
+pub mod sprite_mask;
+pub mod tonemap;
 
 use crate::asset_management::{ToUuid, Uuid};
-use crate::scheduler::JobScheduler;
+use crate::scheduler::{JobScheduler, JobStateTracker};
 use ahash::AHashMap;
 use lazy_static::lazy_static;
 use parking_lot::Mutex;
@@ -10,30 +20,56 @@ use std::sync::Arc;
 use wgpu::*;
 
 lazy_static! {
-    static ref RENDER_PIPELINES: [&'static dyn RenderPipelineInit; 1] =
-        [&sprite::SpriteRenderPipeline];
+    static ref RENDER_PIPELINES: [&'static dyn RenderPipelineInit; 9] = [
+        &sprite::SpriteRenderPipeline,
+        &instanced_sprite::InstancedSpriteRenderPipeline,
+        &mesh::MeshRenderPipeline,
+        &gizmo::GizmoRenderPipeline,
+        &lighting::NormalRenderPipeline,
+        &lighting::LightingRenderPipeline,
+        &sprite_mask::SpriteMaskRenderPipeline,
+        &sprite_mask::MaskedSpriteRenderPipeline,
+        &palette_sprite::PaletteSpriteRenderPipeline,
+    ];
+    static ref COMPUTE_PIPELINES: [&'static dyn ComputePipelineInit; 1] =
+        [&particles::ParticleComputePipeline];
 }
 
 pub trait RenderPipelineInit: ToUuid + Sync {
-    fn init(&self, device: &Device, format: TextureFormat) -> anyhow::Result<RenderPipeline>;
+    /// `samples` is the MSAA sample count of the target the pipeline draws to
+    fn init(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        samples: u32,
+    ) -> anyhow::Result<RenderPipeline>;
+}
+
+pub trait ComputePipelineInit: ToUuid + Sync {
+    fn init(&self, device: &Device) -> anyhow::Result<ComputePipeline>;
 }
 
 pub struct Pipelines {
     render_pipelines: Arc<Mutex<AHashMap<Uuid, Arc<RenderPipeline>>>>,
+    compute_pipelines: Arc<Mutex<AHashMap<Uuid, Arc<ComputePipeline>>>>,
 }
 
 impl Pipelines {
-    pub fn new(format: TextureFormat) -> Self {
+    /// compute pipelines are only initialized if `compute_supported` is true
+    pub fn new(format: TextureFormat, samples: u32, compute_supported: bool) -> Self {
         let res = Self {
             render_pipelines: Arc::new(Mutex::new(AHashMap::new())),
+            compute_pipelines: Arc::new(Mutex::new(AHashMap::new())),
         };
 
-        let mut job_trackers = Vec::new();
+        let mut job_trackers = res.submit_render_pipeline_jobs(format, samples);
 
-        for pipeline in RENDER_PIPELINES.iter() {
-            let map = Arc::clone(&res.render_pipelines);
-            let job = job::InitPipelineJob::new(*pipeline, format.clone(), map);
-            job_trackers.push(JobScheduler::submit(Box::new(job)));
+        if compute_supported {
+            for pipeline in COMPUTE_PIPELINES.iter() {
+                let map = Arc::clone(&res.compute_pipelines);
+                let job = job::InitComputePipelineJob::new(*pipeline, map);
+                job_trackers.push(JobScheduler::submit(Box::new(job)));
+            }
         }
 
         for tracker in job_trackers {
@@ -43,12 +79,42 @@ impl Pipelines {
         res
     }
 
+    /// recreates the render pipelines for another sample count, the old ones stay in use until
+    /// this returns
+    pub fn rebuild_render_pipelines(&self, format: TextureFormat, samples: u32) {
+        for tracker in self.submit_render_pipeline_jobs(format, samples) {
+            tracker.flush().expect("successful pipeline initialization");
+        }
+    }
+
+    fn submit_render_pipeline_jobs(
+        &self,
+        format: TextureFormat,
+        samples: u32,
+    ) -> Vec<JobStateTracker> {
+        RENDER_PIPELINES
+            .iter()
+            .map(|pipeline| {
+                let map = Arc::clone(&self.render_pipelines);
+                let job = job::InitPipelineJob::new(*pipeline, format.clone(), samples, map);
+                JobScheduler::submit(Box::new(job))
+            })
+            .collect()
+    }
+
     /// This will panic if the id isn't present in the hashmap
     #[inline(always)]
     pub fn get_render_pipeline(&self, uuid: Uuid) -> Arc<RenderPipeline> {
         let lock = self.render_pipelines.lock();
         Arc::clone(lock.get(&uuid).expect(&format!("Render pipeline with asset UUID {} isn't initialized yet, or the asset UUID is invalid.", uuid)))
     }
+
+    /// This will panic if the id isn't present in the hashmap
+    #[inline(always)]
+    pub fn get_compute_pipeline(&self, uuid: Uuid) -> Arc<ComputePipeline> {
+        let lock = self.compute_pipelines.lock();
+        Arc::clone(lock.get(&uuid).expect(&format!("Compute pipeline with asset UUID {} isn't initialized yet, or the asset UUID is invalid.", uuid)))
+    }
 }
 
 // fn hash_type_name<T: Any>() -> u64 {
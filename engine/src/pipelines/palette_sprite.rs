@@ -0,0 +1,105 @@
+use crate::asset_management::{AssetLoader, ToUuid};
+use crate::pipelines::sprite::SpritePushConstant;
+use crate::render_engine::buffer::{GpuUniformBuffer, GpuVertexBufferLayout};
+use crate::render_engine::camera::CameraUniform;
+use crate::render_engine::texture::GpuTexture;
+use crate::render_engine::vertex::Vertex2;
+use wgpu::*;
+
+/// Draws indexed sprites with the colors of a row of their palette
+pub struct PaletteSpriteRenderPipeline;
+
+impl ToUuid for PaletteSpriteRenderPipeline {}
+
+impl super::RenderPipelineInit for PaletteSpriteRenderPipeline {
+    fn init(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        samples: u32,
+    ) -> anyhow::Result<RenderPipeline> {
+        Ok(init(device, format, samples))
+    }
+}
+
+pub fn init(device: &Device, format: TextureFormat, samples: u32) -> RenderPipeline {
+    let raw_shader_source = AssetLoader::get_asset("palette_sprite.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Palette sprite SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Palette sprite RPL"),
+        bind_group_layouts: &[
+            &GpuUniformBuffer::<CameraUniform>::bind_group_static(
+                device,
+                Some("Palette sprite RPL Camera BGL"),
+            ),
+            &GpuTexture::build_bind_group_layout(device, "Palette sprite RPL Index Texture BGL"),
+            &GpuTexture::build_bind_group_layout(device, "Palette sprite RPL Palette Texture BGL"),
+        ],
+        push_constant_ranges: &[PushConstantRange {
+            // 0..64 model matrix
+            // 64..68 z-depth
+            // 68..72 palette row
+            stages: ShaderStages::VERTEX_FRAGMENT,
+            range: 0..72,
+        }],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Palette sprite RP"),
+        layout: Some(&render_pipeline_layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex2::layout().to_owned()],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format,
+                blend: Some(BlendState::ALPHA_BLENDING),
+                write_mask: ColorWrites::ALL,
+            }],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Cw,
+            cull_mode: None,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: MultisampleState {
+            count: samples,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+pub struct PaletteSpritePushConstant {
+    sprite: SpritePushConstant,
+    row: u32,
+}
+
+impl PaletteSpritePushConstant {
+    pub fn new(sprite: SpritePushConstant, row: u32) -> Self {
+        Self { sprite, row }
+    }
+
+    pub fn as_bytes(&self) -> [u8; 72] {
+        let mut bytes = [0u8; 72];
+        bytes[0..68].copy_from_slice(&self.sprite.as_bytes());
+        bytes[68..72].copy_from_slice(&self.row.to_ne_bytes());
+        bytes
+    }
+}
@@ -0,0 +1,72 @@
+use crate::asset_management::{AssetLoader, ToUuid};
+use wgpu::*;
+
+/// Simulates the particles of a single emitter and writes them into its instance buffer
+pub struct ParticleComputePipeline;
+
+impl ToUuid for ParticleComputePipeline {}
+
+impl super::ComputePipelineInit for ParticleComputePipeline {
+    fn init(&self, device: &Device) -> anyhow::Result<ComputePipeline> {
+        Ok(init(device))
+    }
+}
+
+/// the workgroup size declared in particles.wgsl
+pub const WORKGROUP_SIZE: u32 = 64;
+
+pub fn init(device: &Device) -> ComputePipeline {
+    let raw_shader_source = AssetLoader::get_asset("particles.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Particle SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Particle CPL"),
+        bind_group_layouts: &[&build_bind_group_layout(device, "Particle CPL")],
+        push_constant_ranges: &[],
+    });
+
+    device.create_compute_pipeline(&ComputePipelineDescriptor {
+        label: Some("Particle CP"),
+        layout: Some(&pipeline_layout),
+        module: &shader,
+        entry_point: "cs_main",
+    })
+}
+
+pub fn build_bind_group_layout(device: &Device, label: &str) -> BindGroupLayout {
+    let storage_entry = |binding| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only: false },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // particles
+            storage_entry(1),
+            // sprite instances
+            storage_entry(2),
+        ],
+        label: Some(&format!("{} Particle BGL", label)),
+    })
+}
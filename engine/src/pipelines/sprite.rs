@@ -11,12 +11,28 @@ pub struct SpriteRenderPipeline;
 impl ToUuid for SpriteRenderPipeline {}
 
 impl super::RenderPipelineInit for SpriteRenderPipeline {
-    fn init(&self, device: &Device, format: TextureFormat) -> anyhow::Result<RenderPipeline> {
-        Ok(init(device, format))
+    fn init(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        samples: u32,
+    ) -> anyhow::Result<RenderPipeline> {
+        Ok(init(device, format, samples))
     }
 }
 
-pub fn init(device: &Device, format: TextureFormat) -> RenderPipeline {
+pub fn init(device: &Device, format: TextureFormat, samples: u32) -> RenderPipeline {
+    create(device, format, samples, "Sprite RP", None)
+}
+
+/// the sprite pipeline with a depth-stencil state, for the passes that draw into a stencil
+pub fn create(
+    device: &Device,
+    format: TextureFormat,
+    samples: u32,
+    label: &str,
+    depth_stencil: Option<DepthStencilState>,
+) -> RenderPipeline {
     let raw_shader_source = AssetLoader::get_asset("sprite.wgsl").unwrap();
     let shader_source = String::from_utf8_lossy(&raw_shader_source);
 
@@ -55,7 +71,7 @@ pub fn init(device: &Device, format: TextureFormat) -> RenderPipeline {
     });
 
     device.create_render_pipeline(&RenderPipelineDescriptor {
-        label: Some("Sprite RP"),
+        label: Some(label),
         layout: Some(&render_pipeline_layout),
         vertex: VertexState {
             module: &shader,
@@ -81,9 +97,9 @@ pub fn init(device: &Device, format: TextureFormat) -> RenderPipeline {
             unclipped_depth: false,
             conservative: false,
         },
-        depth_stencil: None,
+        depth_stencil,
         multisample: MultisampleState {
-            count: 1,
+            count: samples,
             mask: !0,
             alpha_to_coverage_enabled: false,
         },
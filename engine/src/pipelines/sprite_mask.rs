@@ -0,0 +1,154 @@
+use crate::asset_management::{AssetLoader, ToUuid};
+use crate::pipelines::sprite::SpritePushConstant;
+use crate::render_engine::buffer::{GpuUniformBuffer, GpuVertexBufferLayout};
+use crate::render_engine::camera::CameraUniform;
+use crate::render_engine::texture::GpuTexture;
+use crate::render_engine::vertex::Vertex2;
+use wgpu::*;
+
+/// wgpu 0.12 has no stencil-only format, the depth is never tested or written
+pub const MASK_STENCIL_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
+
+/// Writes a `SpriteMask` into the stencil, a mask nested `n` deep only increments the pixels that
+/// are `n - 1`, so the stencil is `n` where all masks around it overlap
+pub struct SpriteMaskRenderPipeline;
+
+impl ToUuid for SpriteMaskRenderPipeline {}
+
+impl super::RenderPipelineInit for SpriteMaskRenderPipeline {
+    fn init(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        samples: u32,
+    ) -> anyhow::Result<RenderPipeline> {
+        Ok(init(device, format, samples))
+    }
+}
+
+/// Draws sprites where the stencil equals the reference, the depth of the mask they're in
+pub struct MaskedSpriteRenderPipeline;
+
+impl ToUuid for MaskedSpriteRenderPipeline {}
+
+impl super::RenderPipelineInit for MaskedSpriteRenderPipeline {
+    fn init(
+        &self,
+        device: &Device,
+        format: TextureFormat,
+        samples: u32,
+    ) -> anyhow::Result<RenderPipeline> {
+        Ok(super::sprite::create(
+            device,
+            format,
+            samples,
+            "Masked sprite RP",
+            Some(stencil_state(StencilOperation::Keep)),
+        ))
+    }
+}
+
+fn stencil_state(pass_op: StencilOperation) -> DepthStencilState {
+    let face = StencilFaceState {
+        compare: CompareFunction::Equal,
+        fail_op: StencilOperation::Keep,
+        depth_fail_op: StencilOperation::Keep,
+        pass_op,
+    };
+
+    DepthStencilState {
+        format: MASK_STENCIL_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: CompareFunction::Always,
+        stencil: StencilState {
+            front: face,
+            back: face,
+            read_mask: !0,
+            write_mask: !0,
+        },
+        bias: DepthBiasState::default(),
+    }
+}
+
+pub fn init(device: &Device, format: TextureFormat, samples: u32) -> RenderPipeline {
+    let raw_shader_source = AssetLoader::get_asset("sprite_mask.wgsl").unwrap();
+    let shader_source = String::from_utf8_lossy(&raw_shader_source);
+
+    let shader = device.create_shader_module(&ShaderModuleDescriptor {
+        label: Some("Sprite mask SM"),
+        source: ShaderSource::Wgsl(shader_source),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Sprite mask RPL"),
+        bind_group_layouts: &[
+            &GpuUniformBuffer::<CameraUniform>::bind_group_static(
+                &device,
+                Some("Sprite mask RPL Camera BGL"),
+            ),
+            &GpuTexture::build_bind_group_layout(&device, "Sprite mask RPL Texture BGL"),
+        ],
+        push_constant_ranges: &[PushConstantRange {
+            // 0..64 model matrix
+            // 64..68 z-depth
+            // 68..72 alpha cutoff
+            stages: ShaderStages::VERTEX_FRAGMENT,
+            range: 0..72,
+        }],
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Sprite mask RP"),
+        layout: Some(&render_pipeline_layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex2::layout().to_owned()],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format,
+                blend: None,
+                // the mask only writes the stencil
+                write_mask: ColorWrites::empty(),
+            }],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Cw,
+            cull_mode: None,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(stencil_state(StencilOperation::IncrementClamp)),
+        multisample: MultisampleState {
+            count: samples,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+pub struct SpriteMaskPushConstant {
+    sprite: SpritePushConstant,
+    /// fragments with an alpha at or below this aren't written, -1.0 for rectangle masks
+    cutoff: f32,
+}
+
+impl SpriteMaskPushConstant {
+    pub fn new(sprite: SpritePushConstant, cutoff: f32) -> Self {
+        Self { sprite, cutoff }
+    }
+
+    pub fn as_bytes(&self) -> [u8; 72] {
+        let mut bytes = [0u8; 72];
+        bytes[0..68].copy_from_slice(&self.sprite.as_bytes());
+        bytes[68..72].copy_from_slice(&self.cutoff.to_ne_bytes());
+        bytes
+    }
+}
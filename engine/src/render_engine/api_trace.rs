@@ -0,0 +1,101 @@
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Records a wgpu API trace for bug reports, the trace can be replayed with wgpu's player
+///
+/// wgpu only records the calls made on a device from its creation until it's dropped, so starting
+/// or stopping a trace recreates the device the same way it's recreated after it was lost.
+#[derive(Default)]
+pub struct ApiTrace {
+    /// the directory the current device records its trace into
+    recording: Option<PathBuf>,
+    /// the trace the next device records, `Some` when a trace should start or stop
+    pending: Option<Option<PathBuf>>,
+    /// the frames the trace is still recorded for, `None` when it's recorded until the engine exits
+    frames_left: Option<u32>,
+}
+
+impl ApiTrace {
+    /// records a trace of the whole run into `dir`, from the `--wgpu-trace <dir>` option
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self {
+            recording: dir,
+            pending: None,
+            frames_left: None,
+        }
+    }
+
+    /// records a trace of the next `frames` frames into a new directory in `dir`
+    pub fn arm(&mut self, dir: &Path, frames: u32) {
+        if self.recording.is_some() {
+            warn!("A wgpu trace is already being recorded");
+            return;
+        }
+
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        self.pending = Some(Some(dir.join(format!("trace-{}", millis))));
+        self.frames_left = Some(frames.max(1));
+    }
+
+    /// the directory of the trace being recorded, and the frames it's still recorded for
+    pub fn status(&self) -> Option<(&Path, Option<u32>)> {
+        self.recording.as_deref().map(|dir| (dir, self.frames_left))
+    }
+
+    /// whether the device has to be recreated to start or stop a trace
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// counts down the frames of an armed trace, it stops once they ran out
+    pub(crate) fn frame_finished(&mut self) {
+        if self.recording.is_none() || self.pending.is_some() {
+            return;
+        }
+
+        if let Some(frames) = self.frames_left.as_mut() {
+            *frames = frames.saturating_sub(1);
+            if *frames == 0 {
+                self.pending = Some(None);
+            }
+        }
+    }
+
+    /// the directory the next device records into, the pending trace if a trace starts or stops,
+    /// it's created if it doesn't exist
+    pub(crate) fn pending_dir(&self) -> Option<PathBuf> {
+        let dir = match &self.pending {
+            Some(pending) => pending.as_ref(),
+            None => self.recording.as_ref(),
+        }?;
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            warn!(
+                "Failed to create {}, the wgpu trace isn't recorded: {}",
+                dir.display(),
+                e
+            );
+            return None;
+        }
+        Some(dir.clone())
+    }
+
+    /// starts or stops the pending trace once a device recording into `dir` was created, `dir` is
+    /// the directory `pending_dir` returned
+    pub(crate) fn commit_pending(&mut self, dir: Option<PathBuf>) {
+        if self.pending.take().is_some() {
+            match (&self.recording, &dir) {
+                (Some(old), None) => info!("Stopped the wgpu trace in {}", old.display()),
+                (_, Some(new)) => info!("Recording a wgpu trace into {}", new.display()),
+                (None, None) => (),
+            }
+        }
+        if dir.is_none() {
+            self.frames_left = None;
+        }
+        self.recording = dir;
+    }
+}
@@ -86,6 +86,21 @@ where
         }
     }
 
+    /// can be updated with `update`, as long as the amount of indices stays the same
+    pub fn new_writable(device: &Device, data: &[T], label: Option<&str>) -> Self {
+        let buffer = GpuBuffer::new_with_data(
+            device,
+            data,
+            BufferUsages::INDEX | BufferUsages::COPY_DST,
+            label,
+        );
+
+        Self {
+            inner: buffer,
+            _index_type: PhantomData,
+        }
+    }
+
     pub fn index_format_static() -> IndexFormat {
         T::INDEX_FORMAT
     }
@@ -131,6 +146,21 @@ where
         }
     }
 
+    /// can be updated with `update`, as long as the amount of vertices stays the same
+    pub fn new_writable(device: &Device, data: &[T], label: Option<&str>) -> Self {
+        let buffer = GpuBuffer::new_with_data(
+            device,
+            data,
+            BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            label,
+        );
+
+        Self {
+            inner: buffer,
+            _vertex_type: PhantomData,
+        }
+    }
+
     pub fn vertex_layout() -> VertexBufferLayout<'static> {
         T::layout()
     }
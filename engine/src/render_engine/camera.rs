@@ -21,23 +21,13 @@ pub struct Camera {
     pub position: Vector2<f32>,
     pub height: f32,
     pub width: f32,
+    /// values above 1.0 zoom in, below 1.0 zoom out
+    pub zoom: f32,
 }
 
 impl Camera {
     pub fn new(device: &Device, height: f32, width: f32) -> Self {
-        let uniform = CameraUniform {
-            proj: Matrix4::identity().into(),
-        };
-
-        let buffer = GpuUniformBuffer::new(&device, &[uniform], Some("Camera UB"));
-        let bind_group = device.create_bind_group(&BindGroupDescriptor {
-            layout: &buffer.bind_group(&device, Some("Camera BGL")),
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
-            label: Some("Camera BG"),
-        });
+        let (buffer, bind_group) = create_uniform(device);
 
         Self {
             uniform_buf: buffer,
@@ -45,20 +35,34 @@ impl Camera {
             position: Vector2::new(0.0, 0.0),
             height,
             width,
+            zoom: 1.0,
         }
     }
 
+    /// creates the uniform buffer on a new device, the camera keeps its position and zoom
+    pub(crate) fn recreate(&mut self, device: &Device) {
+        let (buffer, bind_group) = create_uniform(device);
+        self.uniform_buf = buffer;
+        self.bind_group = bind_group;
+    }
+
     pub fn window_resize(&mut self, new_size: PhysicalSize<u32>) {
         self.height = new_size.height as f32;
         self.width = new_size.width as f32;
     }
 
+    /// the size of the visible area in world units
+    pub fn view_size(&self) -> Vector2<f32> {
+        Vector2::new(self.width, self.height) / self.zoom
+    }
+
     pub fn ortho_proj_matrix(&self) -> cgmath::Matrix4<f32> {
-        let half_height = self.height / 2.0;
+        let view_size = self.view_size();
+        let half_height = view_size.y / 2.0;
         let top = self.position.y + half_height;
         let bottom = self.position.y - half_height;
 
-        let half_width = self.width / 2.0;
+        let half_width = view_size.x / 2.0;
         let right = self.position.x + half_width;
         let left = self.position.x - half_width;
         cgmath::ortho(left, right, bottom, top, 0.0, 1000.0) * OPENGL_TO_WGPU_MATRIX
@@ -77,6 +81,16 @@ impl Camera {
         Vector2::new(result.x, result.y)
     }
 
+    /// the pixel a point in the world is drawn at, the inverse of `mouse_pos_to_world_space`
+    pub fn world_to_screen_space(&self, world_pos: Vector2<f32>) -> Vector2<f32> {
+        let view_size = self.view_size();
+        let offset = world_pos - self.position;
+        Vector2::new(
+            (offset.x / view_size.x + 0.5) * self.width,
+            (0.5 - offset.y / view_size.y) * self.height,
+        )
+    }
+
     pub fn update_uniform_buffer(&self, queue: &Queue) {
         puffin::profile_function!();
         let uniform = CameraUniform {
@@ -119,3 +133,20 @@ impl Uniform for CameraUniform {
         }
     }
 }
+
+fn create_uniform(device: &Device) -> (GpuUniformBuffer<CameraUniform>, BindGroup) {
+    let uniform = CameraUniform {
+        proj: Matrix4::identity().into(),
+    };
+
+    let buffer = GpuUniformBuffer::new(&device, &[uniform], Some("Camera UB"));
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+        layout: &buffer.bind_group(&device, Some("Camera BGL")),
+        entries: &[BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+        label: Some("Camera BG"),
+    });
+    (buffer, bind_group)
+}
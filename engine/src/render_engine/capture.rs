@@ -0,0 +1,376 @@
+use crate::asset_management::ToUuid;
+use crate::ecs::resources::KeyboardInput;
+use crate::scheduler::{Job, JobFrequency, JobScheduler};
+use anyhow::{bail, Result};
+use bevy_ecs::system::Resource;
+use bevy_ecs::world::World;
+use image::{ColorType, ImageFormat};
+use log::info;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wgpu::*;
+use winit::event::{ElementState, VirtualKeyCode};
+
+/// takes a screenshot without the debug UI, with shift held it's included
+pub const SCREENSHOT_KEY: VirtualKeyCode = VirtualKeyCode::F12;
+/// starts or stops recording a PNG sequence without the debug UI
+pub const RECORD_KEY: VirtualKeyCode = VirtualKeyCode::F9;
+pub const RECORDING_FPS: f64 = 60.0;
+const SCREENSHOT_DIR: &str = "./screenshots";
+const RECORDING_DIR: &str = "./recordings";
+
+/// Takes screenshots and records PNG sequences of the frame, the frame is copied into a buffer by
+/// the render graph and read back and encoded on the job scheduler
+///
+/// Surfaces can't be copied from on every backend, so a frame that's captured is drawn into a
+/// `CaptureTarget` instead, which is drawn into the surface after it's copied.
+#[derive(Resource, Default)]
+pub struct FrameCapture {
+    /// the path of every requested screenshot, and whether the debug UI is included
+    screenshots: Vec<(PathBuf, bool)>,
+    recording: Option<Recording>,
+    /// the copies made this frame, they're handed to the job scheduler after the frame is
+    /// submitted
+    pending: Vec<CaptureJob>,
+    target: Option<CaptureTarget>,
+}
+
+struct Recording {
+    dir: PathBuf,
+    timestep: Duration,
+    include_ui: bool,
+    frame: u64,
+}
+
+impl FrameCapture {
+    /// saves the next frame as a PNG at `path`
+    pub fn screenshot<P: Into<PathBuf>>(&mut self, path: P, include_ui: bool) {
+        self.screenshots.push((path.into(), include_ui));
+    }
+
+    /// saves every frame as a numbered PNG in `dir`, the simulation advances `1 / fps` seconds
+    /// every frame while recording, however long the frame took
+    pub fn start_recording<P: Into<PathBuf>>(
+        &mut self,
+        dir: P,
+        fps: f64,
+        include_ui: bool,
+    ) -> Result<()> {
+        if self.recording.is_some() {
+            bail!("Already recording");
+        }
+        if !fps.is_finite() || fps <= 0.0 {
+            bail!(
+                "The frame rate of a recording has to be positive, got {}",
+                fps
+            );
+        }
+
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        info!("Recording to {} at {} fps", dir.display(), fps);
+        self.recording = Some(Recording {
+            dir,
+            timestep: Duration::from_secs_f64(1.0 / fps),
+            include_ui,
+            frame: 0,
+        });
+        Ok(())
+    }
+
+    /// returns the amount of frames that were recorded
+    pub fn stop_recording(&mut self) -> Option<u64> {
+        let recording = self.recording.take()?;
+        info!(
+            "Recorded {} frames to {}",
+            recording.frame,
+            recording.dir.display()
+        );
+        Some(recording.frame)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// the simulated time between two frames while recording
+    pub fn timestep(&self) -> Option<Duration> {
+        self.recording.as_ref().map(|r| r.timestep)
+    }
+
+    /// the paths the frame is saved to at the point where the debug UI is or isn't drawn yet
+    pub(crate) fn take_paths(&mut self, include_ui: bool) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        self.screenshots.retain(|(path, ui)| {
+            if *ui == include_ui {
+                paths.push(path.clone());
+            }
+            *ui != include_ui
+        });
+
+        if let Some(recording) = self
+            .recording
+            .as_mut()
+            .filter(|r| r.include_ui == include_ui)
+        {
+            paths.push(
+                recording
+                    .dir
+                    .join(format!("frame-{:06}.png", recording.frame)),
+            );
+            recording.frame += 1;
+        }
+        paths
+    }
+
+    /// whether the next frame has to be drawn into the capture target
+    pub(crate) fn is_capturing(&self) -> bool {
+        !self.screenshots.is_empty() || self.recording.is_some()
+    }
+
+    /// returns the capture target, it's recreated when the size or format of the surface changed
+    pub(crate) fn prepare_target(
+        &mut self,
+        device: &Device,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+    ) -> &CaptureTarget {
+        match &mut self.target {
+            Some(target) if target.format == format => target.resize(device, width, height),
+            target => *target = Some(CaptureTarget::new(device, width, height, format)),
+        }
+        self.target.as_ref().unwrap()
+    }
+
+    pub(crate) fn target(&self) -> Option<&CaptureTarget> {
+        self.target.as_ref()
+    }
+
+    /// the target is only kept while capturing, and it belongs to the device it was created on
+    pub(crate) fn drop_target(&mut self) {
+        self.target = None;
+    }
+
+    pub(crate) fn push_pending(&mut self, job: CaptureJob) {
+        self.pending.push(job);
+    }
+
+    /// the copies of a frame that wasn't submitted can't be read back
+    pub(crate) fn drop_pending(&mut self) {
+        self.pending.clear();
+    }
+
+    /// has to be called after the copies are submitted, mapping a buffer before that fails
+    pub(crate) fn submit_pending(&mut self) {
+        for job in self.pending.drain(..) {
+            JobScheduler::submit(Box::new(job));
+        }
+    }
+}
+
+/// A texture in the surface format the frame is drawn into while it's captured
+pub(crate) struct CaptureTarget {
+    texture: Texture,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    bind_group_layout: BindGroupLayout,
+    bind_group: BindGroup,
+    pipeline: RenderPipeline,
+}
+
+impl CaptureTarget {
+    fn new(device: &Device, width: u32, height: u32, format: TextureFormat) -> Self {
+        let bind_group_layout =
+            crate::pipelines::blit::build_bind_group_layout(device, "Capture target");
+        let pipeline = crate::pipelines::blit::init(device, format, &bind_group_layout);
+        let (texture, bind_group) =
+            create_target(device, width, height, format, &bind_group_layout);
+
+        Self {
+            texture,
+            width,
+            height,
+            format,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        if (self.width, self.height) == (width, height) {
+            return;
+        }
+
+        let (texture, bind_group) =
+            create_target(device, width, height, self.format, &self.bind_group_layout);
+        self.texture = texture;
+        self.bind_group = bind_group;
+        self.width = width;
+        self.height = height;
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// draws the captured frame into `surface_view`
+    pub fn blit(&self, encoder: &mut CommandEncoder, surface_view: &TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Capture blit pass"),
+            color_attachments: &[RenderPassColorAttachment {
+                view: surface_view,
+                resolve_target: None,
+                ops: Operations {
+                    // every pixel is overwritten
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_target(
+    device: &Device,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    layout: &BindGroupLayout,
+) -> (Texture, BindGroup) {
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("Capture target TEX"),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format,
+        usage: TextureUsages::RENDER_ATTACHMENT
+            | TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_SRC,
+    });
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+        layout,
+        entries: &[BindGroupEntry {
+            binding: 0,
+            resource: BindingResource::TextureView(&view),
+        }],
+        label: Some("Capture target BG"),
+    });
+    (texture, bind_group)
+}
+
+/// reads back a copy of the frame and saves it as a PNG
+pub(crate) struct CaptureJob {
+    pub buffer: Buffer,
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// rows in the buffer are padded to `COPY_BYTES_PER_ROW_ALIGNMENT`
+    pub padded_row: u32,
+    pub format: TextureFormat,
+}
+
+impl ToUuid for CaptureJob {}
+
+impl Job for CaptureJob {
+    fn get_freq(&self) -> JobFrequency {
+        JobFrequency::Once
+    }
+
+    fn run(&mut self, device: &Device, _: &Queue) -> Result<()> {
+        let slice = self.buffer.slice(..);
+        let mapping = slice.map_async(MapMode::Read);
+        // only blocks this worker until the copy is done
+        device.poll(Maintain::Wait);
+        pollster::block_on(mapping)?;
+
+        let row = (self.width * 4) as usize;
+        let mut pixels = Vec::with_capacity(row * self.height as usize);
+        {
+            let data = slice.get_mapped_range();
+            for padded in data
+                .chunks(self.padded_row as usize)
+                .take(self.height as usize)
+            {
+                pixels.extend_from_slice(&padded[..row]);
+            }
+        }
+        self.buffer.unmap();
+
+        match self.format {
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+            }
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => (),
+            format => bail!("Can't save a frame in {:?} as a PNG", format),
+        }
+
+        image::save_buffer_with_format(
+            &self.path,
+            &pixels,
+            self.width,
+            self.height,
+            ColorType::Rgba8,
+            ImageFormat::Png,
+        )?;
+        info!("Saved the frame to {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// handles the screenshot and record hotkeys
+pub fn process_hotkey(world: &mut World, input: winit::event::KeyboardInput) {
+    if input.state != ElementState::Pressed {
+        return;
+    }
+
+    let shift = {
+        let keyboard = world.resource::<KeyboardInput>();
+        keyboard.is_pressed(&VirtualKeyCode::LShift) || keyboard.is_pressed(&VirtualKeyCode::RShift)
+    };
+    let mut capture = world.resource_mut::<FrameCapture>();
+    match input.virtual_keycode {
+        Some(SCREENSHOT_KEY) => {
+            let path =
+                PathBuf::from(SCREENSHOT_DIR).join(format!("screenshot-{}.png", timestamp()));
+            if let Err(e) = std::fs::create_dir_all(SCREENSHOT_DIR) {
+                log::error!("Failed to create {}: {}", SCREENSHOT_DIR, e);
+                return;
+            }
+            capture.screenshot(path, shift);
+        }
+        Some(RECORD_KEY) => {
+            if capture.is_recording() {
+                capture.stop_recording();
+            } else {
+                let dir = PathBuf::from(RECORDING_DIR).join(format!("recording-{}", timestamp()));
+                if let Err(e) = capture.start_recording(dir, RECORDING_FPS, false) {
+                    log::error!("Failed to start recording: {}", e);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+fn timestamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
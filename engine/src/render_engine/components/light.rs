@@ -0,0 +1,71 @@
+use bevy_ecs::component::Component;
+use cgmath::Vector2;
+
+/// A light that shines in all directions from the entity's `Position`
+#[derive(Component, Clone)]
+pub struct PointLight {
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// the distance (in world units) at which the light has fully faded out
+    pub radius: f32,
+    /// how far the light is above the sprite plane, used for normal mapping
+    pub height: f32,
+}
+
+impl Default for PointLight {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            intensity: 1.0,
+            radius: 300.0,
+            height: 50.0,
+        }
+    }
+}
+
+/// A cone shaped light, positioned at the entity's `Position`
+#[derive(Component, Clone)]
+pub struct SpotLight {
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub radius: f32,
+    pub height: f32,
+    pub direction: Vector2<f32>,
+    /// the angle (in radians) from the direction at which the light starts fading out
+    pub inner_angle: f32,
+    /// the angle (in radians) from the direction at which the light has fully faded out
+    pub outer_angle: f32,
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            intensity: 1.0,
+            radius: 300.0,
+            height: 50.0,
+            direction: Vector2::new(0.0, -1.0),
+            inner_angle: 0.35,
+            outer_angle: 0.5,
+        }
+    }
+}
+
+/// A light infinitely far away, like the sun, the entity's position is ignored
+#[derive(Component, Clone)]
+pub struct DirectionalLight {
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// the direction the light is travelling in, the z component points into the screen
+    pub direction: [f32; 3],
+}
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            intensity: 1.0,
+            direction: [0.0, -1.0, -1.0],
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+
+/// The area of a `SpriteMask` the sprites masked by it are visible in
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MaskShape {
+    /// the pixels of the mask's texture with an alpha above `cutoff`
+    Alpha { cutoff: f32 },
+    /// the whole rectangle of the sprite, when every mask a sprite is in is a rectangle it's
+    /// clipped with a scissor rect instead of the stencil
+    Rectangle,
+}
+
+/// Turns the sprite of the entity into a mask for the sprites that are `MaskedBy` it, the mask
+/// itself isn't drawn
+///
+/// A mask can be `MaskedBy` another mask, the sprites in the inner mask are then only visible
+/// where both masks overlap.
+#[derive(Component, Copy, Clone, Debug)]
+pub struct SpriteMask {
+    pub shape: MaskShape,
+}
+
+impl SpriteMask {
+    pub fn alpha(cutoff: f32) -> Self {
+        Self {
+            shape: MaskShape::Alpha { cutoff },
+        }
+    }
+
+    pub fn rectangle() -> Self {
+        Self {
+            shape: MaskShape::Rectangle,
+        }
+    }
+}
+
+impl Default for SpriteMask {
+    fn default() -> Self {
+        Self::alpha(0.5)
+    }
+}
+
+/// Only draws the sprite inside the `SpriteMask` of the entity, masked sprites are drawn over the
+/// other sprites, and aren't drawn at all when the mask is gone
+///
+/// Only plain sprites can be masked, masked nine-slices and palette swapped sprites aren't drawn.
+#[derive(Component, Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MaskedBy(pub Entity);
@@ -0,0 +1,63 @@
+use crate::render_engine::buffer::{GpuIndexBuffer, GpuVertexBuffer};
+use crate::render_engine::mesh::MeshData;
+use crate::render_engine::vertex::MeshVertex;
+use bevy_ecs::component::Component;
+use wgpu::{Device, Queue};
+
+/// A mesh drawn at the entity's `Position`, textured with the entity's `Texture`
+///
+/// A copy of the mesh is kept on the CPU, so the buffers can be created again on a new device.
+#[derive(Component)]
+pub struct Mesh2D {
+    data: MeshData,
+    vertices: GpuVertexBuffer<MeshVertex>,
+    indices: GpuIndexBuffer<u32>,
+}
+
+impl Mesh2D {
+    pub fn new(device: &Device, data: &MeshData) -> Self {
+        Self {
+            data: data.clone(),
+            vertices: GpuVertexBuffer::new_writable(device, &data.vertices, Some("Mesh2D VB")),
+            indices: GpuIndexBuffer::new_writable(device, &data.indices, Some("Mesh2D IB")),
+        }
+    }
+
+    /// replaces the mesh, the buffers are only recreated when the amount of vertices or
+    /// indices changed, so deforming a mesh every frame is cheap
+    pub fn update(&mut self, device: &Device, queue: &Queue, data: &MeshData) {
+        // clone_from reuses the allocations of the old mesh
+        self.data.vertices.clone_from(&data.vertices);
+        self.data.indices.clone_from(&data.indices);
+
+        if self.vertices.data_count() as usize == data.vertices.len() {
+            self.vertices.update(queue, &data.vertices);
+        } else {
+            self.vertices =
+                GpuVertexBuffer::new_writable(device, &data.vertices, Some("Mesh2D VB"));
+        }
+
+        if self.indices.data_count() as usize == data.indices.len() {
+            self.indices.update(queue, &data.indices);
+        } else {
+            self.indices = GpuIndexBuffer::new_writable(device, &data.indices, Some("Mesh2D IB"));
+        }
+    }
+
+    /// creates the buffers on a new device from the copy of the mesh
+    pub(crate) fn recreate(&mut self, device: &Device) {
+        *self = Self::new(device, &self.data);
+    }
+
+    pub fn data(&self) -> &MeshData {
+        &self.data
+    }
+
+    pub fn vertex_buffer(&self) -> &GpuVertexBuffer<MeshVertex> {
+        &self.vertices
+    }
+
+    pub fn index_buffer(&self) -> &GpuIndexBuffer<u32> {
+        &self.indices
+    }
+}
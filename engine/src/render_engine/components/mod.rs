@@ -1,2 +1,14 @@
+pub mod light;
+pub mod mask;
+pub mod mesh;
+pub mod nine_slice;
+pub mod normal_map;
+pub mod occluder;
+pub mod palette_swap;
+pub mod parallax;
+pub mod particle_emitter;
 pub mod position;
+pub mod skeleton;
+pub mod static_sprite;
 pub mod texture;
+pub mod trail;
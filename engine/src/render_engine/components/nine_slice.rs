@@ -0,0 +1,211 @@
+use crate::render_engine::vertex::SpriteInstance;
+use bevy_ecs::component::Component;
+use cgmath::{Vector2, Vector3};
+use serde::Deserialize;
+
+/// The size of the borders of a nine-slice texture, in texture pixels
+#[derive(Deserialize, Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct NineSliceInsets {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+}
+
+impl NineSliceInsets {
+    pub fn uniform(inset: u32) -> Self {
+        Self {
+            left: inset,
+            right: inset,
+            top: inset,
+            bottom: inset,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SliceMode {
+    Stretch,
+    Tile,
+}
+
+/// Draws the entity's `Texture` as a panel of `size` world units, where the corners keep their
+/// size and the edges and centre are stretched or tiled to fill the rest
+#[derive(Component, Clone)]
+pub struct NineSlice {
+    pub size: Vector2<f32>,
+    /// when `None`, the insets from the texture's metadata are used
+    pub insets: Option<NineSliceInsets>,
+    pub edge_mode: SliceMode,
+    pub center_mode: SliceMode,
+    /// world units per texture pixel, used for the borders and the size of tiles
+    pub scale: f32,
+}
+
+impl NineSlice {
+    pub fn new(size: Vector2<f32>) -> Self {
+        Self {
+            size,
+            insets: None,
+            edge_mode: SliceMode::Stretch,
+            center_mode: SliceMode::Stretch,
+            scale: 1.0,
+        }
+    }
+
+    pub fn with_insets(mut self, insets: NineSliceInsets) -> Self {
+        self.insets = Some(insets);
+        self
+    }
+
+    pub fn tiled(mut self) -> Self {
+        self.edge_mode = SliceMode::Tile;
+        self.center_mode = SliceMode::Tile;
+        self
+    }
+
+    /// generates the quads for a texture of `texture_size` pixels, centered on `position`
+    pub(crate) fn instances(
+        &self,
+        position: Vector3<f32>,
+        insets: NineSliceInsets,
+        texture_size: (u32, u32),
+    ) -> Vec<SpriteInstance> {
+        let (tex_w, tex_h) = (texture_size.0 as f32, texture_size.1 as f32);
+        // the top row comes first, as v goes down the texture
+        let columns = slice_axis(
+            self.size.x,
+            [insets.left as f32, insets.right as f32],
+            tex_w,
+            self.scale,
+        );
+        let rows = slice_axis(
+            self.size.y,
+            [insets.top as f32, insets.bottom as f32],
+            tex_h,
+            self.scale,
+        );
+
+        let left = position.x - self.size.x / 2.0;
+        let top = position.y + self.size.y / 2.0;
+        let mut instances = Vec::new();
+
+        for (row, row_slice) in rows.iter().enumerate() {
+            for (column, column_slice) in columns.iter().enumerate() {
+                let mode = match (row, column) {
+                    (1, 1) => self.center_mode,
+                    (1, _) | (_, 1) => self.edge_mode,
+                    _ => SliceMode::Stretch,
+                };
+
+                // corners are never tiled, edges only along their length
+                let tile_x = mode == SliceMode::Tile && column == 1;
+                let tile_y = mode == SliceMode::Tile && row == 1;
+
+                for x in column_slice.segments(tile_x) {
+                    for y in row_slice.segments(tile_y) {
+                        if x.dest_len <= 0.0 || y.dest_len <= 0.0 {
+                            continue;
+                        }
+
+                        instances.push(SpriteInstance {
+                            position: [
+                                left + x.dest_start + x.dest_len / 2.0,
+                                top - y.dest_start - y.dest_len / 2.0,
+                                position.z,
+                                0.0,
+                            ],
+                            size: [x.dest_len, y.dest_len, 0.0, 0.0],
+                            color: [1.0; 4],
+                            // the square's u coordinate goes from right to left, so flip it
+                            uv_rect: [
+                                (x.src_start + x.src_len) / tex_w,
+                                y.src_start / tex_h,
+                                -x.src_len / tex_w,
+                                y.src_len / tex_h,
+                            ],
+                        });
+                    }
+                }
+            }
+        }
+
+        instances
+    }
+}
+
+/// A slice along one axis, in world units for dest and texture pixels for src
+#[derive(Copy, Clone, Debug)]
+struct Slice {
+    dest_start: f32,
+    dest_len: f32,
+    src_start: f32,
+    src_len: f32,
+    /// world units per texture pixel when tiling
+    scale: f32,
+}
+
+impl Slice {
+    fn segments(&self, tile: bool) -> Vec<Slice> {
+        let tile_len = self.src_len * self.scale;
+        if !tile || tile_len <= 0.0 {
+            return vec![*self];
+        }
+
+        let mut segments = Vec::new();
+        let mut offset = 0.0;
+        while offset < self.dest_len {
+            // the last tile is cut off instead of squashed
+            let fraction = ((self.dest_len - offset) / tile_len).min(1.0);
+            segments.push(Slice {
+                dest_start: self.dest_start + offset,
+                dest_len: tile_len * fraction,
+                src_start: self.src_start,
+                src_len: self.src_len * fraction,
+                scale: self.scale,
+            });
+            offset += tile_len;
+        }
+
+        segments
+    }
+}
+
+/// splits `size` into the two borders and the middle, shrinking the borders if they don't fit
+fn slice_axis(size: f32, insets: [f32; 2], texture_len: f32, scale: f32) -> [Slice; 3] {
+    let border = (insets[0] + insets[1]) * scale;
+    let shrink = if border > size && border > 0.0 {
+        size / border
+    } else {
+        1.0
+    };
+
+    let start = insets[0] * scale * shrink;
+    let end = insets[1] * scale * shrink;
+    let middle = (size - start - end).max(0.0);
+    let src_middle = (texture_len - insets[0] - insets[1]).max(0.0);
+
+    [
+        Slice {
+            dest_start: 0.0,
+            dest_len: start,
+            src_start: 0.0,
+            src_len: insets[0],
+            scale,
+        },
+        Slice {
+            dest_start: start,
+            dest_len: middle,
+            src_start: insets[0],
+            src_len: src_middle,
+            scale,
+        },
+        Slice {
+            dest_start: start + middle,
+            dest_len: end,
+            src_start: texture_len - insets[1],
+            src_len: insets[1],
+            scale,
+        },
+    ]
+}
@@ -0,0 +1,6 @@
+use crate::asset_management::GpuTextureRef;
+use bevy_ecs::component::Component;
+
+/// A tangent-space normal map, drawn with the same size and position as the entity's `Texture`
+#[derive(Component)]
+pub struct NormalMap(pub GpuTextureRef);
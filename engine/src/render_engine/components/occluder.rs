@@ -0,0 +1,45 @@
+use bevy_ecs::component::Component;
+use cgmath::Vector2;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ShadowMode {
+    Hard,
+    Soft,
+}
+
+/// A rectangle, centered on the entity's `Position`, that blocks light
+#[derive(Component, Clone)]
+pub struct Occluder {
+    pub half_extents: Vector2<f32>,
+    pub mode: ShadowMode,
+    /// the width (in world units) of the penumbra, only used with `ShadowMode::Soft`
+    pub softness: f32,
+}
+
+impl Occluder {
+    pub fn hard(half_extents: Vector2<f32>) -> Self {
+        Self {
+            half_extents,
+            mode: ShadowMode::Hard,
+            softness: 0.0,
+        }
+    }
+
+    pub fn soft(half_extents: Vector2<f32>, softness: f32) -> Self {
+        Self {
+            half_extents,
+            mode: ShadowMode::Soft,
+            softness,
+        }
+    }
+
+    /// returns the four edges of the occluder in world space
+    pub fn edges(&self, position: Vector2<f32>) -> [[Vector2<f32>; 2]; 4] {
+        let (hx, hy) = (self.half_extents.x, self.half_extents.y);
+        let tl = position + Vector2::new(-hx, hy);
+        let tr = position + Vector2::new(hx, hy);
+        let br = position + Vector2::new(hx, -hy);
+        let bl = position + Vector2::new(-hx, -hy);
+        [[tl, tr], [tr, br], [br, bl], [bl, tl]]
+    }
+}
@@ -0,0 +1,26 @@
+use bevy_ecs::component::Component;
+
+/// Draws the entity's `Texture` with the colors of a palette, the texture has to be imported
+/// with `indexed` in its metadata
+///
+/// A palette is a texture in the `Palettes` resource where every row is a palette, the index of a
+/// pixel is the column of its color. The alpha of the color is the alpha of the pixel, so index 0
+/// is usually transparent. Palette swapped sprites that are `MaskedBy` a mask aren't drawn, as
+/// masks don't apply to palettes yet, and a `NormalMap` covers the whole sprite, as the index map
+/// has no alpha.
+#[derive(Component, Clone, Debug, Eq, PartialEq)]
+pub struct PaletteSwap {
+    /// the name of the palette in `Palettes`, the sprite isn't drawn while there's none
+    pub palette: String,
+    /// rows past the last row of the palette use the last row
+    pub row: u32,
+}
+
+impl PaletteSwap {
+    pub fn new(palette: &str, row: u32) -> Self {
+        Self {
+            palette: palette.to_string(),
+            row,
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::render_engine::camera::Camera;
+use crate::render_engine::vertex::SpriteInstance;
+use bevy_ecs::component::Component;
+use cgmath::{Vector2, Vector3};
+
+/// more tiles than this on one axis are skipped, it only happens with tiny tiles or extreme zoom
+const MAX_TILES_PER_AXIS: i32 = 256;
+
+/// Draws the entity's `Texture` as a background that moves slower (or faster) than the camera,
+/// layers are drawn below everything else, the ones with the smallest scroll factor first
+#[derive(Component, Clone, Debug)]
+pub struct ParallaxLayer {
+    /// how much the layer follows the world when the camera moves, 0.0 keeps it fixed on the
+    /// screen and 1.0 moves it like any other entity
+    pub scroll_factor: Vector2<f32>,
+    pub repeat_x: bool,
+    pub repeat_y: bool,
+    /// world units per second the layer scrolls by itself, for clouds or water
+    pub velocity: Vector2<f32>,
+    /// world units per texture pixel
+    pub scale: f32,
+    pub color: [f32; 4],
+    /// how far the layer has scrolled because of the velocity
+    pub(crate) scroll: Vector2<f32>,
+}
+
+impl ParallaxLayer {
+    pub fn new(scroll_factor: Vector2<f32>) -> Self {
+        Self {
+            scroll_factor,
+            repeat_x: false,
+            repeat_y: false,
+            velocity: Vector2::new(0.0, 0.0),
+            scale: 1.0,
+            color: [1.0; 4],
+            scroll: Vector2::new(0.0, 0.0),
+        }
+    }
+
+    /// tiles the texture infinitely along the enabled axes
+    pub fn repeat(mut self, x: bool, y: bool) -> Self {
+        self.repeat_x = x;
+        self.repeat_y = y;
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: Vector2<f32>) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// moves the layer by its velocity, the scroll wraps around on repeating axes so it never
+    /// grows large enough to lose precision
+    pub(crate) fn advance(&mut self, dt: f32, texture_size: (u32, u32)) {
+        self.scroll += self.velocity * dt;
+
+        let tile = self.tile_size(texture_size);
+        if self.repeat_x && tile.x > 0.0 {
+            self.scroll.x = self.scroll.x.rem_euclid(tile.x);
+        }
+        if self.repeat_y && tile.y > 0.0 {
+            self.scroll.y = self.scroll.y.rem_euclid(tile.y);
+        }
+    }
+
+    fn tile_size(&self, texture_size: (u32, u32)) -> Vector2<f32> {
+        Vector2::new(texture_size.0 as f32, texture_size.1 as f32) * self.scale
+    }
+
+    /// generates the quads covering the visible part of the layer, `position` is where the
+    /// center of the texture is when the camera is at the origin
+    pub(crate) fn instances(
+        &self,
+        position: Vector3<f32>,
+        camera: &Camera,
+        texture_size: (u32, u32),
+    ) -> Vec<SpriteInstance> {
+        let tile = self.tile_size(texture_size);
+        if tile.x <= 0.0 || tile.y <= 0.0 {
+            return Vec::new();
+        }
+
+        // the part of the camera movement the layer doesn't follow is added to its position
+        let follow = Vector2::new(
+            camera.position.x * (1.0 - self.scroll_factor.x),
+            camera.position.y * (1.0 - self.scroll_factor.y),
+        );
+        let center = position.truncate() + follow + self.scroll;
+
+        let view_half = camera.view_size() / 2.0;
+        let view_min = camera.position - view_half;
+        let view_max = camera.position + view_half;
+
+        let columns = tile_range(self.repeat_x, center.x, tile.x, view_min.x, view_max.x);
+        let rows = tile_range(self.repeat_y, center.y, tile.y, view_min.y, view_max.y);
+
+        let mut instances = Vec::new();
+        for column in columns {
+            for row in rows.clone() {
+                instances.push(SpriteInstance {
+                    position: [
+                        center.x + column as f32 * tile.x,
+                        center.y + row as f32 * tile.y,
+                        position.z,
+                        0.0,
+                    ],
+                    size: [tile.x, tile.y, 0.0, 0.0],
+                    color: self.color,
+                    // the square's u coordinate goes from right to left, so flip it
+                    uv_rect: [1.0, 0.0, -1.0, 1.0],
+                });
+            }
+        }
+
+        instances
+    }
+}
+
+/// the tiles that overlap the view along one axis, relative to the tile at `center`
+fn tile_range(
+    repeat: bool,
+    center: f32,
+    tile: f32,
+    view_min: f32,
+    view_max: f32,
+) -> std::ops::Range<i32> {
+    if !repeat {
+        return 0..1;
+    }
+
+    let first = ((view_min - center) / tile + 0.5).floor() as i32;
+    let last = ((view_max - center) / tile + 0.5).ceil() as i32;
+    first..last.min(first + MAX_TILES_PER_AXIS)
+}
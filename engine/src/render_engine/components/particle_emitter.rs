@@ -0,0 +1,222 @@
+use crate::asset_management::GpuTextureRef;
+use crate::render_engine::particles::GpuParticle;
+use bevy_ecs::component::Component;
+use cgmath::{Vector2, Vector3};
+
+/// A piecewise linear curve over the lifetime of a particle, `t` goes from 0.0 to 1.0
+#[derive(Clone, Debug)]
+pub struct Curve {
+    keys: Vec<(f32, f32)>,
+}
+
+impl Curve {
+    /// the keys don't have to be sorted, but there has to be at least one
+    pub fn new(mut keys: Vec<(f32, f32)>) -> Self {
+        assert!(!keys.is_empty(), "A curve needs at least one key");
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Self { keys }
+    }
+
+    pub fn constant(value: f32) -> Self {
+        Self::new(vec![(0.0, value)])
+    }
+
+    pub fn linear(start: f32, end: f32) -> Self {
+        Self::new(vec![(0.0, start), (1.0, end)])
+    }
+
+    pub fn sample(&self, t: f32) -> f32 {
+        sample_keys(&self.keys, t, |a, b, f| a + (b - a) * f)
+    }
+}
+
+/// A piecewise linear RGBA gradient over the lifetime of a particle
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    keys: Vec<(f32, [f32; 4])>,
+}
+
+impl Gradient {
+    pub fn new(mut keys: Vec<(f32, [f32; 4])>) -> Self {
+        assert!(!keys.is_empty(), "A gradient needs at least one key");
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Self { keys }
+    }
+
+    pub fn constant(color: [f32; 4]) -> Self {
+        Self::new(vec![(0.0, color)])
+    }
+
+    pub fn linear(start: [f32; 4], end: [f32; 4]) -> Self {
+        Self::new(vec![(0.0, start), (1.0, end)])
+    }
+
+    pub fn sample(&self, t: f32) -> [f32; 4] {
+        sample_keys(&self.keys, t, |a, b, f| {
+            let mut out = [0.0; 4];
+            for i in 0..4 {
+                out[i] = a[i] + (b[i] - a[i]) * f;
+            }
+            out
+        })
+    }
+}
+
+fn sample_keys<T: Copy, F: Fn(T, T, f32) -> T>(keys: &[(f32, T)], t: f32, lerp: F) -> T {
+    let first = keys[0];
+    if t <= first.0 {
+        return first.1;
+    }
+
+    for pair in keys.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if t <= b.0 {
+            let f = (t - a.0) / (b.0 - a.0).max(f32::EPSILON);
+            return lerp(a.1, b.1, f);
+        }
+    }
+
+    keys[keys.len() - 1].1
+}
+
+/// Spawns `count` particles at once, `time` seconds after the emitter started (or looped)
+#[derive(Copy, Clone, Debug)]
+pub struct Burst {
+    pub time: f32,
+    pub count: u32,
+}
+
+/// Plays the frames of a sprite sheet (left to right, top to bottom) over a particle's life
+#[derive(Copy, Clone, Debug)]
+pub struct SpriteSheetAnimation {
+    pub columns: u32,
+    pub rows: u32,
+    pub frame_count: u32,
+    pub fps: f32,
+}
+
+#[derive(Component)]
+pub struct ParticleEmitter {
+    /// the maximum amount of particles alive at once, the oldest particles get replaced when exceeded
+    pub max_particles: u32,
+    /// particles spawned per second
+    pub spawn_rate: f32,
+    pub bursts: Vec<Burst>,
+    /// the emitter loops after this many seconds, re-triggering its bursts
+    pub duration: Option<f32>,
+    /// the minimum and maximum lifetime of a particle, in seconds
+    pub lifetime: (f32, f32),
+    /// the initial velocity is picked at random between these two
+    pub velocity: (Vector2<f32>, Vector2<f32>),
+    pub acceleration: Vector2<f32>,
+    pub color_over_lifetime: Gradient,
+    /// the size of a particle in world units
+    pub size_over_lifetime: Curve,
+    pub texture: GpuTextureRef,
+    pub animation: Option<SpriteSheetAnimation>,
+    state: EmitterState,
+}
+
+struct EmitterState {
+    elapsed: f32,
+    spawn_accumulator: f32,
+    next_burst: usize,
+    cursor: u32,
+    rng: u32,
+}
+
+impl ParticleEmitter {
+    pub fn new(texture: GpuTextureRef) -> Self {
+        Self {
+            max_particles: 256,
+            spawn_rate: 20.0,
+            bursts: Vec::new(),
+            duration: None,
+            lifetime: (1.0, 2.0),
+            velocity: (Vector2::new(-50.0, 50.0), Vector2::new(50.0, 150.0)),
+            acceleration: Vector2::new(0.0, -100.0),
+            color_over_lifetime: Gradient::linear([1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 0.0]),
+            size_over_lifetime: Curve::constant(16.0),
+            texture,
+            animation: None,
+            state: EmitterState {
+                elapsed: 0.0,
+                spawn_accumulator: 0.0,
+                next_burst: 0,
+                cursor: 0,
+                rng: 0x9E3779B9,
+            },
+        }
+    }
+
+    /// advances the emitter by `dt` seconds, returns the particles to spawn and the slot they go in
+    pub(crate) fn spawn(&mut self, origin: Vector3<f32>, dt: f32) -> Vec<(u32, GpuParticle)> {
+        let mut count = 0;
+
+        self.state.spawn_accumulator += self.spawn_rate * dt;
+        count += self.state.spawn_accumulator.floor() as u32;
+        self.state.spawn_accumulator = self.state.spawn_accumulator.fract();
+
+        self.state.elapsed += dt;
+        while let Some(burst) = self.bursts.get(self.state.next_burst) {
+            if burst.time > self.state.elapsed {
+                break;
+            }
+            count += burst.count;
+            self.state.next_burst += 1;
+        }
+
+        if let Some(duration) = self.duration {
+            if self.state.elapsed >= duration {
+                self.state.elapsed -= duration;
+                self.state.next_burst = 0;
+            }
+        }
+
+        // spawning more than the capacity would just overwrite the particles we spawn this frame
+        let count = count.min(self.max_particles);
+        let mut spawned = Vec::with_capacity(count as usize);
+        // max_particles can be lowered after the cursor went past it
+        let capacity = self.max_particles.max(1);
+        self.state.cursor %= capacity;
+
+        for _ in 0..count {
+            let lifetime = lerp(self.lifetime.0, self.lifetime.1, self.random());
+            let velocity = Vector2::new(
+                lerp(self.velocity.0.x, self.velocity.1.x, self.random()),
+                lerp(self.velocity.0.y, self.velocity.1.y, self.random()),
+            );
+
+            let slot = self.state.cursor;
+            self.state.cursor = (self.state.cursor + 1) % capacity;
+            spawned.push((
+                slot,
+                GpuParticle {
+                    position: [origin.x, origin.y, 0.0, lifetime],
+                    velocity: [velocity.x, velocity.y, origin.z, 1.0],
+                },
+            ));
+        }
+
+        spawned
+    }
+
+    /// the buffers were recreated, so the particles are spawned from the first slot again
+    pub(crate) fn reset_cursor(&mut self) {
+        self.state.cursor = 0;
+    }
+
+    /// xorshift, returns a value between 0.0 and 1.0
+    fn random(&mut self) -> f32 {
+        let mut x = self.state.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state.rng = x;
+        (x as f64 / u32::MAX as f64) as f32
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
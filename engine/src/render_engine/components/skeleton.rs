@@ -0,0 +1,184 @@
+use crate::asset_management::{AssetLoader, GpuTextureRef};
+use crate::render_engine::mesh::MeshData;
+use crate::render_engine::skeleton::pose::Pose;
+use crate::render_engine::skeleton::{SkeletonData, DEFAULT_SKIN};
+use anyhow::{bail, Result};
+use bevy_ecs::component::Component;
+use std::sync::Arc;
+
+/// An animation playing on a skeleton
+#[derive(Clone, Debug)]
+pub struct AnimationTrack {
+    pub animation: String,
+    /// seconds since the animation started
+    pub time: f32,
+    pub speed: f32,
+    pub looping: bool,
+    /// how much of the animation is mixed into the tracks before it, from 0 to 1
+    pub weight: f32,
+    /// the weight added per second while crossfading in
+    fade: f32,
+}
+
+/// A skeleton at the entity's `Position`, `ecs_update_skeletons` animates it and gives the
+/// entity a `Mesh2D` and `Texture` so it's drawn by the mesh pipeline
+#[derive(Component)]
+pub struct Skeleton {
+    data: Arc<SkeletonData>,
+    texture: GpuTextureRef,
+    skin: String,
+    /// multiplied with the color of every attachment
+    pub tint: [f32; 4],
+    tracks: Vec<AnimationTrack>,
+}
+
+impl Skeleton {
+    pub fn new(data: Arc<SkeletonData>, texture: GpuTextureRef) -> Self {
+        Self {
+            data,
+            texture,
+            skin: DEFAULT_SKIN.to_string(),
+            tint: [1.0; 4],
+            tracks: Vec::new(),
+        }
+    }
+
+    /// loads a Spine export, together with the texture its atlas refers to
+    pub fn load(json_id: &str, atlas_id: &str) -> Result<Self> {
+        let data = SkeletonData::load_spine(json_id, atlas_id)?;
+        let texture = AssetLoader::load_texture(&data.atlas_page)?;
+        Ok(Self::new(Arc::new(data), texture))
+    }
+
+    pub fn data(&self) -> &Arc<SkeletonData> {
+        &self.data
+    }
+
+    pub fn texture(&self) -> &GpuTextureRef {
+        &self.texture
+    }
+
+    pub fn skin(&self) -> &str {
+        &self.skin
+    }
+
+    pub fn set_skin(&mut self, skin: &str) -> Result<()> {
+        if !self.data.skins.contains_key(skin) {
+            bail!("Skeleton doesn't have skin {}", skin);
+        }
+        self.skin = skin.to_string();
+        Ok(())
+    }
+
+    /// stops all other animations and plays `animation` from the start
+    pub fn play(&mut self, animation: &str, looping: bool) -> Result<()> {
+        let track = self.track(animation, looping, 1.0)?;
+        self.tracks.clear();
+        self.tracks.push(track);
+        Ok(())
+    }
+
+    /// fades `animation` in over `duration` seconds, the other animations are stopped once
+    /// it's fully faded in
+    pub fn crossfade(&mut self, animation: &str, looping: bool, duration: f32) -> Result<()> {
+        if duration <= 0.0 {
+            return self.play(animation, looping);
+        }
+
+        let mut track = self.track(animation, looping, 0.0)?;
+        track.fade = 1.0 / duration;
+        self.tracks.push(track);
+        Ok(())
+    }
+
+    /// plays `animation` on top of the others, mixed in with `weight`
+    pub fn add_layer(&mut self, animation: &str, looping: bool, weight: f32) -> Result<()> {
+        let track = self.track(animation, looping, weight)?;
+        self.tracks.push(track);
+        Ok(())
+    }
+
+    /// the tracks are applied in order, later tracks override the earlier ones by their weight
+    pub fn tracks(&self) -> &[AnimationTrack] {
+        &self.tracks
+    }
+
+    pub fn tracks_mut(&mut self) -> &mut Vec<AnimationTrack> {
+        &mut self.tracks
+    }
+
+    /// true while any of the animations that don't loop hasn't finished yet
+    pub fn is_playing(&self) -> bool {
+        self.tracks.iter().any(|t| {
+            t.looping
+                || self
+                    .data
+                    .animation(&t.animation)
+                    .map_or(false, |a| t.time < a.duration)
+        })
+    }
+
+    fn track(&self, animation: &str, looping: bool, weight: f32) -> Result<AnimationTrack> {
+        if self.data.animation(animation).is_none() {
+            bail!("Skeleton doesn't have animation {}", animation);
+        }
+
+        Ok(AnimationTrack {
+            animation: animation.to_string(),
+            time: 0.0,
+            speed: 1.0,
+            looping,
+            weight,
+            fade: 0.0,
+        })
+    }
+
+    pub(crate) fn advance(&mut self, dt: f32) {
+        let mut faded_in = None;
+        for (i, track) in self.tracks.iter_mut().enumerate() {
+            track.time += dt * track.speed;
+            if track.fade > 0.0 {
+                track.weight = (track.weight + track.fade * dt).min(1.0);
+                if track.weight >= 1.0 {
+                    track.fade = 0.0;
+                    faded_in = Some(i);
+                }
+            }
+        }
+
+        // the tracks below a crossfade that finished are completely hidden by it
+        if let Some(i) = faded_in {
+            self.tracks.drain(..i);
+        }
+    }
+
+    pub(crate) fn pose(&self) -> Pose {
+        let mut pose = Pose::setup(&self.data);
+
+        for track in self.tracks.iter() {
+            let animation = match self.data.animation(&track.animation) {
+                Some(animation) => animation,
+                None => continue,
+            };
+
+            let time = if track.looping && animation.duration > 0.0 {
+                track.time.rem_euclid(animation.duration)
+            } else {
+                track.time.min(animation.duration)
+            };
+            animation.apply(
+                &self.data,
+                &mut pose,
+                time,
+                track.weight.clamp(0.0, 1.0),
+                &self.skin,
+            );
+        }
+
+        pose
+    }
+
+    pub(crate) fn build_mesh(&self) -> MeshData {
+        self.pose().build_mesh(&self.data, &self.skin, self.tint)
+    }
+}
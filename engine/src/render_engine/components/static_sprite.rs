@@ -0,0 +1,6 @@
+use bevy_ecs::component::Component;
+
+/// Marks a sprite that rarely changes, it's drawn from a cached render bundle instead of being
+/// encoded every frame, changing its `Position` or `Texture` re-records the bundle it's in
+#[derive(Component, Default, Copy, Clone, Debug)]
+pub struct Static;
@@ -0,0 +1,196 @@
+use crate::asset_management::GpuTextureRef;
+use crate::render_engine::components::mesh::Mesh2D;
+use crate::render_engine::mesh::MeshData;
+use crate::render_engine::vertex::MeshVertex;
+use bevy_ecs::component::Component;
+use cgmath::{InnerSpace, Vector2};
+use std::collections::VecDeque;
+
+/// the most points a trail keeps, the oldest are dropped first
+const MAX_TRAIL_POINTS: usize = 256;
+
+/// How much of the path of the entity a `Trail` covers
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TrailLength {
+    /// the positions of the last this many seconds, the trail shrinks when the entity stops
+    Time(f32),
+    /// this many world units behind the entity, the trail stays when the entity stops
+    Distance(f32),
+}
+
+/// Records the recent positions of the entity and draws a textured ribbon through them,
+/// `ecs_update_trails` records the positions and builds the mesh every frame
+///
+/// The texture is stretched along the trail, u goes from the head to the tail and v across it.
+/// The width and color go from the head values at the entity to the tail values at the end.
+#[derive(Component)]
+pub struct Trail {
+    pub texture: GpuTextureRef,
+    pub length: TrailLength,
+    pub head_width: f32,
+    pub tail_width: f32,
+    pub head_color: [f32; 4],
+    pub tail_color: [f32; 4],
+    /// a position is only recorded once the entity moved this far from the last one
+    pub min_distance: f32,
+    /// the recorded positions and how many seconds ago they were recorded, the newest first
+    points: VecDeque<(Vector2<f32>, f32)>,
+    mesh: Option<Mesh2D>,
+}
+
+impl Trail {
+    pub fn new(texture: GpuTextureRef, length: TrailLength) -> Self {
+        Self {
+            texture,
+            length,
+            head_width: 20.0,
+            tail_width: 0.0,
+            head_color: [1.0; 4],
+            tail_color: [1.0, 1.0, 1.0, 0.0],
+            min_distance: 2.0,
+            points: VecDeque::new(),
+            mesh: None,
+        }
+    }
+
+    pub fn with_width(mut self, head: f32, tail: f32) -> Self {
+        self.head_width = head;
+        self.tail_width = tail;
+        self
+    }
+
+    pub fn with_color(mut self, head: [f32; 4], tail: [f32; 4]) -> Self {
+        self.head_color = head;
+        self.tail_color = tail;
+        self
+    }
+
+    /// forgets the recorded positions, so teleporting the entity doesn't draw a line
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// `None` while the trail has nothing to draw
+    pub fn mesh(&self) -> Option<&Mesh2D> {
+        self.mesh.as_ref()
+    }
+
+    pub(crate) fn mesh_mut(&mut self) -> &mut Option<Mesh2D> {
+        &mut self.mesh
+    }
+
+    /// ages the recorded positions by `dt` seconds and records `position` if the entity moved
+    pub(crate) fn record(&mut self, position: Vector2<f32>, dt: f32) {
+        for (_, age) in self.points.iter_mut() {
+            *age += dt;
+        }
+
+        let moved = self.points.front().map_or(true, |(last, _)| {
+            (position - last).magnitude() >= self.min_distance
+        });
+        if moved {
+            self.points.push_front((position, 0.0));
+        }
+
+        match self.length {
+            TrailLength::Time(seconds) => {
+                while matches!(self.points.back(), Some((_, age)) if *age > seconds) {
+                    self.points.pop_back();
+                }
+            }
+            TrailLength::Distance(max) => {
+                // the first point past the length is kept, the mesh is cut off inside its segment
+                let mut distance = 0.0;
+                let mut keep = self.points.len();
+                for i in 1..self.points.len() {
+                    distance += (self.points[i].0 - self.points[i - 1].0).magnitude();
+                    if distance >= max {
+                        keep = i + 1;
+                        break;
+                    }
+                }
+                self.points.truncate(keep);
+            }
+        }
+        self.points.truncate(MAX_TRAIL_POINTS);
+    }
+
+    /// the ribbon from the entity at `head` through the recorded positions, in world space
+    pub(crate) fn build_mesh(&self, head: Vector2<f32>) -> MeshData {
+        let mut points: Vec<Vector2<f32>> = Vec::with_capacity(self.points.len() + 1);
+        points.push(head);
+        for (point, _) in self.points.iter() {
+            // the newest point is usually where the entity still is
+            if (point - points[points.len() - 1]).magnitude() > f32::EPSILON {
+                points.push(*point);
+            }
+        }
+
+        let mut distances = vec![0.0];
+        for i in 1..points.len() {
+            let segment = (points[i] - points[i - 1]).magnitude();
+            distances.push(distances[i - 1] + segment);
+        }
+
+        if let TrailLength::Distance(max) = self.length {
+            if let Some(past) = distances.iter().skip(1).position(|d| *d > max) {
+                let past = past + 1;
+                // moves the point past the length back onto it
+                let (from, to) = (points[past - 1], points[past]);
+                let t = (max - distances[past - 1]) / (distances[past] - distances[past - 1]);
+                points.truncate(past + 1);
+                distances.truncate(past + 1);
+                points[past] = from + (to - from) * t;
+                distances[past] = max;
+            }
+        }
+
+        let total = distances[distances.len() - 1];
+        if points.len() < 2 || total <= f32::EPSILON {
+            return MeshData::default();
+        }
+
+        let mut vertices = Vec::with_capacity(points.len() * 2);
+        for (i, point) in points.iter().enumerate() {
+            let t = distances[i] / total;
+            // the direction at a point is the average of the segments on both sides of it
+            let (before, after) = (
+                points[i.saturating_sub(1)],
+                points[(i + 1).min(points.len() - 1)],
+            );
+            let direction = before - after;
+            let direction = if direction.magnitude2() > f32::EPSILON {
+                direction.normalize()
+            } else {
+                Vector2::new(1.0, 0.0)
+            };
+            let normal = Vector2::new(-direction.y, direction.x);
+            let half_width = lerp(self.head_width, self.tail_width, t) / 2.0;
+            let mut color = self.head_color;
+            for (c, tail) in color.iter_mut().zip(self.tail_color.iter()) {
+                *c = lerp(*c, *tail, t);
+            }
+
+            for (side, v) in [(1.0, 0.0), (-1.0, 1.0)] {
+                let position = point + normal * half_width * side;
+                vertices.push(MeshVertex {
+                    position: [position.x, position.y],
+                    tex_coord: [t, v],
+                    color,
+                });
+            }
+        }
+
+        let mut indices = Vec::with_capacity((points.len() - 1) * 6);
+        for i in 0..points.len() as u32 - 1 {
+            let (left, right) = (i * 2, i * 2 + 1);
+            indices.extend_from_slice(&[left, right, left + 2, right, right + 2, left + 2]);
+        }
+
+        MeshData::new(vertices, indices)
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
@@ -0,0 +1,386 @@
+use anyhow::{bail, Context, Result};
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use ktx2::SupercompressionScheme;
+use std::convert::TryInto;
+use std::io::Read;
+use wgpu::{Extent3d, Features, TextureFormat};
+
+const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const DDS_MAGIC: [u8; 4] = *b"DDS ";
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Container {
+    Ktx2,
+    Dds,
+}
+
+impl Container {
+    /// looks at the magic bytes, returns `None` for anything that isn't a KTX2 or DDS file
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&KTX2_MAGIC) {
+            Some(Container::Ktx2)
+        } else if data.starts_with(&DDS_MAGIC) {
+            Some(Container::Dds)
+        } else {
+            None
+        }
+    }
+}
+
+/// A texture that's stored in a GPU format, with all of its mip levels
+pub struct CompressedImage {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// the data of every mip level, starting with the full size image
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl CompressedImage {
+    pub fn load(data: &[u8]) -> Result<Self> {
+        match Container::detect(data) {
+            Some(Container::Ktx2) => Self::from_ktx2(data),
+            Some(Container::Dds) => Self::from_dds(data),
+            None => bail!("Data isn't a KTX2 or DDS container"),
+        }
+    }
+
+    pub fn from_ktx2(data: &[u8]) -> Result<Self> {
+        let reader = ktx2::Reader::new(data).context("Invalid KTX2 file")?;
+        let header = reader.header();
+
+        if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+            bail!("Only 2D KTX2 textures are supported, not arrays, cubemaps or 3D textures");
+        }
+
+        let format = match header.format.and_then(ktx2_format) {
+            Some(x) => x,
+            None => bail!("Unsupported KTX2 format {:?}", header.format),
+        };
+
+        let mut levels = Vec::with_capacity(header.level_count.max(1) as usize);
+        for level in reader.levels() {
+            levels.push(match header.supercompression_scheme {
+                None => level.to_vec(),
+                Some(SupercompressionScheme::Zstandard) => {
+                    let mut source = level;
+                    let mut decoder = ruzstd::StreamingDecoder::new(&mut source)
+                        .map_err(|e| anyhow::anyhow!("Invalid zstd data in KTX2 file: {}", e))?;
+                    let mut decompressed = Vec::new();
+                    decoder.read_to_end(&mut decompressed)?;
+                    decompressed
+                }
+                Some(scheme) => bail!("Unsupported KTX2 supercompression {:?}", scheme),
+            });
+        }
+
+        Self::new(format, header.pixel_width, header.pixel_height, levels)
+    }
+
+    pub fn from_dds(data: &[u8]) -> Result<Self> {
+        let dds = Dds::read(data).context("Invalid DDS file")?;
+
+        if dds.get_depth() > 1 || dds.get_num_array_layers() > 1 {
+            bail!("Only 2D DDS textures are supported, not arrays, cubemaps or 3D textures");
+        }
+
+        let format = match (dds.get_dxgi_format(), dds.get_d3d_format()) {
+            (Some(dxgi), _) => dxgi_format(dxgi),
+            (None, Some(d3d)) => d3d_format(d3d),
+            (None, None) => None,
+        };
+        let format = match format {
+            Some(x) => x,
+            None => bail!("Unsupported DDS format"),
+        };
+
+        let (width, height) = (dds.get_width(), dds.get_height());
+        let mut remaining = dds
+            .get_data(0)
+            .map_err(|e| anyhow::anyhow!("Invalid DDS data: {}", e))?;
+        let mut levels = Vec::new();
+        for level in 0..dds.get_num_mipmap_levels().max(1) {
+            let len = level_size(format, width, height, level);
+            if remaining.len() < len {
+                bail!("DDS file is missing data for mip level {}", level);
+            }
+            let (data, rest) = remaining.split_at(len);
+            levels.push(data.to_vec());
+            remaining = rest;
+        }
+
+        Self::new(format, width, height, levels)
+    }
+
+    fn new(format: TextureFormat, width: u32, height: u32, levels: Vec<Vec<u8>>) -> Result<Self> {
+        if levels.is_empty() {
+            bail!("Texture doesn't contain any data");
+        }
+
+        for (level, data) in levels.iter().enumerate() {
+            let expected = level_size(format, width, height, level as u32);
+            if data.len() < expected {
+                bail!(
+                    "Mip level {} is {} bytes, expected {}",
+                    level,
+                    data.len(),
+                    expected
+                );
+            }
+        }
+
+        Ok(Self {
+            format,
+            width,
+            height,
+            levels,
+        })
+    }
+
+    pub fn size(&self) -> Extent3d {
+        Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        }
+    }
+
+    /// whether the texture can be uploaded as is
+    pub fn is_supported(&self, features: Features) -> bool {
+        features.contains(self.format.describe().required_features)
+    }
+
+    /// the format the texture ends up in after `decompress`
+    pub fn decompressed_format(&self) -> TextureFormat {
+        if self.format.describe().srgb {
+            TextureFormat::Rgba8UnormSrgb
+        } else {
+            TextureFormat::Rgba8Unorm
+        }
+    }
+
+    /// decodes every mip level to RGBA8 on the CPU, for devices that don't support the format
+    pub fn decompress(&self) -> Result<Vec<Vec<u8>>> {
+        puffin::profile_function!();
+        let mut levels = Vec::with_capacity(self.levels.len());
+        for (level, data) in self.levels.iter().enumerate() {
+            let width = (self.width >> level).max(1) as usize;
+            let height = (self.height >> level).max(1) as usize;
+            levels.push(decode(self.format, data, width, height)?);
+        }
+        Ok(levels)
+    }
+}
+
+/// the amount of bytes in a row of blocks, and the amount of rows
+pub fn level_layout(format: TextureFormat, width: u32, height: u32, level: u32) -> (u32, u32) {
+    let info = format.describe();
+    let (block_width, block_height) = (
+        info.block_dimensions.0 as u32,
+        info.block_dimensions.1 as u32,
+    );
+    let width = (width >> level).max(1);
+    let height = (height >> level).max(1);
+    let blocks_x = width.div_ceil(block_width);
+    let blocks_y = height.div_ceil(block_height);
+    (blocks_x * info.block_size as u32, blocks_y)
+}
+
+fn level_size(format: TextureFormat, width: u32, height: u32, level: u32) -> usize {
+    let (bytes_per_row, rows) = level_layout(format, width, height, level);
+    bytes_per_row as usize * rows as usize
+}
+
+fn decode(format: TextureFormat, data: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    use texture2ddecoder::*;
+
+    if let TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb = format {
+        return match data.get(..width * height * 4) {
+            Some(pixels) => Ok(pixels.to_vec()),
+            None => bail!(
+                "Expected {} bytes of RGBA8 data, got {}",
+                width * height * 4,
+                data.len()
+            ),
+        };
+    }
+
+    let mut pixels = vec![0u32; width * height];
+    let result = match format {
+        TextureFormat::Bc1RgbaUnorm | TextureFormat::Bc1RgbaUnormSrgb => {
+            decode_bc1(data, width, height, &mut pixels)
+        }
+        TextureFormat::Bc2RgbaUnorm | TextureFormat::Bc2RgbaUnormSrgb => {
+            decode_bc2(data, width, height, &mut pixels)
+        }
+        TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb => {
+            decode_bc3(data, width, height, &mut pixels)
+        }
+        TextureFormat::Bc4RUnorm => decode_bc4(data, width, height, &mut pixels),
+        TextureFormat::Bc5RgUnorm => decode_bc5(data, width, height, &mut pixels),
+        TextureFormat::Bc7RgbaUnorm | TextureFormat::Bc7RgbaUnormSrgb => {
+            decode_bc7(data, width, height, &mut pixels)
+        }
+        TextureFormat::Etc2Rgb8Unorm | TextureFormat::Etc2Rgb8UnormSrgb => {
+            decode_etc2_rgb(data, width, height, &mut pixels)
+        }
+        TextureFormat::Etc2Rgb8A1Unorm | TextureFormat::Etc2Rgb8A1UnormSrgb => {
+            decode_etc2_rgba1(data, width, height, &mut pixels)
+        }
+        TextureFormat::Etc2Rgba8Unorm | TextureFormat::Etc2Rgba8UnormSrgb => {
+            decode_etc2_rgba8(data, width, height, &mut pixels)
+        }
+        TextureFormat::EacR11Unorm => decode_eacr(data, width, height, &mut pixels),
+        TextureFormat::EacRg11Unorm => decode_eacrg(data, width, height, &mut pixels),
+        format if format.describe().required_features == Features::TEXTURE_COMPRESSION_ASTC_LDR => {
+            let (block_width, block_height) = format.describe().block_dimensions;
+            decode_astc(
+                data,
+                width,
+                height,
+                block_width as usize,
+                block_height as usize,
+                &mut pixels,
+            )
+        }
+        format => bail!("No CPU decoder for {:?}", format),
+    };
+
+    if let Err(e) = result {
+        bail!("Failed to decode {:?} texture: {}", format, e);
+    }
+
+    // the decoder writes BGRA pixels
+    Ok(pixels
+        .into_iter()
+        .flat_map(|pixel| {
+            let [b, g, r, a] = pixel.to_le_bytes();
+            [r, g, b, a]
+        })
+        .collect())
+}
+
+/// texture2ddecoder has no BC2 decoder, a BC2 block is 4 bits of alpha per pixel followed by a
+/// BC1 block with the colors
+fn decode_bc2(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    pixels: &mut [u32],
+) -> std::result::Result<(), &'static str> {
+    let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+    if data.len() < blocks_x * blocks_y * 16 {
+        return Err("Not enough data to decode image!");
+    }
+
+    let mut block = [0u32; 16];
+    for (i, raw) in data.chunks_exact(16).take(blocks_x * blocks_y).enumerate() {
+        texture2ddecoder::decode_bc1_block(&raw[8..], &mut block);
+        let alpha = u64::from_le_bytes(raw[..8].try_into().unwrap());
+        for (j, pixel) in block.iter_mut().enumerate() {
+            let a = ((alpha >> (j * 4)) & 0xF) as u32 * 17;
+            *pixel = (*pixel & 0x00FF_FFFF) | (a << 24);
+        }
+
+        // the blocks on the right and bottom edges can stick out of the image
+        let (bx, by) = (i % blocks_x * 4, i / blocks_x * 4);
+        for y in 0..4.min(height - by) {
+            for x in 0..4.min(width - bx) {
+                pixels[(by + y) * width + bx + x] = block[y * 4 + x];
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn ktx2_format(format: ktx2::Format) -> Option<TextureFormat> {
+    use ktx2::Format as K;
+    use TextureFormat as T;
+
+    Some(match format {
+        K::R8G8B8A8_UNORM => T::Rgba8Unorm,
+        K::R8G8B8A8_SRGB => T::Rgba8UnormSrgb,
+        // BC1 doesn't have an opaque variant in wgpu, the alpha bit is unused for those
+        K::BC1_RGB_UNORM_BLOCK | K::BC1_RGBA_UNORM_BLOCK => T::Bc1RgbaUnorm,
+        K::BC1_RGB_SRGB_BLOCK | K::BC1_RGBA_SRGB_BLOCK => T::Bc1RgbaUnormSrgb,
+        K::BC2_UNORM_BLOCK => T::Bc2RgbaUnorm,
+        K::BC2_SRGB_BLOCK => T::Bc2RgbaUnormSrgb,
+        K::BC3_UNORM_BLOCK => T::Bc3RgbaUnorm,
+        K::BC3_SRGB_BLOCK => T::Bc3RgbaUnormSrgb,
+        K::BC4_UNORM_BLOCK => T::Bc4RUnorm,
+        K::BC5_UNORM_BLOCK => T::Bc5RgUnorm,
+        K::BC7_UNORM_BLOCK => T::Bc7RgbaUnorm,
+        K::BC7_SRGB_BLOCK => T::Bc7RgbaUnormSrgb,
+        K::ETC2_R8G8B8_UNORM_BLOCK => T::Etc2Rgb8Unorm,
+        K::ETC2_R8G8B8_SRGB_BLOCK => T::Etc2Rgb8UnormSrgb,
+        K::ETC2_R8G8B8A1_UNORM_BLOCK => T::Etc2Rgb8A1Unorm,
+        K::ETC2_R8G8B8A1_SRGB_BLOCK => T::Etc2Rgb8A1UnormSrgb,
+        K::ETC2_R8G8B8A8_UNORM_BLOCK => T::Etc2Rgba8Unorm,
+        K::ETC2_R8G8B8A8_SRGB_BLOCK => T::Etc2Rgba8UnormSrgb,
+        K::EAC_R11_UNORM_BLOCK => T::EacR11Unorm,
+        K::EAC_R11G11_UNORM_BLOCK => T::EacRg11Unorm,
+        K::ASTC_4x4_UNORM_BLOCK => T::Astc4x4RgbaUnorm,
+        K::ASTC_4x4_SRGB_BLOCK => T::Astc4x4RgbaUnormSrgb,
+        K::ASTC_5x4_UNORM_BLOCK => T::Astc5x4RgbaUnorm,
+        K::ASTC_5x4_SRGB_BLOCK => T::Astc5x4RgbaUnormSrgb,
+        K::ASTC_5x5_UNORM_BLOCK => T::Astc5x5RgbaUnorm,
+        K::ASTC_5x5_SRGB_BLOCK => T::Astc5x5RgbaUnormSrgb,
+        K::ASTC_6x5_UNORM_BLOCK => T::Astc6x5RgbaUnorm,
+        K::ASTC_6x5_SRGB_BLOCK => T::Astc6x5RgbaUnormSrgb,
+        K::ASTC_6x6_UNORM_BLOCK => T::Astc6x6RgbaUnorm,
+        K::ASTC_6x6_SRGB_BLOCK => T::Astc6x6RgbaUnormSrgb,
+        K::ASTC_8x5_UNORM_BLOCK => T::Astc8x5RgbaUnorm,
+        K::ASTC_8x5_SRGB_BLOCK => T::Astc8x5RgbaUnormSrgb,
+        K::ASTC_8x6_UNORM_BLOCK => T::Astc8x6RgbaUnorm,
+        K::ASTC_8x6_SRGB_BLOCK => T::Astc8x6RgbaUnormSrgb,
+        K::ASTC_8x8_UNORM_BLOCK => T::Astc8x8RgbaUnorm,
+        K::ASTC_8x8_SRGB_BLOCK => T::Astc8x8RgbaUnormSrgb,
+        K::ASTC_10x5_UNORM_BLOCK => T::Astc10x5RgbaUnorm,
+        K::ASTC_10x5_SRGB_BLOCK => T::Astc10x5RgbaUnormSrgb,
+        K::ASTC_10x6_UNORM_BLOCK => T::Astc10x6RgbaUnorm,
+        K::ASTC_10x6_SRGB_BLOCK => T::Astc10x6RgbaUnormSrgb,
+        K::ASTC_10x8_UNORM_BLOCK => T::Astc10x8RgbaUnorm,
+        K::ASTC_10x8_SRGB_BLOCK => T::Astc10x8RgbaUnormSrgb,
+        K::ASTC_10x10_UNORM_BLOCK => T::Astc10x10RgbaUnorm,
+        K::ASTC_10x10_SRGB_BLOCK => T::Astc10x10RgbaUnormSrgb,
+        K::ASTC_12x10_UNORM_BLOCK => T::Astc12x10RgbaUnorm,
+        K::ASTC_12x10_SRGB_BLOCK => T::Astc12x10RgbaUnormSrgb,
+        K::ASTC_12x12_UNORM_BLOCK => T::Astc12x12RgbaUnorm,
+        K::ASTC_12x12_SRGB_BLOCK => T::Astc12x12RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+fn dxgi_format(format: DxgiFormat) -> Option<TextureFormat> {
+    use TextureFormat as T;
+
+    Some(match format {
+        DxgiFormat::R8G8B8A8_UNorm => T::Rgba8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => T::Rgba8UnormSrgb,
+        DxgiFormat::BC1_UNorm => T::Bc1RgbaUnorm,
+        DxgiFormat::BC1_UNorm_sRGB => T::Bc1RgbaUnormSrgb,
+        DxgiFormat::BC2_UNorm => T::Bc2RgbaUnorm,
+        DxgiFormat::BC2_UNorm_sRGB => T::Bc2RgbaUnormSrgb,
+        DxgiFormat::BC3_UNorm => T::Bc3RgbaUnorm,
+        DxgiFormat::BC3_UNorm_sRGB => T::Bc3RgbaUnormSrgb,
+        DxgiFormat::BC4_UNorm => T::Bc4RUnorm,
+        DxgiFormat::BC5_UNorm => T::Bc5RgUnorm,
+        DxgiFormat::BC7_UNorm => T::Bc7RgbaUnorm,
+        DxgiFormat::BC7_UNorm_sRGB => T::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+fn d3d_format(format: D3DFormat) -> Option<TextureFormat> {
+    use TextureFormat as T;
+
+    Some(match format {
+        D3DFormat::DXT1 => T::Bc1RgbaUnorm,
+        D3DFormat::DXT3 => T::Bc2RgbaUnorm,
+        D3DFormat::DXT5 => T::Bc3RgbaUnorm,
+        _ => return None,
+    })
+}
@@ -0,0 +1,206 @@
+use crate::asset_management::AssetLoader;
+use crate::pipelines::sprite_mask::MASK_STENCIL_FORMAT;
+use anyhow::{anyhow, bail, Result};
+use wgpu::{Adapter, Backend, PresentMode, TextureFormat, TextureUsages};
+use winit::dpi::PhysicalSize;
+use winit::window::{Fullscreen, Icon, Window};
+
+pub const DEFAULT_WINDOW_TITLE: &str = "sprite-engine";
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WindowMode {
+    Windowed,
+    /// a window without decorations covering the whole monitor
+    Borderless,
+    /// exclusive fullscreen, using the video mode of the monitor closest to the window size
+    Fullscreen,
+}
+
+/// How the colors of the scene are mapped into the range of the surface
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Tonemapping {
+    /// colors brighter than white are clipped
+    None,
+    /// `c / (c + 1)`, keeps the hue but washes out bright colors
+    Reinhard,
+    /// the filmic curve of the ACES reference transform, fitted by Krzysztof Narkowicz
+    Aces,
+}
+
+/// The color spaces the frame is drawn in, the passes draw and blend in linear space, and the
+/// tonemapping pass encodes the scene as sRGB for the surface
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColorSettings {
+    /// draws the scene into an `Rgba16Float` target instead of `Rgba8UnormSrgb`, so lights and
+    /// colors brighter than white survive until they're tonemapped
+    pub hdr: bool,
+    pub tonemapping: Tonemapping,
+    /// the scene is multiplied with this before it's tonemapped
+    pub exposure: f32,
+}
+
+impl Default for ColorSettings {
+    fn default() -> Self {
+        Self {
+            hdr: false,
+            tonemapping: Tonemapping::None,
+            exposure: 1.0,
+        }
+    }
+}
+
+impl ColorSettings {
+    /// the format of the target the passes draw the scene into
+    pub fn scene_format(&self) -> TextureFormat {
+        if self.hdr {
+            TextureFormat::Rgba16Float
+        } else {
+            TextureFormat::Rgba8UnormSrgb
+        }
+    }
+}
+
+/// The settings of the window and the surface, applied with
+/// `RenderEngineResources::apply_display_settings`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisplaySettings {
+    pub present_mode: PresentMode,
+    /// the tonemapping pass encodes the colors itself when the format isn't sRGB
+    pub surface_format: TextureFormat,
+    pub color: ColorSettings,
+    /// the samples per pixel of the scene, 1 turns MSAA off
+    pub msaa_samples: u32,
+    pub window_mode: WindowMode,
+    /// the inner size of the window, only used when windowed
+    pub size: PhysicalSize<u32>,
+    pub title: String,
+    /// the asset name of the window icon
+    pub icon: Option<String>,
+}
+
+impl DisplaySettings {
+    pub(crate) fn new(
+        size: PhysicalSize<u32>,
+        surface_format: TextureFormat,
+        msaa_samples: u32,
+    ) -> Self {
+        Self {
+            present_mode: PresentMode::Fifo,
+            surface_format,
+            color: ColorSettings::default(),
+            msaa_samples,
+            window_mode: WindowMode::Windowed,
+            size,
+            title: DEFAULT_WINDOW_TITLE.to_string(),
+            icon: None,
+        }
+    }
+}
+
+/// the present modes the surface can support on a backend, wgpu can't list the modes of a
+/// surface yet, so this follows what the backends report to it
+///
+/// Vulkan drivers don't have to support `Mailbox` or `Immediate`, wgpu falls back to `Fifo` with
+/// a warning when they don't.
+pub fn supported_present_modes(backend: Backend) -> Vec<PresentMode> {
+    match backend {
+        Backend::Vulkan => vec![
+            PresentMode::Fifo,
+            PresentMode::Mailbox,
+            PresentMode::Immediate,
+        ],
+        Backend::Metal | Backend::Dx12 => vec![PresentMode::Fifo, PresentMode::Immediate],
+        _ => vec![PresentMode::Fifo],
+    }
+}
+
+/// the formats the surface can be configured with, wgpu can't list the formats of a surface yet
+///
+/// `preferred` is the first of the four formats WebGPU guarantees that the surface supports, which
+/// is the sRGB one if there is one. Vulkan and Metal surfaces support the linear variant of an
+/// sRGB format along with it in practice.
+pub fn supported_surface_formats(preferred: TextureFormat) -> Vec<TextureFormat> {
+    match preferred {
+        TextureFormat::Bgra8UnormSrgb => vec![preferred, TextureFormat::Bgra8Unorm],
+        TextureFormat::Rgba8UnormSrgb => vec![preferred, TextureFormat::Rgba8Unorm],
+        _ => vec![preferred],
+    }
+}
+
+/// the MSAA sample counts the adapter supports for every scene format and the mask stencil, wgpu
+/// can't tell which sample counts a format supports yet
+///
+/// WebGPU guarantees 4 samples for the formats that can be rendered into, so 4 is only offered when
+/// the adapter and the guaranteed features of every format allow rendering into it.
+pub fn supported_msaa_samples(adapter: &Adapter) -> Vec<u32> {
+    let sdr = ColorSettings::default();
+    let hdr = ColorSettings { hdr: true, ..sdr };
+    let formats = [sdr.scene_format(), hdr.scene_format(), MASK_STENCIL_FORMAT];
+    let renderable = formats.iter().all(|&format| {
+        let guaranteed = format.describe().guaranteed_format_features;
+        let features = adapter.get_texture_format_features(format);
+        guaranteed
+            .allowed_usages
+            .contains(TextureUsages::RENDER_ATTACHMENT)
+            && features
+                .allowed_usages
+                .contains(TextureUsages::RENDER_ATTACHMENT)
+    });
+    if renderable {
+        vec![1, 4]
+    } else {
+        vec![1]
+    }
+}
+
+/// applies the window related settings, the new size reaches the surface through the resize event
+/// of the window
+pub(crate) fn apply_to_window(window: &Window, settings: &DisplaySettings) -> Result<()> {
+    if settings.size.width == 0 || settings.size.height == 0 {
+        bail!(
+            "The window size has to be positive, got {}x{}",
+            settings.size.width,
+            settings.size.height
+        );
+    }
+
+    // load the icon first, so a missing icon doesn't leave the window half updated
+    let icon = settings.icon.as_deref().map(load_icon).transpose()?;
+
+    match settings.window_mode {
+        WindowMode::Windowed => {
+            window.set_fullscreen(None);
+            window.set_inner_size(settings.size);
+        }
+        WindowMode::Borderless => {
+            window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+        }
+        WindowMode::Fullscreen => {
+            let monitor = window
+                .current_monitor()
+                .ok_or_else(|| anyhow!("The window isn't on a monitor, can't go fullscreen"))?;
+            // the mode closest to the window size, with the highest refresh rate
+            let mode = monitor
+                .video_modes()
+                .min_by_key(|m| {
+                    let size = m.size();
+                    let distance = (size.width as i64 - settings.size.width as i64).abs()
+                        + (size.height as i64 - settings.size.height as i64).abs();
+                    (distance, std::cmp::Reverse(m.refresh_rate()))
+                })
+                .ok_or_else(|| anyhow!("The monitor doesn't have any video modes"))?;
+            window.set_fullscreen(Some(Fullscreen::Exclusive(mode)));
+        }
+    }
+
+    window.set_title(&settings.title);
+    window.set_window_icon(icon);
+    Ok(())
+}
+
+fn load_icon(id: &str) -> Result<Icon> {
+    let data = AssetLoader::get_asset(id)?;
+    let image = image::load_from_memory(&data)?.to_rgba8();
+    let (width, height) = image.dimensions();
+    Ok(Icon::from_rgba(image.into_raw(), width, height)?)
+}
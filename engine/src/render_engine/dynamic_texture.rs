@@ -0,0 +1,258 @@
+use crate::asset_management::{
+    AssetLoader, AssetMetadata, GpuTextureRef, Uuid, UUID_NAMESPACE_ASSETS,
+};
+use crate::render_engine::texture::{GpuTexture, TextureImportSettings};
+use bevy_ecs::component::Component;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use wgpu::{Device, Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, TextureAspect};
+
+/// after this many separate dirty rectangles, they're merged into one covering all of them
+const MAX_DIRTY_RECTS: usize = 16;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DirtyRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DirtyRect {
+    fn right(&self) -> u32 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    /// also true for rectangles that only touch, as merging those doesn't upload anything extra
+    fn touches(&self, other: &DirtyRect) -> bool {
+        self.x <= other.right()
+            && other.x <= self.right()
+            && self.y <= other.bottom()
+            && other.y <= self.bottom()
+    }
+
+    fn union(&self, other: &DirtyRect) -> DirtyRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        DirtyRect {
+            x,
+            y,
+            width: self.right().max(other.right()) - x,
+            height: self.bottom().max(other.bottom()) - y,
+        }
+    }
+}
+
+/// An RGBA8 texture with a pixel buffer on the CPU, the parts that changed are uploaded to the
+/// GPU every frame by `ecs_upload_dynamic_textures`, or by calling `upload` manually
+#[derive(Component)]
+pub struct DynamicTexture {
+    texture: GpuTextureRef,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    dirty: Vec<DirtyRect>,
+}
+
+impl DynamicTexture {
+    /// the texture starts out fully transparent, `id` has to be unique among all textures
+    pub fn new(
+        device: &Device,
+        id: &str,
+        width: u32,
+        height: u32,
+        settings: TextureImportSettings,
+    ) -> Self {
+        let uuid = Uuid::new_v5(&UUID_NAMESPACE_ASSETS, id.as_bytes());
+        let metadata = AssetMetadata {
+            texture: settings,
+            ..Default::default()
+        };
+        let texture = GpuTexture::new_empty(device, width, height, id, uuid, metadata);
+
+        Self {
+            texture: AssetLoader::insert_generated_texture(id, Arc::new(texture)),
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+            dirty: Vec::new(),
+        }
+    }
+
+    /// a reference that can be used in a `Texture` component, or anywhere else a texture is used
+    pub fn texture_ref(&self) -> GpuTextureRef {
+        self.texture.clone()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// the pixels in RGBA8 format, row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = self.index(x, y);
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        let i = self.index(x, y);
+        self.pixels[i..i + 4].copy_from_slice(&color);
+        self.mark_dirty(x, y, 1, 1);
+    }
+
+    pub fn fill(&mut self, color: [u8; 4]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+        self.mark_dirty(0, 0, self.width, self.height);
+    }
+
+    /// copies tightly packed RGBA8 `data` into the rectangle at `x`, `y`
+    pub fn write_rect(&mut self, x: u32, y: u32, width: u32, height: u32, data: &[u8]) {
+        assert_eq!(
+            data.len(),
+            (width * height * 4) as usize,
+            "Data doesn't match the size of the rectangle"
+        );
+
+        let row_len = (width * 4) as usize;
+        for row in 0..height {
+            let start = self.index(x, y + row);
+            let src = row as usize * row_len;
+            self.pixels[start..start + row_len].copy_from_slice(&data[src..src + row_len]);
+        }
+        self.mark_dirty(x, y, width, height);
+    }
+
+    /// calls `f` with the position and the RGBA8 value of every pixel in the rectangle
+    pub fn update_rect<F: FnMut(u32, u32, &mut [u8])>(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        mut f: F,
+    ) {
+        for py in y..y + height {
+            for px in x..x + width {
+                let i = self.index(px, py);
+                f(px, py, &mut self.pixels[i..i + 4]);
+            }
+        }
+        self.mark_dirty(x, y, width, height);
+    }
+
+    /// makes sure the rectangle is uploaded, the parts outside of the texture are ignored
+    pub fn mark_dirty(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        if x >= self.width || y >= self.height || width == 0 || height == 0 {
+            return;
+        }
+
+        let mut rect = DirtyRect {
+            x,
+            y,
+            width: width.min(self.width - x),
+            height: height.min(self.height - y),
+        };
+
+        // keep merging until the rectangle doesn't touch any of the others
+        while let Some(i) = self.dirty.iter().position(|other| other.touches(&rect)) {
+            rect = rect.union(&self.dirty.swap_remove(i));
+        }
+        self.dirty.push(rect);
+
+        if self.dirty.len() > MAX_DIRTY_RECTS {
+            let bounds = self
+                .dirty
+                .iter()
+                .skip(1)
+                .fold(self.dirty[0], |a, b| a.union(b));
+            self.dirty.clear();
+            self.dirty.push(bounds);
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// writes the dirty rectangles to the GPU texture
+    pub fn upload(&mut self, queue: &Queue) {
+        if self.dirty.is_empty() {
+            return;
+        }
+
+        let texture = self.texture.load();
+        for rect in self.dirty.drain(..) {
+            queue.write_texture(
+                ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: Origin3d {
+                        x: rect.x,
+                        y: rect.y,
+                        z: 0,
+                    },
+                    aspect: TextureAspect::All,
+                },
+                // the whole buffer is passed in, the offset and row length select the rectangle
+                &self.pixels,
+                ImageDataLayout {
+                    offset: ((rect.y * self.width + rect.x) * 4) as u64,
+                    bytes_per_row: NonZeroU32::new(self.width * 4),
+                    rows_per_image: NonZeroU32::new(self.height),
+                },
+                Extent3d {
+                    width: rect.width,
+                    height: rect.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+    }
+
+    /// creates the GPU texture on a new device, the pixels are uploaded again on the next upload
+    pub(crate) fn recreate(&mut self, device: &Device) {
+        let old = self.texture.load();
+        let texture = GpuTexture::new_empty(
+            device,
+            self.width,
+            self.height,
+            &old.uuid().to_string(),
+            old.uuid(),
+            old.metadata().clone(),
+        );
+        drop(old);
+        AssetLoader::replace_cached_texture(&self.texture.uuid(), Arc::new(texture));
+        self.mark_dirty(0, 0, self.width, self.height);
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "Pixel ({}, {}) is outside of the {}x{} texture",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        ((y * self.width + x) * 4) as usize
+    }
+}
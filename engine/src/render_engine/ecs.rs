@@ -1,8 +1,19 @@
 use crate::ecs::EcsWorld;
-use crate::render_engine::resources::CommandBufferCollector;
+use crate::render_engine::capture::FrameCapture;
+use crate::render_engine::gizmos::Gizmos;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::palettes::Palettes;
+use crate::render_engine::particles::ParticleJobs;
+use crate::render_engine::render_graph::{slots, RenderGraphSet, RenderNode};
+use crate::render_engine::resources::{
+    AmbientLight, CommandBufferCollector, FrameResources, LightingResources, MaskStencil,
+};
+use crate::render_engine::spatial_grid::SpatialGrid;
+use crate::render_engine::static_bundles::StaticSpriteBundles;
 use crate::render_engine::RenderEngineResources;
 use crate::ui::integration::EguiRequestRedrawEvent;
 use bevy_ecs::prelude::*;
+use std::path::PathBuf;
 use winit::event_loop::EventLoop;
 use winit::window::Window;
 
@@ -12,14 +23,34 @@ pub fn init_renderer_resources_in_world(
     world: &mut EcsWorld,
     window: Window,
     event_loop: &EventLoop<EguiRequestRedrawEvent>,
+    wgpu_trace: Option<PathBuf>,
 ) {
-    let engine_res = RenderEngineResources::new(window, event_loop);
+    let engine_res = RenderEngineResources::new(window, event_loop, wgpu_trace);
+    let lighting_res =
+        LightingResources::new(&engine_res.device, &engine_res.queue, engine_res.size);
+    let gpu_profiler = GpuProfiler::new(&engine_res.device, &engine_res.queue);
     world.world.insert_resource(engine_res);
+    world.world.insert_resource(lighting_res);
+    world.world.insert_resource(gpu_profiler);
+    world.world.insert_resource(AmbientLight::default());
+    world.world.insert_resource(ParticleJobs::default());
+    world.world.insert_resource(Gizmos::default());
+    world.world.insert_resource(SpatialGrid::default());
+    world.world.insert_resource(StaticSpriteBundles::default());
+    world.world.insert_resource(FrameCapture::default());
+    world.world.insert_resource(MaskStencil::default());
+    world.world.insert_resource(Palettes::default());
     world
         .world
         .insert_resource(CommandBufferCollector::default());
 }
 
+/// whether a surface texture was acquired for this frame, the render schedule is skipped without
+/// one
+pub(crate) fn frame_acquired(world: &World) -> bool {
+    world.contains_resource::<FrameResources>()
+}
+
 pub fn insert_renderer_systems_in_schedule(world: &mut EcsWorld) {
     //world.schedule.add_system_to_stage(
     //    ScheduleStages::Update.to_str(),
@@ -47,20 +78,129 @@ pub fn insert_renderer_systems_in_schedule(world: &mut EcsWorld) {
     //);
 
     world.schedule.add_systems((
+        systems::recover_lost_device::ecs_recover_lost_device
+            .before(systems::update::update_render_engine),
         systems::update::update_render_engine,
+        systems::capture_frame::ecs_sync_fixed_timestep
+            .before(crate::ecs::systems::delta_time::update_delta_time),
         systems::create_frame::ecs_render_create_frame_resource
             .after(systems::update::update_render_engine),
+        systems::simulate_particles::simulate_particles
+            .after(crate::ecs::systems::delta_time::update_delta_time),
+        systems::update_skeletons::ecs_update_skeletons
+            .after(crate::ecs::systems::delta_time::update_delta_time),
+        systems::update_trails::ecs_update_trails
+            .after(crate::ecs::systems::delta_time::update_delta_time),
+        systems::scroll_parallax_layers::ecs_scroll_parallax_layers
+            .after(crate::ecs::systems::delta_time::update_delta_time),
     ));
 
+    // the passes of the frame, they're ordered by the slots they use and added to the render
+    // schedule when the graph is built before the first frame
+    let graph = &mut world.render_graph;
+    graph.import(slots::FRAME);
+    graph.import(slots::OUTPUT);
+    graph.import(slots::NORMALS);
+    graph.add_node(
+        RenderNode::new("clear", systems::clear_frame::ecs_clear_frame).writes(slots::FRAME),
+    );
+    // parallax layers are the background, everything else is drawn on top of them
+    graph.add_node(
+        RenderNode::new("parallax", systems::render_parallax::ecs_render_parallax)
+            .writes(slots::FRAME),
+    );
+    // meshes are usually terrain or water, so they're drawn below the sprites
+    graph.add_node(
+        RenderNode::new("meshes", systems::render_meshes::ecs_render_meshes).writes(slots::FRAME),
+    );
+    // trails come out of the back of projectiles, so they're drawn below the sprites
+    graph.add_node(
+        RenderNode::new("trails", systems::render_trails::ecs_render_trails).writes(slots::FRAME),
+    );
+    graph.add_node(
+        RenderNode::new(
+            "sprites",
+            systems::render_sprites::ecs_render_sprites
+                .after(systems::update_spatial_grid::ecs_update_spatial_grid)
+                .after(systems::update_static_bundles::ecs_update_static_bundles),
+        )
+        .writes(slots::FRAME),
+    );
+    // palette swapped sprites are usually characters, so they go over the rest of the sprites
+    graph.add_node(
+        RenderNode::new(
+            "palette_sprites",
+            systems::render_palette_sprites::ecs_render_palette_sprites,
+        )
+        .writes(slots::FRAME),
+    );
+    // masked sprites are usually UI, like scroll views and health bars, so they go over the others
+    graph.add_node(
+        RenderNode::new(
+            "masked_sprites",
+            systems::render_masked_sprites::ecs_render_masked_sprites,
+        )
+        .writes(slots::FRAME),
+    );
+    graph.add_node(
+        RenderNode::new(
+            "normals",
+            systems::render_normals::ecs_render_normals
+                .after(systems::update_spatial_grid::ecs_update_spatial_grid)
+                .after(systems::update_static_bundles::ecs_update_static_bundles),
+        )
+        .writes(slots::NORMALS),
+    );
+    graph.add_node(
+        RenderNode::new("particles", systems::render_particles::ecs_render_particles)
+            .writes(slots::FRAME),
+    );
+    // the lighting is composited on top of the sprites, but below the UI
+    graph.add_node(
+        RenderNode::new("lighting", systems::render_lighting::ecs_render_lighting)
+            .reads(slots::NORMALS)
+            .writes(slots::FRAME),
+    );
+    // gizmos are drawn after the lighting, so they aren't darkened by it
+    graph.add_node(
+        RenderNode::new("gizmos", systems::render_gizmos::ecs_render_gizmos).writes(slots::FRAME),
+    );
+    // nodes drawing the scene have to run before this, nodes drawing over it write the output
+    graph.add_node(
+        RenderNode::new("tonemap", systems::tonemap::ecs_tonemap)
+            .reads(slots::FRAME)
+            .writes(slots::OUTPUT),
+    );
+    // screenshots without the debug UI are copied before it's drawn
+    graph.add_node(
+        RenderNode::new("capture_scene", systems::capture_frame::ecs_capture_scene)
+            .reads(slots::OUTPUT),
+    );
+    graph.add_node(
+        RenderNode::new("egui", systems::render_egui_ui::ecs_render_egui_ui).writes(slots::OUTPUT),
+    );
+    graph.add_node(
+        RenderNode::new("capture_frame", systems::capture_frame::ecs_capture_frame)
+            .reads(slots::OUTPUT),
+    );
+    graph.add_node(
+        RenderNode::new(
+            "present_capture",
+            systems::capture_frame::ecs_present_capture,
+        )
+        .reads(slots::OUTPUT)
+        .after("capture_frame"),
+    );
+
     world.render_schedule.add_systems((
-        systems::render_sprites::ecs_render_sprites
-            .after(systems::create_frame::ecs_render_create_frame_resource),
-        systems::render_egui_ui::ecs_render_egui_ui
+        systems::upload_dynamic_textures::ecs_upload_dynamic_textures
             .after(systems::create_frame::ecs_render_create_frame_resource)
-            // TODO this is a really bad way to fix this, but it should now always draw the UI over the sprites
-            .after(systems::render_sprites::ecs_render_sprites),
+            .before(RenderGraphSet),
+        systems::allocate_render_targets::ecs_allocate_render_targets.before(RenderGraphSet),
+        systems::update_spatial_grid::ecs_update_spatial_grid,
+        systems::update_static_bundles::ecs_update_static_bundles,
         systems::submit_commands::ecs_render_submit_commands
-            .after(systems::render_sprites::ecs_render_sprites)
-            .after(systems::render_egui_ui::ecs_render_egui_ui),
+            .after(systems::upload_dynamic_textures::ecs_upload_dynamic_textures)
+            .after(RenderGraphSet),
     ));
 }
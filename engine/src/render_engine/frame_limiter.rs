@@ -0,0 +1,166 @@
+use crate::ui::integration::EguiRequestRedrawEvent;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::ControlFlow;
+
+/// the last part of the wait is spent spinning, sleeping isn't accurate enough to hit the target
+/// frametime, especially on Windows
+const SPIN_DURATION: Duration = Duration::from_millis(2);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RedrawMode {
+    /// redraws continuously, limited to the target frame rate if there is one
+    Continuous,
+    /// only redraws after input, window events or `RenderEngineResources::request_redraw`, for
+    /// tools that don't need to animate
+    Reactive,
+}
+
+/// What to do while the window isn't focused, minimised windows are never rendered
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BackgroundMode {
+    /// keep rendering like when focused
+    Continue,
+    /// render at most at this frame rate
+    Throttle(f64),
+    Pause,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameLimiterSettings {
+    /// `None` doesn't limit the frame rate, the present mode still can
+    pub target_fps: Option<f64>,
+    pub background: BackgroundMode,
+    pub redraw_mode: RedrawMode,
+}
+
+impl Default for FrameLimiterSettings {
+    fn default() -> Self {
+        Self {
+            target_fps: None,
+            background: BackgroundMode::Throttle(30.0),
+            redraw_mode: RedrawMode::Continuous,
+        }
+    }
+}
+
+/// Decides when the next frame is drawn, it's driven by the events of the event loop
+pub struct FrameLimiter {
+    settings: FrameLimiterSettings,
+    focused: bool,
+    minimized: bool,
+    last_frame: Instant,
+    last_frame_end: Instant,
+    /// set by input and redraw requests, a frame in reactive mode is only drawn when it's set
+    redraw_requested: AtomicBool,
+    /// the time between the end of the previous frame and the start of the last one
+    idle_time: Duration,
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        Self {
+            settings: FrameLimiterSettings::default(),
+            focused: true,
+            minimized: false,
+            last_frame: Instant::now(),
+            last_frame_end: Instant::now(),
+            redraw_requested: AtomicBool::new(true),
+            idle_time: Duration::new(0, 0),
+        }
+    }
+}
+
+impl FrameLimiter {
+    pub fn settings(&self) -> FrameLimiterSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: FrameLimiterSettings) {
+        self.settings = settings;
+        self.request_redraw();
+    }
+
+    pub fn idle_time(&self) -> Duration {
+        self.idle_time
+    }
+
+    /// whether frames are being drawn at all right now
+    pub fn is_paused(&self) -> bool {
+        self.minimized || (!self.focused && self.settings.background == BackgroundMode::Pause)
+    }
+
+    pub fn request_redraw(&self) {
+        self.redraw_requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn process_event(&mut self, event: &Event<EguiRequestRedrawEvent>) {
+        match event {
+            Event::WindowEvent { event, .. } => {
+                match event {
+                    WindowEvent::Focused(focused) => self.focused = *focused,
+                    // winit reports a size of 0 when the window is minimised
+                    WindowEvent::Resized(size) => {
+                        self.minimized = size.width == 0 || size.height == 0
+                    }
+                    _ => (),
+                }
+                // anything happening to the window can change what's on screen in reactive mode
+                self.request_redraw();
+            }
+            Event::UserEvent(EguiRequestRedrawEvent::RequestRedraw) => self.request_redraw(),
+            _ => (),
+        }
+    }
+
+    /// called at the end of every iteration of the event loop, returns whether a frame should be
+    /// drawn now and how long the event loop can wait otherwise
+    pub fn poll(&mut self) -> (bool, ControlFlow) {
+        if self.is_paused() {
+            return (false, ControlFlow::Wait);
+        }
+
+        if self.settings.redraw_mode == RedrawMode::Reactive
+            && !self.redraw_requested.load(Ordering::Relaxed)
+        {
+            return (false, ControlFlow::Wait);
+        }
+
+        let target_fps = if self.focused {
+            self.settings.target_fps
+        } else {
+            match self.settings.background {
+                BackgroundMode::Throttle(fps) => {
+                    Some(self.settings.target_fps.map_or(fps, |t| t.min(fps)))
+                }
+                _ => self.settings.target_fps,
+            }
+        };
+
+        if let Some(fps) = target_fps.filter(|fps| *fps > 0.0) {
+            let deadline = self.last_frame + Duration::from_secs_f64(1.0 / fps);
+            let now = Instant::now();
+            if deadline > now + SPIN_DURATION {
+                // sleep in the event loop, so events are still handled while waiting
+                return (false, ControlFlow::WaitUntil(deadline - SPIN_DURATION));
+            }
+            while Instant::now() < deadline {
+                std::hint::spin_loop();
+            }
+        }
+
+        (true, ControlFlow::Poll)
+    }
+
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+        self.idle_time = now.duration_since(self.last_frame_end);
+        self.last_frame = now;
+        self.redraw_requested.store(false, Ordering::Relaxed);
+    }
+
+    pub fn end_frame(&mut self) {
+        self.last_frame_end = Instant::now();
+    }
+}
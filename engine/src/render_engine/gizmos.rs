@@ -0,0 +1,167 @@
+use crate::render_engine::vertex::ColorVertex2;
+use bevy_ecs::system::Resource;
+use cgmath::{InnerSpace, Vector2};
+use parking_lot::Mutex;
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use std::time::Duration;
+
+const CIRCLE_SEGMENTS: usize = 32;
+/// the length of the arrow head, relative to the length of the arrow
+const ARROW_HEAD_FRACTION: f32 = 0.2;
+
+pub const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+pub const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+pub const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+pub const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+pub const YELLOW: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+
+struct GizmoLine {
+    start: Vector2<f32>,
+    end: Vector2<f32>,
+    color: [f32; 4],
+    /// seconds left before the line is removed, lines with 0.0 are drawn for a single frame
+    remaining: f32,
+}
+
+/// Immediate-mode debug shapes in world space, drawn on top of the scene.
+/// Cloning is cheap, every clone draws into the same set of shapes.
+#[derive(Resource, Clone, Default)]
+pub struct Gizmos {
+    lines: Arc<Mutex<Vec<GizmoLine>>>,
+}
+
+impl Gizmos {
+    /// shapes drawn with the returned drawer stay visible for `duration` instead of a single frame
+    pub fn for_duration(&self, duration: Duration) -> GizmoDrawer {
+        GizmoDrawer {
+            gizmos: self,
+            duration: duration.as_secs_f32(),
+        }
+    }
+
+    fn drawer(&self) -> GizmoDrawer {
+        GizmoDrawer {
+            gizmos: self,
+            duration: 0.0,
+        }
+    }
+
+    pub fn line(&self, start: Vector2<f32>, end: Vector2<f32>, color: [f32; 4]) {
+        self.drawer().line(start, end, color)
+    }
+
+    pub fn rect(&self, center: Vector2<f32>, size: Vector2<f32>, color: [f32; 4]) {
+        self.drawer().rect(center, size, color)
+    }
+
+    pub fn circle(&self, center: Vector2<f32>, radius: f32, color: [f32; 4]) {
+        self.drawer().circle(center, radius, color)
+    }
+
+    pub fn arrow(&self, start: Vector2<f32>, end: Vector2<f32>, color: [f32; 4]) {
+        self.drawer().arrow(start, end, color)
+    }
+
+    pub fn cross(&self, center: Vector2<f32>, size: f32, color: [f32; 4]) {
+        self.drawer().cross(center, size, color)
+    }
+
+    /// builds a line list out of every shape that is currently visible
+    pub(crate) fn vertices(&self) -> Vec<ColorVertex2> {
+        let lines = self.lines.lock();
+        let mut vertices = Vec::with_capacity(lines.len() * 2);
+        for line in lines.iter() {
+            vertices.push(ColorVertex2 {
+                position: line.start.into(),
+                color: line.color,
+            });
+            vertices.push(ColorVertex2 {
+                position: line.end.into(),
+                color: line.color,
+            });
+        }
+        vertices
+    }
+
+    /// removes the shapes that have been visible for long enough
+    pub(crate) fn tick(&self, dt: f32) {
+        let mut lines = self.lines.lock();
+        lines.retain_mut(|line| {
+            line.remaining -= dt;
+            line.remaining > 0.0
+        });
+    }
+
+    fn push(&self, line: GizmoLine) {
+        self.lines.lock().push(line);
+    }
+}
+
+pub struct GizmoDrawer<'a> {
+    gizmos: &'a Gizmos,
+    duration: f32,
+}
+
+impl<'a> GizmoDrawer<'a> {
+    pub fn line(&self, start: Vector2<f32>, end: Vector2<f32>, color: [f32; 4]) {
+        self.gizmos.push(GizmoLine {
+            start,
+            end,
+            color,
+            remaining: self.duration,
+        });
+    }
+
+    pub fn rect(&self, center: Vector2<f32>, size: Vector2<f32>, color: [f32; 4]) {
+        let half = size / 2.0;
+        let tl = center + Vector2::new(-half.x, half.y);
+        let tr = center + half;
+        let br = center + Vector2::new(half.x, -half.y);
+        let bl = center - half;
+
+        self.line(tl, tr, color);
+        self.line(tr, br, color);
+        self.line(br, bl, color);
+        self.line(bl, tl, color);
+    }
+
+    pub fn circle(&self, center: Vector2<f32>, radius: f32, color: [f32; 4]) {
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + Vector2::new(angle.cos(), angle.sin()) * radius
+        };
+
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    pub fn arrow(&self, start: Vector2<f32>, end: Vector2<f32>, color: [f32; 4]) {
+        self.line(start, end, color);
+
+        let delta = end - start;
+        if delta.magnitude2() <= f32::EPSILON {
+            return;
+        }
+
+        let back = -delta * ARROW_HEAD_FRACTION;
+        let side = Vector2::new(-back.y, back.x) * 0.5;
+        self.line(end, end + back + side, color);
+        self.line(end, end + back - side, color);
+    }
+
+    pub fn cross(&self, center: Vector2<f32>, size: f32, color: [f32; 4]) {
+        let half = size / 2.0;
+        self.line(
+            center + Vector2::new(-half, -half),
+            center + Vector2::new(half, half),
+            color,
+        );
+        self.line(
+            center + Vector2::new(-half, half),
+            center + Vector2::new(half, -half),
+            color,
+        );
+    }
+}
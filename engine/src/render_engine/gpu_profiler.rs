@@ -0,0 +1,277 @@
+use ahash::AHashMap;
+use bevy_ecs::system::Resource;
+use bevy_tasks::{IoTaskPool, TaskPool};
+use parking_lot::Mutex;
+use puffin::{GlobalProfiler, ScopeDetails, ScopeId, Stream, StreamInfo, ThreadInfo};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use wgpu::*;
+
+/// two queries per scope, more scopes than this in a frame aren't timed
+const MAX_QUERIES: u32 = 128;
+/// the timestamps are read back a few frames later, so reading them never stalls the GPU
+const READBACK_FRAMES: usize = 3;
+const TIMESTAMP_SIZE: BufferAddress = std::mem::size_of::<u64>() as BufferAddress;
+
+/// Times the render passes on the GPU with timestamp queries, the durations are reported to puffin
+/// as the "GPU" thread and shown in the frametime window
+///
+/// It does nothing when the adapter doesn't support `Features::TIMESTAMP_QUERY`.
+#[derive(Resource)]
+pub struct GpuProfiler {
+    queries: Option<TimestampQueries>,
+    /// the duration of every scope of the last frame that was read back
+    last_times: Vec<(&'static str, Duration)>,
+}
+
+struct TimestampQueries {
+    query_set: QuerySet,
+    /// nanoseconds per timestamp tick
+    timestamp_period: f32,
+    next_query: AtomicU32,
+    /// the name and first query of every scope of the frame being recorded
+    scopes: Mutex<Vec<(&'static str, u32)>>,
+    readbacks: Vec<Readback>,
+    next_readback: usize,
+    /// the readback buffer the timestamps of the frame being submitted are copied into
+    resolved: Option<usize>,
+    scope_ids: AHashMap<&'static str, ScopeId>,
+}
+
+struct Readback {
+    buffer: Buffer,
+    /// the scopes of the frame the buffer holds, `None` when the buffer is free, a buffer that
+    /// failed to map is never freed again
+    scopes: Option<Vec<(&'static str, u32)>>,
+    mapped: Arc<AtomicBool>,
+}
+
+/// A pass timed by the `GpuProfiler`, the begin and end timestamps can be written into different
+/// encoders as long as they are submitted in the same frame, every scope has to be ended
+#[derive(Copy, Clone)]
+pub struct GpuScope<'a> {
+    /// the query set and the begin query, `None` when the pass isn't timed
+    query: Option<(&'a QuerySet, u32)>,
+}
+
+impl GpuScope<'_> {
+    pub fn begin(&self, encoder: &mut CommandEncoder) {
+        if let Some((query_set, index)) = self.query {
+            encoder.write_timestamp(query_set, index);
+        }
+    }
+
+    pub fn end(&self, encoder: &mut CommandEncoder) {
+        if let Some((query_set, index)) = self.query {
+            encoder.write_timestamp(query_set, index + 1);
+        }
+    }
+}
+
+impl GpuProfiler {
+    /// the device has to be created with `Features::TIMESTAMP_QUERY` for the passes to be timed
+    pub(crate) fn new(device: &Device, queue: &Queue) -> Self {
+        if !device.features().contains(Features::TIMESTAMP_QUERY) {
+            return Self {
+                queries: None,
+                last_times: Vec::new(),
+            };
+        }
+
+        let query_set = device.create_query_set(&QuerySetDescriptor {
+            label: Some("GPU profiler QS"),
+            ty: QueryType::Timestamp,
+            count: MAX_QUERIES,
+        });
+        let size = MAX_QUERIES as BufferAddress * TIMESTAMP_SIZE;
+        let readbacks = (0..READBACK_FRAMES)
+            .map(|_| Readback {
+                buffer: device.create_buffer(&BufferDescriptor {
+                    label: Some("GPU profiler readback BUF"),
+                    size,
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                scopes: None,
+                mapped: Arc::new(AtomicBool::new(false)),
+            })
+            .collect();
+
+        Self {
+            queries: Some(TimestampQueries {
+                query_set,
+                timestamp_period: queue.get_timestamp_period(),
+                next_query: AtomicU32::new(0),
+                scopes: Mutex::new(Vec::new()),
+                readbacks,
+                next_readback: 0,
+                resolved: None,
+                scope_ids: AHashMap::new(),
+            }),
+            last_times: Vec::new(),
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.queries.is_some()
+    }
+
+    /// starts timing a pass, the scope isn't timed when the queries of this frame ran out
+    pub fn scope(&self, name: &'static str) -> GpuScope<'_> {
+        let query = self.queries.as_ref().and_then(|q| {
+            let index = q.next_query.fetch_add(2, Ordering::Relaxed);
+            if index + 2 > MAX_QUERIES {
+                return None;
+            }
+            q.scopes.lock().push((name, index));
+            Some((&q.query_set, index))
+        });
+
+        GpuScope { query }
+    }
+
+    /// the durations of the scopes of the last frame that was read back, in the order the scopes
+    /// ran on the GPU
+    pub fn last_times(&self) -> &[(&'static str, Duration)] {
+        &self.last_times
+    }
+
+    /// resolves the timestamps of this frame into a readback buffer, has to be submitted after all
+    /// other command buffers of the frame
+    pub(crate) fn resolve(&mut self, device: &Device) -> Option<CommandBuffer> {
+        let queries = self.queries.as_mut()?;
+        let count = queries
+            .next_query
+            .swap(0, Ordering::Relaxed)
+            .min(MAX_QUERIES);
+        let scopes = std::mem::take(queries.scopes.get_mut());
+
+        let readback = &mut queries.readbacks[queries.next_readback];
+        if scopes.is_empty() || readback.scopes.is_some() {
+            // the GPU is more than READBACK_FRAMES frames behind, this frame isn't timed
+            return None;
+        }
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("gpu_profiler_resolve_encoder"),
+        });
+        encoder.resolve_query_set(&queries.query_set, 0..count, &readback.buffer, 0);
+        readback.scopes = Some(scopes);
+        queries.resolved = Some(queries.next_readback);
+        queries.next_readback = (queries.next_readback + 1) % READBACK_FRAMES;
+
+        Some(encoder.finish())
+    }
+
+    /// maps the buffer resolved into this frame and reads back the buffers that finished mapping,
+    /// called after the frame is submitted
+    pub(crate) fn collect(&mut self, device: &Device) {
+        let queries = match &mut self.queries {
+            Some(q) => q,
+            None => return,
+        };
+
+        if let Some(index) = queries.resolved.take() {
+            let readback = &mut queries.readbacks[index];
+            let mapped = Arc::clone(&readback.mapped);
+            let future = readback.buffer.slice(..).map_async(MapMode::Read);
+            // the future completes while polling the device, only the flag is checked afterwards
+            IoTaskPool::get_or_init(TaskPool::default)
+                .spawn(async move {
+                    if future.await.is_ok() {
+                        mapped.store(true, Ordering::Release);
+                    }
+                })
+                .detach();
+        }
+
+        device.poll(Maintain::Poll);
+
+        let period = queries.timestamp_period as f64;
+        for readback in queries.readbacks.iter_mut() {
+            if !readback.mapped.load(Ordering::Acquire) {
+                continue;
+            }
+
+            let scopes = readback.scopes.take().unwrap_or_default();
+            let mut times = {
+                let data = readback.buffer.slice(..).get_mapped_range();
+                let timestamps: &[u64] = bytemuck::cast_slice(&data);
+                scopes
+                    .into_iter()
+                    .map(|(name, index)| {
+                        let (begin, end) =
+                            (timestamps[index as usize], timestamps[index as usize + 1]);
+                        let ticks = end.saturating_sub(begin) as f64;
+                        let begin_ns = begin as f64 * period;
+                        (
+                            name,
+                            begin_ns,
+                            Duration::from_nanos((ticks * period) as u64),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+            readback.buffer.unmap();
+            readback.mapped.store(false, Ordering::Release);
+
+            times.sort_by(|a, b| a.1.total_cmp(&b.1));
+            report_to_puffin(&mut queries.scope_ids, &times);
+            self.last_times = times
+                .into_iter()
+                .map(|(name, _, duration)| (name, duration))
+                .collect();
+        }
+    }
+}
+
+/// reports the scopes as the "GPU" thread of the current puffin frame, GPU time can't be related
+/// to CPU time, so the scopes end when they're reported and keep their distance to each other
+fn report_to_puffin(
+    scope_ids: &mut AHashMap<&'static str, ScopeId>,
+    times: &[(&'static str, f64, Duration)],
+) {
+    if !puffin::are_scopes_on() || times.is_empty() {
+        return;
+    }
+
+    let mut profiler = GlobalProfiler::lock();
+    let first_ns = times[0].1;
+    let last_ns = times
+        .iter()
+        .map(|(_, begin, duration)| begin + duration.as_nanos() as f64)
+        .fold(first_ns, f64::max);
+    let offset = puffin::now_ns() - (last_ns - first_ns) as i64;
+
+    let mut stream = Stream::default();
+    let mut range_ns = (i64::MAX, i64::MIN);
+    let mut previous_end = i64::MIN;
+    for (name, begin, duration) in times {
+        let id = *scope_ids.entry(name).or_insert_with(|| {
+            profiler.register_user_scopes(&[ScopeDetails::from_scope_name(*name)])[0]
+        });
+        // the scopes are on one queue, but rounding could make them overlap
+        let begin_ns = (offset + (begin - first_ns) as i64).max(previous_end);
+        let end_ns = begin_ns + duration.as_nanos() as i64;
+
+        let (start, _) = stream.begin_scope(|| begin_ns, id, "");
+        stream.end_scope(start, end_ns);
+        range_ns = (range_ns.0.min(begin_ns), range_ns.1.max(end_ns));
+        previous_end = end_ns;
+    }
+
+    let info = StreamInfo {
+        stream,
+        num_scopes: times.len(),
+        depth: 1,
+        range_ns,
+    };
+    profiler.report_user_scopes(
+        ThreadInfo {
+            start_time_ns: None,
+            name: "GPU".to_string(),
+        },
+        &info.as_stream_into_ref(),
+    );
+}
@@ -0,0 +1,81 @@
+use anyhow::{bail, Context, Result};
+use image::{DynamicImage, ImageFormat};
+
+/// A texture of palette indices, one byte per pixel, drawn with the colors of a `PaletteSwap`
+pub struct IndexedImage {
+    pub width: u32,
+    pub height: u32,
+    /// the index of every pixel, row by row
+    pub indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    /// loads an indexed PNG, or a greyscale image where the grey value of a pixel is its index
+    pub fn load(data: &[u8], format: Option<ImageFormat>) -> Result<Self> {
+        let format = match format {
+            Some(x) => x,
+            None => image::guess_format(data)?,
+        };
+        if format == ImageFormat::Png {
+            return Self::from_png(data);
+        }
+
+        match image::load_from_memory_with_format(data, format)? {
+            DynamicImage::ImageLuma8(image) => Ok(Self {
+                width: image.width(),
+                height: image.height(),
+                indices: image.into_raw(),
+            }),
+            DynamicImage::ImageLumaA8(image) => Ok(Self {
+                width: image.width(),
+                height: image.height(),
+                // the alpha comes from the palette
+                indices: image.pixels().map(|p| p.0[0]).collect(),
+            }),
+            image => bail!(
+                "Index maps have to be indexed PNGs or greyscale images, got {:?}",
+                image.color()
+            ),
+        }
+    }
+
+    /// the `image` crate turns the indices of a PNG into the colors of its palette, so PNGs are
+    /// decoded without any transformations instead
+    pub fn from_png(data: &[u8]) -> Result<Self> {
+        let mut decoder = png::Decoder::new(data);
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let (info, mut reader) = decoder.read_info().context("Invalid PNG file")?;
+
+        match info.color_type {
+            png::ColorType::Indexed | png::ColorType::Grayscale => (),
+            color_type => bail!(
+                "Index maps have to be indexed or greyscale PNGs, got {:?}",
+                color_type
+            ),
+        }
+        let bits = info.bit_depth as usize;
+        if bits > 8 {
+            bail!("Index maps can't be 16 bit, there are at most 256 colors in a palette");
+        }
+
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data)?;
+
+        // pixels under 8 bits are packed into the bytes of a row, the leftmost in the highest bits
+        let per_byte = 8 / bits;
+        let mask = ((1u16 << bits) - 1) as u8;
+        let mut indices = Vec::with_capacity(info.width as usize * info.height as usize);
+        for row in data.chunks(info.line_size) {
+            for x in 0..info.width as usize {
+                let shift = 8 - bits * (x % per_byte + 1);
+                indices.push((row[x / per_byte] >> shift) & mask);
+            }
+        }
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            indices,
+        })
+    }
+}
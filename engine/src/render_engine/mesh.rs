@@ -0,0 +1,123 @@
+use crate::asset_management::AssetLoader;
+use crate::render_engine::vertex::MeshVertex;
+use anyhow::{anyhow, bail, Result};
+use cgmath::Vector2;
+use serde::Deserialize;
+
+/// The vertices and indices of a mesh on the CPU, turned into a `Mesh2D` to draw it
+///
+/// Meshes can be stored in an archive as RON:
+/// ```ron
+/// (
+///     vertices: [
+///         (position: (0.0, 0.0), tex_coord: (0.0, 1.0), color: (1.0, 1.0, 1.0, 1.0)),
+///         ...
+///     ],
+///     indices: [0, 1, 2],
+/// )
+/// ```
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct MeshData {
+    pub vertices: Vec<MeshVertex>,
+    /// every three indices form a triangle
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn new(vertices: Vec<MeshVertex>, indices: Vec<u32>) -> Self {
+        Self { vertices, indices }
+    }
+
+    pub fn load(id: &str) -> Result<Self> {
+        let data = AssetLoader::get_asset(id)?;
+        let mesh: MeshData = ron::de::from_bytes(&data)?;
+        mesh.validate()?;
+        Ok(mesh)
+    }
+
+    /// triangulates a simple polygon, the texture is stretched over its bounding box
+    pub fn polygon(
+        outline: &[Vector2<f32>],
+        holes: &[Vec<Vector2<f32>>],
+        color: [f32; 4],
+    ) -> Result<Self> {
+        let indices = triangulate(outline, holes)?;
+
+        let points = outline.iter().chain(holes.iter().flatten());
+        let (mut min, mut max) = (outline[0], outline[0]);
+        for point in points.clone() {
+            min = Vector2::new(min.x.min(point.x), min.y.min(point.y));
+            max = Vector2::new(max.x.max(point.x), max.y.max(point.y));
+        }
+        let extent = Vector2::new(
+            (max.x - min.x).max(f32::EPSILON),
+            (max.y - min.y).max(f32::EPSILON),
+        );
+
+        let vertices = points
+            .map(|point| MeshVertex {
+                position: [point.x, point.y],
+                // v goes down the texture, y goes up
+                tex_coord: [
+                    (point.x - min.x) / extent.x,
+                    1.0 - (point.y - min.y) / extent.y,
+                ],
+                color,
+            })
+            .collect();
+
+        Ok(Self { vertices, indices })
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.indices.len() % 3 != 0 {
+            bail!(
+                "Mesh index count {} isn't a multiple of 3",
+                self.indices.len()
+            );
+        }
+
+        if let Some(index) = self
+            .indices
+            .iter()
+            .find(|i| **i as usize >= self.vertices.len())
+        {
+            bail!(
+                "Mesh index {} is out of bounds, there are {} vertices",
+                index,
+                self.vertices.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Triangulates a simple polygon with holes, the returned indices point into the outline
+/// followed by the points of every hole, in order
+///
+/// The outline and holes don't have to be closed, the last point connects to the first.
+pub fn triangulate(outline: &[Vector2<f32>], holes: &[Vec<Vector2<f32>>]) -> Result<Vec<u32>> {
+    if outline.len() < 3 {
+        bail!("A polygon needs at least 3 points, got {}", outline.len());
+    }
+
+    let mut coords =
+        Vec::with_capacity((outline.len() + holes.iter().map(Vec::len).sum::<usize>()) * 2);
+    let mut hole_indices = Vec::with_capacity(holes.len());
+
+    for point in outline {
+        coords.extend_from_slice(&[point.x as f64, point.y as f64]);
+    }
+
+    for hole in holes {
+        hole_indices.push(coords.len() / 2);
+        for point in hole {
+            coords.extend_from_slice(&[point.x as f64, point.y as f64]);
+        }
+    }
+
+    let indices = earcutr::earcut(&coords, &hole_indices, 2)
+        .map_err(|e| anyhow!("Failed to triangulate polygon: {}", e))?;
+    Ok(indices.into_iter().map(|i| i as u32).collect())
+}
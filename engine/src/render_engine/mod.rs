@@ -1,40 +1,66 @@
+pub mod api_trace;
 pub mod buffer;
 pub mod camera;
+pub mod capture;
 pub mod components;
+pub mod compressed_texture;
+pub mod display;
+pub mod dynamic_texture;
 pub(crate) mod ecs;
+pub mod frame_limiter;
+pub mod gizmos;
+pub mod gpu_profiler;
+pub mod indexed_texture;
+pub mod mesh;
+pub mod palettes;
+pub mod particles;
+pub mod render_graph;
 mod resources;
+pub mod skeleton;
+pub mod spatial_grid;
+pub mod static_bundles;
 mod systems;
 pub mod texture;
+pub mod tonemap;
 pub mod vertex;
 
 use bevy_ecs::system::Resource;
 use image::ImageFormat;
 use std::ops::DerefMut;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
 
 use crate::asset_management::AssetLoader;
 use crate::pipelines::Pipelines;
 use crate::scheduler::JobScheduler;
 use crate::ui::integration::{EguiIntegration, EguiRequestRedrawEvent};
 use crate::ui::DebugUi;
+use api_trace::ApiTrace;
 use buffer::{GpuIndexBuffer, GpuVertexBuffer};
 use camera::Camera;
-use log::warn;
+use display::DisplaySettings;
+use frame_limiter::FrameLimiter;
+use log::{error, info, warn};
 use parking_lot::{Mutex, RwLock};
 use pollster::block_on;
+use tonemap::Tonemapper;
 use vertex::Vertex2;
 use wgpu::*;
 use winit::dpi::PhysicalSize;
 use winit::event::Event;
-use winit::event_loop::EventLoop;
+use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::{Window, WindowId};
 
 const PUSH_CONSTANT_SIZE_LIMIT: u32 = 256;
 
+/// the MSAA sample count the engine starts with, if the adapter supports it
+pub const DEFAULT_MSAA_SAMPLES: u32 = 4;
+
 #[derive(Resource)]
 pub struct RenderEngineResources {
     window: Arc<Window>,
+    instance: Instance,
     surface: Surface,
     device: Arc<Device>,
     queue: Arc<Queue>,
@@ -44,43 +70,48 @@ pub struct RenderEngineResources {
     camera: Camera,
     sprite_square_vertex_buf: GpuVertexBuffer<Vertex2>,
     sprite_square_index_buf: GpuIndexBuffer<u16>,
-    idle_time: Duration,
+    frame_limiter: FrameLimiter,
     egui_integration: Arc<Mutex<EguiIntegration>>,
     egui_debug_ui: Arc<RwLock<DebugUi>>,
+    compute_supported: bool,
+    /// the multisampled target the passes draw into, resolved into the frame after every pass,
+    /// `None` without MSAA
+    msaa_target: Option<(Texture, TextureView)>,
+    /// the scene target of the frame and the pass tonemapping it into the surface
+    tonemapper: Tonemapper,
+    display: DisplaySettings,
+    supported_present_modes: Vec<PresentMode>,
+    supported_surface_formats: Vec<TextureFormat>,
+    supported_msaa_samples: Vec<u32>,
+    /// set by the error handler of the device and by surface errors, `ecs_recover_lost_device`
+    /// recreates the device when it's set
+    device_lost: Arc<AtomicBool>,
+    api_trace: ApiTrace,
 }
 
 impl RenderEngineResources {
-    pub fn new(window: Window, event_loop: &EventLoop<EguiRequestRedrawEvent>) -> Self {
+    /// records a wgpu API trace of the whole run into `wgpu_trace` when it's set
+    pub fn new(
+        window: Window,
+        event_loop: &EventLoop<EguiRequestRedrawEvent>,
+        wgpu_trace: Option<PathBuf>,
+    ) -> Self {
         let size = window.inner_size();
 
         // should make it work on linux, macos and windows
         // since vulkan works on linux and windows, and metal on macos
         let instance = Instance::new(Backends::VULKAN | Backends::METAL);
         let surface = unsafe { instance.create_surface(&window) };
-        let adapter = block_on(instance.request_adapter(&RequestAdapterOptions {
-            power_preference: PowerPreference::HighPerformance,
-            force_fallback_adapter: false,
-            compatible_surface: Some(&surface),
-        }))
-        .unwrap();
-
-        let mut limits = Limits::default();
-        limits.max_push_constant_size = PUSH_CONSTANT_SIZE_LIMIT;
-
-        let mut features = Features::empty();
-        features.set(Features::PUSH_CONSTANTS, true);
-
-        let (device, queue) = block_on(adapter.request_device(
-            &DeviceDescriptor {
-                label: None,
-                features,
-                limits,
-            },
-            None,
-        ))
-        .unwrap();
-
-        let (device, queue) = (Arc::new(device), Arc::new(queue));
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let mut api_trace = ApiTrace::new(wgpu_trace);
+        let trace_dir = api_trace.pending_dir();
+        let (adapter, device, queue) =
+            request_device(&instance, &surface, &device_lost, trace_dir.as_deref()).unwrap();
+        api_trace.commit_pending(trace_dir);
+        let compute_supported = adapter
+            .get_downlevel_properties()
+            .flags
+            .contains(DownlevelFlags::COMPUTE_SHADERS);
 
         JobScheduler::init_device_queue(Arc::clone(&device), Arc::clone(&queue));
         // TODO: use propper amount of CPU cores
@@ -89,16 +120,41 @@ impl RenderEngineResources {
         AssetLoader::set_tex_placeholder(&device, &queue, "placeholder-32.png", ImageFormat::Png)
             .unwrap();
 
+        let supported_present_modes = display::supported_present_modes(adapter.get_info().backend);
+        let supported_surface_formats =
+            display::supported_surface_formats(surface_format(&surface, &adapter).unwrap());
+        let supported_msaa_samples = display::supported_msaa_samples(&adapter);
+        let msaa_samples = if supported_msaa_samples.contains(&DEFAULT_MSAA_SAMPLES) {
+            DEFAULT_MSAA_SAMPLES
+        } else {
+            1
+        };
+        let display = DisplaySettings::new(size, supported_surface_formats[0], msaa_samples);
+        window.set_title(&display.title);
+
         let config = SurfaceConfiguration {
+            // Metal and GL surfaces can only be drawn into, screenshots are copied out of the
+            // capture target of FrameCapture instead
             usage: TextureUsages::RENDER_ATTACHMENT,
-            format: surface.get_preferred_format(&adapter).unwrap(),
+            format: display.surface_format,
             width: size.width,
             height: size.height,
-            present_mode: PresentMode::Fifo,
+            present_mode: display.present_mode,
         };
         surface.configure(&device, &config);
 
-        let pipelines = Pipelines::new(config.format);
+        let tonemapper = Tonemapper::new(&device, size, config.format, display.color);
+        let pipelines = Pipelines::new(
+            tonemapper.scene_format(),
+            display.msaa_samples,
+            compute_supported,
+        );
+        let msaa_target = create_msaa_target(
+            &device,
+            &config,
+            tonemapper.scene_format(),
+            display.msaa_samples,
+        );
 
         let sprite_vertex_buf = GpuVertexBuffer::new(&device, &vertex::SQUARE, Some("Square VB"));
         let sprite_index_buf = GpuIndexBuffer::new(&device, &[0, 1, 2, 0, 2, 3], Some("Square IB"));
@@ -108,6 +164,7 @@ impl RenderEngineResources {
 
         Self {
             window: Arc::new(window),
+            instance,
             surface,
             device,
             queue,
@@ -119,12 +176,22 @@ impl RenderEngineResources {
             egui_debug_ui: Arc::new(RwLock::new(DebugUi::default())),
             sprite_square_vertex_buf: sprite_vertex_buf,
             sprite_square_index_buf: sprite_index_buf,
-            idle_time: Duration::new(0, 0),
+            frame_limiter: FrameLimiter::default(),
+            compute_supported,
+            msaa_target,
+            tonemapper,
+            display,
+            supported_present_modes,
+            supported_surface_formats,
+            supported_msaa_samples,
+            device_lost,
+            api_trace,
         }
     }
 
     pub fn process_event(&mut self, event: &Event<EguiRequestRedrawEvent>) {
         self.egui_integration.lock().process_event(event);
+        self.frame_limiter.process_event(event);
     }
 
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
@@ -133,6 +200,13 @@ impl RenderEngineResources {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.reconfigure_surface();
+            self.tonemapper.resize(&self.device, new_size);
+            self.msaa_target = create_msaa_target(
+                &self.device,
+                &self.config,
+                self.tonemapper.scene_format(),
+                self.display.msaa_samples,
+            );
             self.camera.window_resize(new_size);
         } else {
             warn!("Attempt to resize window to a size where x = 0 or where y = 0");
@@ -158,15 +232,383 @@ impl RenderEngineResources {
         &self.camera
     }
 
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
     pub fn device(&self) -> &Device {
         &self.device
     }
 
+    /// whether the adapter supports compute shaders, some systems fall back to the CPU if it doesn't
+    pub fn compute_supported(&self) -> bool {
+        self.compute_supported
+    }
+
+    pub fn msaa_samples(&self) -> u32 {
+        self.display.msaa_samples
+    }
+
+    pub fn display_settings(&self) -> &DisplaySettings {
+        &self.display
+    }
+
+    pub fn supported_present_modes(&self) -> &[PresentMode] {
+        &self.supported_present_modes
+    }
+
+    pub fn supported_surface_formats(&self) -> &[TextureFormat] {
+        &self.supported_surface_formats
+    }
+
+    pub fn supported_msaa_samples(&self) -> &[u32] {
+        &self.supported_msaa_samples
+    }
+
+    /// the format of the target the passes draw the scene into, it's linear and tonemapped into
+    /// the surface at the end of the frame
+    pub fn scene_format(&self) -> TextureFormat {
+        self.tonemapper.scene_format()
+    }
+
+    /// applies the settings to the window and reconfigures the surface if the present mode or
+    /// format changed, nothing changes if the settings are invalid
+    ///
+    /// Changing `hdr` or the MSAA sample count rebuilds the render pipelines.
+    pub fn apply_display_settings(&mut self, settings: DisplaySettings) -> anyhow::Result<()> {
+        if !self
+            .supported_present_modes
+            .contains(&settings.present_mode)
+        {
+            anyhow::bail!(
+                "The surface doesn't support {:?}, the supported present modes are {:?}",
+                settings.present_mode,
+                self.supported_present_modes
+            );
+        }
+        if !self
+            .supported_surface_formats
+            .contains(&settings.surface_format)
+        {
+            anyhow::bail!(
+                "The surface doesn't support {:?}, the supported formats are {:?}",
+                settings.surface_format,
+                self.supported_surface_formats
+            );
+        }
+        if !self.supported_msaa_samples.contains(&settings.msaa_samples) {
+            anyhow::bail!(
+                "The adapter doesn't support {} MSAA samples, the supported sample counts are {:?}",
+                settings.msaa_samples,
+                self.supported_msaa_samples
+            );
+        }
+        if !settings.color.exposure.is_finite() || settings.color.exposure <= 0.0 {
+            anyhow::bail!(
+                "The exposure has to be positive, got {}",
+                settings.color.exposure
+            );
+        }
+
+        puffin::profile_function!();
+        display::apply_to_window(&self.window, &settings)?;
+        if settings.present_mode != self.config.present_mode
+            || settings.surface_format != self.config.format
+        {
+            self.config.present_mode = settings.present_mode;
+            self.config.format = settings.surface_format;
+            self.reconfigure_surface();
+            self.egui_integration
+                .lock()
+                .recreate_render_pass(&self.device, &self.config);
+        }
+        self.apply_scene_settings(&settings);
+        self.display = settings;
+        Ok(())
+    }
+
+    /// reconfigures the tonemapper if the colors changed, and rebuilds the render pipelines and
+    /// the MSAA target if the scene format or the sample count changed
+    fn apply_scene_settings(&mut self, settings: &DisplaySettings) {
+        let old_format = self.tonemapper.scene_format();
+        if settings.color != self.display.color
+            || settings.surface_format != self.display.surface_format
+        {
+            self.tonemapper.reconfigure(
+                &self.device,
+                &self.queue,
+                self.config.format,
+                settings.color,
+            );
+        }
+        if self.tonemapper.scene_format() != old_format
+            || settings.msaa_samples != self.display.msaa_samples
+        {
+            self.pipelines
+                .rebuild_render_pipelines(self.tonemapper.scene_format(), settings.msaa_samples);
+            self.msaa_target = create_msaa_target(
+                &self.device,
+                &self.config,
+                self.tonemapper.scene_format(),
+                settings.msaa_samples,
+            );
+        }
+    }
+
+    /// the attachment for passes drawing on top of the frame, with MSAA they draw into the
+    /// multisampled target and resolve it into the frame
+    pub fn frame_color_attachment<'a>(
+        &'a self,
+        frame_view: &'a TextureView,
+    ) -> RenderPassColorAttachment<'a> {
+        let (view, resolve_target) = match &self.msaa_target {
+            Some((_, msaa_view)) => (msaa_view, Some(frame_view)),
+            None => (frame_view, None),
+        };
+
+        RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: Operations {
+                load: LoadOp::Load,
+                store: true,
+            },
+        }
+    }
+
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn mark_device_lost(&self) {
+        self.device_lost.store(true, Ordering::Relaxed);
+    }
+
+    /// whether `ecs_recover_lost_device` has to recreate the device, because it was lost or to
+    /// start or stop a wgpu trace
+    pub(crate) fn needs_new_device(&self) -> bool {
+        self.is_device_lost() || self.api_trace.is_pending()
+    }
+
+    pub fn api_trace(&self) -> &ApiTrace {
+        &self.api_trace
+    }
+
+    /// arming a trace recreates the device before the next frame
+    pub fn api_trace_mut(&mut self) -> &mut ApiTrace {
+        &mut self.api_trace
+    }
+
+    /// creates a new surface, device and queue after the device was lost or a trace started or
+    /// stopped, and everything in the engine and the texture cache living on the old device, the
+    /// resources in the world are recreated by `ecs_recover_lost_device`
+    pub(crate) fn recover_device(&mut self) -> anyhow::Result<()> {
+        puffin::profile_function!();
+        let surface = unsafe { self.instance.create_surface(self.window.as_ref()) };
+        // the pending trace only starts or stops once there's a device recording it
+        let trace_dir = self.api_trace.pending_dir();
+        let (adapter, device, queue) = request_device(
+            &self.instance,
+            &surface,
+            &self.device_lost,
+            trace_dir.as_deref(),
+        )?;
+        self.api_trace.commit_pending(trace_dir);
+        info!("Recreated the device on {}", adapter.get_info().name);
+
+        self.surface = surface;
+        self.device = device;
+        self.queue = queue;
+        self.compute_supported = adapter
+            .get_downlevel_properties()
+            .flags
+            .contains(DownlevelFlags::COMPUTE_SHADERS);
+        self.supported_present_modes = display::supported_present_modes(adapter.get_info().backend);
+        if !self
+            .supported_present_modes
+            .contains(&self.config.present_mode)
+        {
+            self.config.present_mode = PresentMode::Fifo;
+            self.display.present_mode = PresentMode::Fifo;
+        }
+        // the new adapter might be another one, with other surface formats
+        self.supported_surface_formats =
+            display::supported_surface_formats(surface_format(&self.surface, &adapter)?);
+        if !self.supported_surface_formats.contains(&self.config.format) {
+            self.config.format = self.supported_surface_formats[0];
+            self.display.surface_format = self.config.format;
+        }
+        self.supported_msaa_samples = display::supported_msaa_samples(&adapter);
+        if !self
+            .supported_msaa_samples
+            .contains(&self.display.msaa_samples)
+        {
+            self.display.msaa_samples = 1;
+        }
+        self.reconfigure_surface();
+
+        JobScheduler::init_device_queue(Arc::clone(&self.device), Arc::clone(&self.queue));
+        crate::pipelines::mipmap::clear_cache();
+        let generated = AssetLoader::reupload_textures(&self.device, &self.queue);
+        if !generated.is_empty() {
+            warn!(
+                "{} generated textures weren't uploaded again, they have to be recreated",
+                generated.len()
+            );
+        }
+
+        self.tonemapper = Tonemapper::new(
+            &self.device,
+            self.size,
+            self.config.format,
+            self.display.color,
+        );
+        self.pipelines = Pipelines::new(
+            self.tonemapper.scene_format(),
+            self.display.msaa_samples,
+            self.compute_supported,
+        );
+        self.msaa_target = create_msaa_target(
+            &self.device,
+            &self.config,
+            self.tonemapper.scene_format(),
+            self.display.msaa_samples,
+        );
+        self.camera.recreate(&self.device);
+        self.sprite_square_vertex_buf =
+            GpuVertexBuffer::new(&self.device, &vertex::SQUARE, Some("Square VB"));
+        self.sprite_square_index_buf =
+            GpuIndexBuffer::new(&self.device, &[0, 1, 2, 0, 2, 3], Some("Square IB"));
+        self.egui_integration
+            .lock()
+            .recreate_render_pass(&self.device, &self.config);
+        self.egui_debug_ui.write().cache_window_mut().clear();
+
+        self.device_lost.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
     pub fn window_id(&self) -> WindowId {
         self.window.id()
     }
 
+    /// asks for a frame to be drawn, in reactive mode frames are only drawn when asked for
     pub fn request_redraw(&self) {
-        self.window.request_redraw();
+        self.frame_limiter.request_redraw();
+    }
+
+    pub fn frame_limiter(&self) -> &FrameLimiter {
+        &self.frame_limiter
+    }
+
+    pub fn frame_limiter_mut(&mut self) -> &mut FrameLimiter {
+        &mut self.frame_limiter
     }
+
+    /// requests a redraw from the window when the frame limiter says the next frame is due,
+    /// returns how the event loop should wait until it's polled again
+    pub fn poll_frame_limiter(&mut self) -> ControlFlow {
+        let (redraw, control_flow) = self.frame_limiter.poll();
+        if redraw {
+            self.window.request_redraw();
+        }
+        control_flow
+    }
+}
+
+fn request_device(
+    instance: &Instance,
+    surface: &Surface,
+    device_lost: &Arc<AtomicBool>,
+    trace_path: Option<&Path>,
+) -> anyhow::Result<(Adapter, Arc<Device>, Arc<Queue>)> {
+    let adapter = block_on(instance.request_adapter(&RequestAdapterOptions {
+        power_preference: PowerPreference::HighPerformance,
+        force_fallback_adapter: false,
+        compatible_surface: Some(surface),
+    }))
+    .ok_or_else(|| anyhow::anyhow!("No adapter compatible with the surface"))?;
+
+    let mut limits = Limits::default();
+    limits.max_push_constant_size = PUSH_CONSTANT_SIZE_LIMIT;
+
+    let mut features = Features::empty();
+    features.set(Features::PUSH_CONSTANTS, true);
+    // compressed textures are uploaded as is when the adapter supports their format
+    features |= adapter.features()
+        & (Features::TEXTURE_COMPRESSION_BC
+            | Features::TEXTURE_COMPRESSION_ETC2
+            | Features::TEXTURE_COMPRESSION_ASTC_LDR);
+    // the passes are timed on the GPU when the adapter supports it
+    features |= adapter.features() & Features::TIMESTAMP_QUERY;
+
+    let (device, queue) = block_on(adapter.request_device(
+        &DeviceDescriptor {
+            label: None,
+            features,
+            limits,
+        },
+        trace_path,
+    ))?;
+
+    // wgpu reports a lost device through the errors of the calls made on it, other errors only
+    // invalidate what the call created, so they're logged
+    let lost = Arc::clone(device_lost);
+    device.on_uncaptured_error(move |e| {
+        if matches!(e, Error::OutOfMemory { .. }) || is_device_lost(&e) {
+            if !lost.swap(true, Ordering::Relaxed) {
+                error!("{}, recreating the device", e);
+            }
+        } else {
+            error!("wgpu error: {}", e);
+        }
+    });
+
+    Ok((adapter, Arc::new(device), Arc::new(queue)))
+}
+
+/// whether the error was caused by the device being lost, wgpu only reports it as the source of
+/// the errors of the calls made on the device
+fn is_device_lost(error: &Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(e) = source {
+        if let Some(wgpu_core::device::DeviceError::Lost) = e.downcast_ref() {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+fn surface_format(surface: &Surface, adapter: &Adapter) -> anyhow::Result<TextureFormat> {
+    surface
+        .get_preferred_format(adapter)
+        .ok_or_else(|| anyhow::anyhow!("The surface isn't compatible with the adapter"))
+}
+
+fn create_msaa_target(
+    device: &Device,
+    config: &SurfaceConfiguration,
+    format: TextureFormat,
+    samples: u32,
+) -> Option<(Texture, TextureView)> {
+    if samples <= 1 {
+        return None;
+    }
+
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("MSAA target TEX"),
+        size: Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: samples,
+        dimension: TextureDimension::D2,
+        format,
+        usage: TextureUsages::RENDER_ATTACHMENT,
+    });
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    Some((texture, view))
 }
@@ -0,0 +1,47 @@
+use crate::asset_management::{AssetLoader, GpuTextureRef};
+use ahash::AHashMap;
+use anyhow::Result;
+use bevy_ecs::system::Resource;
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+/// The palette textures `PaletteSwap`s refer to by name, replacing the texture of a name recolors
+/// every sprite using it from the next frame on
+///
+/// Every row of a palette texture is a palette, with the color of index `i` in column `i`.
+/// Cloning is cheap, every clone shares the same palettes, which is how Lua scripts swap them.
+#[derive(Resource, Clone, Default)]
+pub struct Palettes {
+    palettes: Arc<RwLock<AHashMap<String, GpuTextureRef>>>,
+}
+
+impl Palettes {
+    /// returns the texture the name had before
+    pub fn insert(&self, name: &str, texture: GpuTextureRef) -> Option<GpuTextureRef> {
+        self.palettes.write().insert(name.to_string(), texture)
+    }
+
+    /// loads the texture in the background, the sprites use the placeholder texture as their
+    /// palette until it's loaded
+    pub fn load(&self, name: &str, id: &str) -> Result<()> {
+        let texture = AssetLoader::load_texture(id)?;
+        self.insert(name, texture);
+        Ok(())
+    }
+
+    pub fn remove(&self, name: &str) -> Option<GpuTextureRef> {
+        self.palettes.write().remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<GpuTextureRef> {
+        self.palettes.read().get(name).cloned()
+    }
+
+    /// the amount of palettes in the texture of the name
+    pub fn rows(&self, name: &str) -> Option<u32> {
+        self.palettes
+            .read()
+            .get(name)
+            .map(|texture| texture.load().size().height)
+    }
+}
@@ -0,0 +1,323 @@
+use crate::asset_management::ToUuid;
+use crate::pipelines::particles::build_bind_group_layout;
+use crate::render_engine::buffer::{GpuBuffer, GpuUniformBuffer, Uniform};
+use crate::render_engine::components::particle_emitter::ParticleEmitter;
+use crate::render_engine::vertex::SpriteInstance;
+use crate::scheduler::{Job, JobFrequency, JobStateTracker};
+use bevy_ecs::component::Component;
+use bevy_ecs::system::Resource;
+use bytemuck::Zeroable;
+use parking_lot::Mutex;
+use std::mem::{size_of, swap};
+use std::sync::Arc;
+use wgpu::*;
+
+/// the amount of samples the lifetime curves are baked into, keep in sync with particles.wgsl
+pub const CURVE_SAMPLES: usize = 16;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuParticle {
+    /// xy = position, z = age, w = lifetime
+    pub position: [f32; 4],
+    /// xy = velocity, z = z layer, w = 1.0 when alive
+    pub velocity: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct EmitterUniform {
+    /// xy = acceleration, z = delta time in seconds, w = amount of particles
+    pub acceleration: [f32; 4],
+    /// x = columns, y = rows, z = frames per second, w = frame count
+    pub animation: [f32; 4],
+    pub color_curve: [[f32; 4]; CURVE_SAMPLES],
+    /// packed 4 samples per entry
+    pub size_curve: [[f32; 4]; CURVE_SAMPLES / 4],
+}
+
+impl EmitterUniform {
+    pub fn new(emitter: &ParticleEmitter, dt: f32) -> Self {
+        let mut uniform = Self::zeroed();
+        uniform.acceleration = [
+            emitter.acceleration.x,
+            emitter.acceleration.y,
+            dt,
+            emitter.max_particles as f32,
+        ];
+        uniform.animation = match emitter.animation {
+            Some(anim) => [
+                anim.columns.max(1) as f32,
+                anim.rows.max(1) as f32,
+                anim.fps,
+                anim.frame_count as f32,
+            ],
+            None => [1.0, 1.0, 0.0, 1.0],
+        };
+
+        for i in 0..CURVE_SAMPLES {
+            let t = i as f32 / (CURVE_SAMPLES - 1) as f32;
+            uniform.color_curve[i] = emitter.color_over_lifetime.sample(t);
+            uniform.size_curve[i / 4][i % 4] = emitter.size_over_lifetime.sample(t);
+        }
+
+        uniform
+    }
+
+    fn sample_color(&self, t: f32) -> [f32; 4] {
+        let (i, j, f) = curve_indices(t);
+        let (a, b) = (self.color_curve[i], self.color_curve[j]);
+        [
+            a[0] + (b[0] - a[0]) * f,
+            a[1] + (b[1] - a[1]) * f,
+            a[2] + (b[2] - a[2]) * f,
+            a[3] + (b[3] - a[3]) * f,
+        ]
+    }
+
+    fn sample_size(&self, t: f32) -> f32 {
+        let (i, j, f) = curve_indices(t);
+        let (a, b) = (self.size_curve[i / 4][i % 4], self.size_curve[j / 4][j % 4]);
+        a + (b - a) * f
+    }
+}
+
+fn curve_indices(t: f32) -> (usize, usize, f32) {
+    let x = t.clamp(0.0, 1.0) * (CURVE_SAMPLES - 1) as f32;
+    let i = x.floor() as usize;
+    (i, (i + 1).min(CURVE_SAMPLES - 1), x.fract())
+}
+
+impl Uniform for EmitterUniform {
+    fn bind_group_layout_entry() -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }
+    }
+}
+
+/// The GPU side of a `ParticleEmitter`, inserted automatically by `simulate_particles`
+#[derive(Component)]
+pub struct ParticleBuffers {
+    capacity: u32,
+    particles: GpuBuffer,
+    instances: Arc<GpuBuffer>,
+    uniform: GpuUniformBuffer<EmitterUniform>,
+    /// only present when the particles are simulated in a compute shader
+    bind_group: Option<BindGroup>,
+    /// only used when the particles are simulated on the CPU
+    cpu_particles: Arc<Mutex<Vec<GpuParticle>>>,
+}
+
+impl ParticleBuffers {
+    pub fn new(device: &Device, capacity: u32, compute: bool) -> Self {
+        let particles = GpuBuffer::new_with_data(
+            device,
+            &vec![GpuParticle::zeroed(); capacity as usize],
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            Some("Particles SB"),
+        );
+        let instances = GpuBuffer::new_with_data(
+            device,
+            &vec![SpriteInstance::HIDDEN; capacity as usize],
+            BufferUsages::STORAGE | BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            Some("Particle instances VB"),
+        );
+        let uniform = GpuUniformBuffer::new(
+            device,
+            &[EmitterUniform::zeroed()],
+            Some("Particle emitter UB"),
+        );
+
+        let bind_group = if compute {
+            Some(device.create_bind_group(&BindGroupDescriptor {
+                layout: &build_bind_group_layout(device, "Particle emitter"),
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: uniform.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: particles.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: instances.as_entire_binding(),
+                    },
+                ],
+                label: Some("Particle emitter BG"),
+            }))
+        } else {
+            None
+        };
+
+        Self {
+            capacity,
+            particles,
+            instances: Arc::new(instances),
+            uniform,
+            bind_group,
+            cpu_particles: Arc::new(Mutex::new(vec![GpuParticle::zeroed(); capacity as usize])),
+        }
+    }
+
+    /// creates the buffers on a new device, the particles simulated on the CPU are kept, the ones
+    /// simulated on the GPU were in the buffers of the old device
+    pub(crate) fn recreate(&self, device: &Device, compute: bool) -> Self {
+        let mut buffers = Self::new(device, self.capacity, compute);
+        if self.bind_group.is_none() && !compute {
+            buffers.cpu_particles = Arc::clone(&self.cpu_particles);
+        }
+        buffers
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn instances(&self) -> &GpuBuffer {
+        &self.instances
+    }
+
+    /// writes the newly spawned particles and the emitter parameters, then records the compute pass
+    pub fn simulate_gpu(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        pipeline: &ComputePipeline,
+        uniform: EmitterUniform,
+        spawned: &[(u32, GpuParticle)],
+    ) {
+        let bind_group = self
+            .bind_group
+            .as_ref()
+            .expect("ParticleBuffers created without compute support");
+
+        for (slot, particle) in spawned {
+            queue.write_buffer(
+                &self.particles,
+                *slot as BufferAddress * size_of::<GpuParticle>() as BufferAddress,
+                bytemuck::bytes_of(particle),
+            );
+        }
+        self.uniform.update(queue, &[uniform]);
+
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("Particle simulation pass"),
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        let workgroup_size = crate::pipelines::particles::WORKGROUP_SIZE;
+        pass.dispatch((self.capacity + workgroup_size - 1) / workgroup_size, 1, 1);
+    }
+
+    /// submits a job that simulates the particles on a worker thread
+    pub fn simulate_cpu(
+        &self,
+        uniform: EmitterUniform,
+        spawned: Vec<(u32, GpuParticle)>,
+    ) -> JobStateTracker {
+        crate::scheduler::JobScheduler::submit(Box::new(ParticleSimulationJob {
+            particles: Arc::clone(&self.cpu_particles),
+            instances: Arc::clone(&self.instances),
+            uniform,
+            spawned,
+        }))
+    }
+}
+
+/// The jobs simulating particles on the CPU this frame, they are waited on before drawing
+#[derive(Resource, Default)]
+pub struct ParticleJobs {
+    inner: Mutex<Vec<JobStateTracker>>,
+}
+
+impl ParticleJobs {
+    pub fn push(&self, tracker: JobStateTracker) {
+        self.inner.lock().push(tracker);
+    }
+
+    pub fn take(&self) -> Vec<JobStateTracker> {
+        let mut jobs = Vec::new();
+        swap(&mut *self.inner.lock(), &mut jobs);
+        jobs
+    }
+}
+
+pub struct ParticleSimulationJob {
+    particles: Arc<Mutex<Vec<GpuParticle>>>,
+    instances: Arc<GpuBuffer>,
+    uniform: EmitterUniform,
+    spawned: Vec<(u32, GpuParticle)>,
+}
+
+impl ToUuid for ParticleSimulationJob {}
+
+impl Job for ParticleSimulationJob {
+    fn get_freq(&self) -> JobFrequency {
+        JobFrequency::Frame
+    }
+
+    fn run(&mut self, _: &Device, queue: &Queue) -> anyhow::Result<()> {
+        puffin::profile_scope!("ParticleSimulationJob");
+        let mut particles = self.particles.lock();
+        for (slot, particle) in self.spawned.iter() {
+            particles[*slot as usize] = *particle;
+        }
+
+        let instances = simulate(&self.uniform, &mut particles);
+        queue.write_buffer(&self.instances, 0, bytemuck::cast_slice(&instances));
+        Ok(())
+    }
+}
+
+/// the CPU version of particles.wgsl
+pub fn simulate(uniform: &EmitterUniform, particles: &mut [GpuParticle]) -> Vec<SpriteInstance> {
+    let dt = uniform.acceleration[2];
+    let [columns, rows, fps, frame_count] = uniform.animation;
+
+    particles
+        .iter_mut()
+        .map(|p| {
+            if p.velocity[3] <= 0.0 {
+                return SpriteInstance::HIDDEN;
+            }
+
+            p.position[2] += dt;
+            if p.position[2] >= p.position[3] {
+                p.velocity[3] = 0.0;
+                return SpriteInstance::HIDDEN;
+            }
+
+            p.velocity[0] += uniform.acceleration[0] * dt;
+            p.velocity[1] += uniform.acceleration[1] * dt;
+            p.position[0] += p.velocity[0] * dt;
+            p.position[1] += p.velocity[1] * dt;
+
+            let t = p.position[2] / p.position[3];
+            let size = uniform.sample_size(t);
+            let frame = (p.position[2] * fps).floor() % frame_count.max(1.0);
+            let scale = [1.0 / columns, 1.0 / rows];
+
+            SpriteInstance {
+                position: [p.position[0], p.position[1], p.velocity[2], 0.0],
+                size: [size, size, 0.0, 0.0],
+                color: uniform.sample_color(t),
+                uv_rect: [
+                    (frame % columns) * scale[0],
+                    (frame / columns).floor() * scale[1],
+                    scale[0],
+                    scale[1],
+                ],
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,656 @@
+use ahash::{AHashMap, AHashSet};
+use anyhow::{bail, Result};
+use bevy_ecs::prelude::*;
+use bevy_ecs::schedule::SystemConfigs;
+use log::debug;
+use std::collections::BTreeSet;
+use wgpu::{
+    Device, Extent3d, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureView, TextureViewDescriptor,
+};
+use winit::dpi::PhysicalSize;
+
+/// The slots of the engine's own nodes
+pub mod slots {
+    /// the linear scene target of the frame, imported from `FrameResources`
+    pub const FRAME: &str = "frame";
+    /// the surface texture of the frame, or the capture target of `FrameCapture` while the frame
+    /// is captured, the scene is tonemapped into it before the UI is drawn
+    pub const OUTPUT: &str = "output";
+    /// the normals of the sprites, imported from `LightingResources`
+    pub const NORMALS: &str = "normals";
+}
+
+/// Every node of the render graph is in this set, anything that has to run before or after all
+/// the passes of a frame can be ordered against it
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RenderGraphSet;
+
+/// The set of the systems of a single node, named after the node
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RenderNodeLabel(pub &'static str);
+
+/// A pass in the render graph, the systems of the node encode the pass and push it to the
+/// `CommandBufferCollector`
+///
+/// The order of the nodes follows from the slots they read and write: a node reading a slot runs
+/// after the node that last wrote it, and nodes writing the same slot draw in the order they were
+/// added to the graph. `after` and `before` move a node relative to others, to put a pass
+/// between two of the engine's own ones for example.
+pub struct RenderNode {
+    name: &'static str,
+    systems: Option<SystemConfigs>,
+    reads: Vec<&'static str>,
+    writes: Vec<&'static str>,
+    after: Vec<&'static str>,
+    before: Vec<&'static str>,
+}
+
+impl RenderNode {
+    pub fn new<M>(name: &'static str, systems: impl IntoSystemConfigs<M>) -> Self {
+        Self {
+            name,
+            systems: Some(systems.into_configs()),
+            reads: Vec::new(),
+            writes: Vec::new(),
+            after: Vec::new(),
+            before: Vec::new(),
+        }
+    }
+
+    pub fn reads(mut self, slot: &'static str) -> Self {
+        self.reads.push(slot);
+        self
+    }
+
+    pub fn writes(mut self, slot: &'static str) -> Self {
+        self.writes.push(slot);
+        self
+    }
+
+    pub fn after(mut self, node: &'static str) -> Self {
+        self.after.push(node);
+        self
+    }
+
+    pub fn before(mut self, node: &'static str) -> Self {
+        self.before.push(node);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn uses(&self, slot: &str) -> bool {
+        self.reads.contains(&slot) || self.writes.contains(&slot)
+    }
+}
+
+/// Describes a texture the graph allocates for its nodes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TargetDescriptor {
+    pub format: TextureFormat,
+    /// the size relative to the surface, 0.5 for a half resolution target
+    pub scale: f32,
+    pub usage: TextureUsages,
+}
+
+impl TargetDescriptor {
+    pub fn new(format: TextureFormat) -> Self {
+        Self {
+            format,
+            scale: 1.0,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_usage(mut self, usage: TextureUsages) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    fn size(&self, surface: PhysicalSize<u32>) -> Extent3d {
+        Extent3d {
+            width: ((surface.width as f32 * self.scale) as u32).max(1),
+            height: ((surface.height as f32 * self.scale) as u32).max(1),
+            depth_or_array_layers: 1,
+        }
+    }
+}
+
+/// The passes of a frame and the textures passed between them
+///
+/// The graph is built into the render schedule before the first frame, so the nodes and targets
+/// have to be added during startup, game code can add its own next to the ones added in
+/// `insert_renderer_systems_in_schedule`.
+#[derive(Default)]
+pub struct RenderGraph {
+    nodes: Vec<RenderNode>,
+    /// slots backed by a texture that something other than the graph owns
+    imported: Vec<&'static str>,
+    /// slots the graph allocates a texture for, only while a node uses them
+    targets: Vec<(&'static str, TargetDescriptor)>,
+    built: bool,
+}
+
+impl RenderGraph {
+    pub fn import(&mut self, slot: &'static str) {
+        self.assert_not_built();
+        self.imported.push(slot);
+    }
+
+    pub fn add_target(&mut self, slot: &'static str, descriptor: TargetDescriptor) {
+        self.assert_not_built();
+        self.targets.push((slot, descriptor));
+    }
+
+    pub fn add_node(&mut self, node: RenderNode) {
+        self.assert_not_built();
+        self.nodes.push(node);
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.built
+    }
+
+    /// the names of the nodes sorted by their dependencies, nodes that don't depend on each
+    /// other can still run in parallel
+    pub fn node_order(&self) -> Result<Vec<&'static str>> {
+        Ok(self
+            .order()?
+            .into_iter()
+            .map(|i| self.nodes[i].name)
+            .collect())
+    }
+
+    /// adds the systems of the nodes to the schedule, ordered by the dependencies between the
+    /// nodes, and inserts the `RenderTargets` with the textures the transient slots share
+    pub fn build(&mut self, world: &mut World, schedule: &mut Schedule) -> Result<()> {
+        self.assert_not_built();
+        self.validate()?;
+        let order = self.order()?;
+        let edges = self.edges(&order)?;
+        let targets = self.allocate_targets(&order, &edges);
+
+        debug!(
+            "Render graph order: {:?}",
+            order
+                .iter()
+                .map(|i| self.nodes[*i].name)
+                .collect::<Vec<_>>()
+        );
+
+        for node in self.nodes.iter_mut() {
+            if let Some(systems) = node.systems.take() {
+                schedule.add_systems(
+                    systems
+                        .in_set(RenderNodeLabel(node.name))
+                        .in_set(RenderGraphSet),
+                );
+            }
+        }
+        for (from, to) in edges {
+            schedule.configure_sets(
+                RenderNodeLabel(self.nodes[to].name).after(RenderNodeLabel(self.nodes[from].name)),
+            );
+        }
+
+        world.insert_resource(targets);
+        self.built = true;
+        Ok(())
+    }
+
+    fn assert_not_built(&self) {
+        assert!(
+            !self.built,
+            "The render graph can't be changed after it has been built"
+        );
+    }
+
+    fn node_index(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|n| n.name == name)
+    }
+
+    fn is_slot(&self, slot: &str) -> bool {
+        self.imported.contains(&slot) || self.targets.iter().any(|(s, _)| *s == slot)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut names = AHashSet::new();
+        for node in self.nodes.iter() {
+            if !names.insert(node.name) {
+                bail!("There are multiple render nodes named '{}'", node.name);
+            }
+        }
+
+        let mut slots = AHashSet::new();
+        for slot in self
+            .imported
+            .iter()
+            .chain(self.targets.iter().map(|(s, _)| s))
+        {
+            if !slots.insert(*slot) {
+                bail!("The slot '{}' is declared multiple times", slot);
+            }
+        }
+
+        for node in self.nodes.iter() {
+            for slot in node.reads.iter().chain(node.writes.iter()) {
+                if !self.is_slot(slot) {
+                    bail!("Render node '{}' uses unknown slot '{}'", node.name, slot);
+                }
+            }
+            for other in node.after.iter().chain(node.before.iter()) {
+                if self.node_index(other).is_none() {
+                    bail!(
+                        "Render node '{}' is ordered against unknown node '{}'",
+                        node.name,
+                        other
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// sorts the nodes by their dependencies, the order the slots are used in follows from where
+    /// the nodes end up after `after` and `before` moved them as little as possible
+    fn order(&self) -> Result<Vec<usize>> {
+        let placed = self.sort(
+            &self.explicit_edges(),
+            &(0..self.nodes.len()).collect::<Vec<_>>(),
+        )?;
+        // the slot dependencies are derived from that order, sorting by them together with the
+        // explicit ones keeps it, unless they disagree
+        let edges = self.edges(&placed)?;
+        self.sort(&edges, &placed)
+    }
+
+    /// a topological sort of the nodes by the `(from, to)` edges, nodes that don't depend on each
+    /// other keep their order in `preferred`
+    ///
+    /// A node that has to run before another one gets the place of the earliest node depending on
+    /// it, so a node added last that runs `before` another ends up right before it, instead of
+    /// after every node that doesn't depend on it.
+    fn sort(&self, edges: &[(usize, usize)], preferred: &[usize]) -> Result<Vec<usize>> {
+        let count = self.nodes.len();
+        let mut rank = vec![0; count];
+        for (position, node) in preferred.iter().enumerate() {
+            rank[*node] = position;
+        }
+        let mut successors = vec![Vec::new(); count];
+        let mut remaining = vec![0; count];
+        for &(from, to) in edges {
+            successors[from].push(to);
+            remaining[to] += 1;
+        }
+
+        let priority: Vec<usize> = (0..count)
+            .map(|node| {
+                let mut visited = AHashSet::new();
+                let mut stack = vec![node];
+                let mut priority = rank[node];
+                while let Some(next) = stack.pop() {
+                    if visited.insert(next) {
+                        priority = priority.min(rank[next]);
+                        stack.extend(successors[next].iter().copied());
+                    }
+                }
+                priority
+            })
+            .collect();
+
+        let key = |node: usize| (priority[node], rank[node], node);
+        let mut ready: BTreeSet<_> = (0..count).filter(|i| remaining[*i] == 0).map(key).collect();
+        let mut order = Vec::with_capacity(count);
+
+        while let Some(&next) = ready.iter().next() {
+            ready.remove(&next);
+            let (_, _, next) = next;
+            order.push(next);
+            for &successor in successors[next].iter() {
+                remaining[successor] -= 1;
+                if remaining[successor] == 0 {
+                    ready.insert(key(successor));
+                }
+            }
+        }
+
+        if order.len() != count {
+            let cycle: Vec<_> = (0..count)
+                .filter(|i| !order.contains(i))
+                .map(|i| self.nodes[i].name)
+                .collect();
+            bail!("The render nodes {:?} depend on each other", cycle);
+        }
+
+        Ok(order)
+    }
+
+    /// the `after` and `before` dependencies between nodes
+    fn explicit_edges(&self) -> Vec<(usize, usize)> {
+        let mut edges = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            for other in node.after.iter() {
+                edges.extend(self.node_index(other).map(|o| (o, i)));
+            }
+            for other in node.before.iter() {
+                edges.extend(self.node_index(other).map(|o| (i, o)));
+            }
+        }
+        edges
+    }
+
+    /// the dependencies between nodes, a node runs after the last writer of the slots it reads,
+    /// and after the previous writer and the readers since then of the slots it writes
+    fn edges(&self, order: &[usize]) -> Result<Vec<(usize, usize)>> {
+        let mut edges = self.explicit_edges();
+
+        let mut last_writer: AHashMap<&str, usize> = AHashMap::new();
+        let mut readers: AHashMap<&str, Vec<usize>> = AHashMap::new();
+        for &i in order {
+            let node = &self.nodes[i];
+            for slot in node.reads.iter() {
+                match last_writer.get(slot) {
+                    Some(writer) => edges.push((*writer, i)),
+                    None if self.imported.contains(slot) => (),
+                    None => bail!(
+                        "Render node '{}' reads '{}' before any node writes it",
+                        node.name,
+                        slot
+                    ),
+                }
+                readers.entry(slot).or_default().push(i);
+            }
+            for slot in node.writes.iter() {
+                if let Some(writer) = last_writer.insert(slot, i) {
+                    edges.push((writer, i));
+                }
+                for reader in readers.remove(slot).unwrap_or_default() {
+                    if reader != i {
+                        edges.push((reader, i));
+                    }
+                }
+            }
+        }
+
+        edges.sort_unstable();
+        edges.dedup();
+        Ok(edges)
+    }
+
+    /// gives every transient slot a texture, slots with the same descriptor share one when all
+    /// the passes using the first are submitted before the next one is written
+    fn allocate_targets(&self, order: &[usize], edges: &[(usize, usize)]) -> RenderTargets {
+        let position = |node: usize| order.iter().position(|n| *n == node).unwrap();
+        let users = |slot: &str| -> Vec<usize> {
+            let mut users: Vec<_> = (0..self.nodes.len())
+                .filter(|i| self.nodes[*i].uses(slot))
+                .collect();
+            users.sort_by_key(|i| position(*i));
+            users
+        };
+
+        let mut targets: Vec<_> = self
+            .targets
+            .iter()
+            .map(|(slot, descriptor)| (*slot, *descriptor, users(slot)))
+            .filter(|(slot, _, users)| {
+                if users.is_empty() {
+                    debug!("Render target '{}' isn't used by any node", slot);
+                }
+                !users.is_empty()
+            })
+            .collect();
+        targets.sort_by_key(|(_, _, users)| position(users[0]));
+
+        // the descriptor of every texture and the nodes using the slot that has it last
+        let mut textures: Vec<(TargetDescriptor, Vec<usize>)> = Vec::new();
+        let mut slots = AHashMap::new();
+        for (slot, descriptor, users) in targets {
+            let first = users[0];
+            let shared = textures.iter().position(|(d, last_users)| {
+                *d == descriptor
+                    && last_users
+                        .iter()
+                        .all(|u| *u != first && reaches(edges, *u, first))
+            });
+            let index = match shared {
+                Some(index) => {
+                    textures[index].1 = users;
+                    index
+                }
+                None => {
+                    textures.push((descriptor, users));
+                    textures.len() - 1
+                }
+            };
+            slots.insert(slot, index);
+        }
+
+        RenderTargets {
+            slots,
+            descriptors: textures.into_iter().map(|(d, _)| d).collect(),
+            textures: Vec::new(),
+            size: PhysicalSize::new(0, 0),
+            generation: 0,
+        }
+    }
+}
+
+/// whether `to` always runs after `from`
+fn reaches(edges: &[(usize, usize)], from: usize, to: usize) -> bool {
+    let mut stack = vec![from];
+    let mut visited = AHashSet::new();
+    while let Some(node) = stack.pop() {
+        if node == to {
+            return true;
+        }
+        if visited.insert(node) {
+            stack.extend(edges.iter().filter(|(f, _)| *f == node).map(|(_, t)| *t));
+        }
+    }
+    false
+}
+
+/// The textures of the transient slots of the render graph, slots that are never used at the
+/// same time can share a texture
+#[derive(Resource, Default)]
+pub struct RenderTargets {
+    slots: AHashMap<&'static str, usize>,
+    descriptors: Vec<TargetDescriptor>,
+    textures: Vec<(Texture, TextureView)>,
+    size: PhysicalSize<u32>,
+    generation: u64,
+}
+
+impl RenderTargets {
+    pub fn view(&self, slot: &str) -> Option<&TextureView> {
+        self.slots
+            .get(slot)
+            .and_then(|i| self.textures.get(*i))
+            .map(|(_, view)| view)
+    }
+
+    pub fn texture(&self, slot: &str) -> Option<&Texture> {
+        self.slots
+            .get(slot)
+            .and_then(|i| self.textures.get(*i))
+            .map(|(texture, _)| texture)
+    }
+
+    /// the amount of textures backing the slots
+    pub fn texture_count(&self) -> usize {
+        self.descriptors.len()
+    }
+
+    /// changes every time the textures are recreated, bind groups with the views have to be
+    /// recreated when it does
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// drops the textures, they're created again before the next frame
+    pub(crate) fn clear(&mut self) {
+        self.textures.clear();
+    }
+
+    /// recreates the textures if the size of the surface has changed
+    pub(crate) fn resize_if_needed(&mut self, device: &Device, size: PhysicalSize<u32>) {
+        if self.size == size && self.textures.len() == self.descriptors.len() {
+            return;
+        }
+
+        puffin::profile_function!();
+        self.textures = self
+            .descriptors
+            .iter()
+            .enumerate()
+            .map(|(i, descriptor)| {
+                let texture = device.create_texture(&TextureDescriptor {
+                    label: Some(&format!("Render target {} TEX", i)),
+                    size: descriptor.size(size),
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: descriptor.format,
+                    usage: descriptor.usage,
+                });
+                let view = texture.create_view(&TextureViewDescriptor::default());
+                (texture, view)
+            })
+            .collect();
+        self.size = size;
+        self.generation += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop() {}
+
+    /// the engine's passes around the tonemapping, like `insert_renderer_systems_in_schedule`
+    /// adds them
+    fn engine_graph() -> RenderGraph {
+        let mut graph = RenderGraph::default();
+        graph.import(slots::FRAME);
+        graph.import(slots::OUTPUT);
+        graph.add_node(RenderNode::new("clear", noop).writes(slots::FRAME));
+        graph.add_node(RenderNode::new("sprites", noop).writes(slots::FRAME));
+        graph.add_node(RenderNode::new("gizmos", noop).writes(slots::FRAME));
+        graph.add_node(
+            RenderNode::new("tonemap", noop)
+                .reads(slots::FRAME)
+                .writes(slots::OUTPUT),
+        );
+        graph.add_node(RenderNode::new("capture_scene", noop).reads(slots::OUTPUT));
+        graph.add_node(RenderNode::new("egui", noop).writes(slots::OUTPUT));
+        graph.add_node(RenderNode::new("capture_frame", noop).reads(slots::OUTPUT));
+        graph
+    }
+
+    fn edge(graph: &RenderGraph, from: &str, to: &str) -> (usize, usize) {
+        (
+            graph.node_index(from).unwrap(),
+            graph.node_index(to).unwrap(),
+        )
+    }
+
+    #[test]
+    fn nodes_keep_the_order_they_were_added_in() {
+        let graph = engine_graph();
+        assert_eq!(
+            graph.node_order().unwrap(),
+            [
+                "clear",
+                "sprites",
+                "gizmos",
+                "tonemap",
+                "capture_scene",
+                "egui",
+                "capture_frame"
+            ]
+        );
+    }
+
+    #[test]
+    fn node_added_last_runs_right_before_the_node_it_has_to() {
+        let mut graph = engine_graph();
+        graph.add_node(
+            RenderNode::new("custom", noop)
+                .writes(slots::FRAME)
+                .before("tonemap"),
+        );
+
+        let order = graph.order().unwrap();
+        let names: Vec<_> = order.iter().map(|i| graph.nodes[*i].name).collect();
+        assert_eq!(
+            names,
+            [
+                "clear",
+                "sprites",
+                "gizmos",
+                "custom",
+                "tonemap",
+                "capture_scene",
+                "egui",
+                "capture_frame"
+            ]
+        );
+
+        let edges = graph.edges(&order).unwrap();
+        assert!(edges.contains(&edge(&graph, "gizmos", "custom")));
+        assert!(edges.contains(&edge(&graph, "custom", "tonemap")));
+        assert!(edges.contains(&edge(&graph, "tonemap", "capture_scene")));
+        assert!(edges.contains(&edge(&graph, "capture_scene", "egui")));
+        assert!(!edges.contains(&edge(&graph, "egui", "tonemap")));
+        assert!(!edges.contains(&edge(&graph, "capture_frame", "tonemap")));
+    }
+
+    #[test]
+    fn node_ordered_after_another_uses_the_slots_after_it() {
+        let mut graph = engine_graph();
+        graph.add_node(
+            RenderNode::new("custom", noop)
+                .reads(slots::OUTPUT)
+                .after("tonemap")
+                .before("egui"),
+        );
+
+        let order = graph.order().unwrap();
+        let edges = graph.edges(&order).unwrap();
+        assert!(edges.contains(&edge(&graph, "tonemap", "custom")));
+        assert!(edges.contains(&edge(&graph, "custom", "egui")));
+    }
+
+    #[test]
+    fn cycles_are_detected() {
+        let mut graph = RenderGraph::default();
+        graph.add_node(RenderNode::new("a", noop).after("b"));
+        graph.add_node(RenderNode::new("b", noop).after("c"));
+        graph.add_node(RenderNode::new("c", noop).after("a"));
+        graph.add_node(RenderNode::new("d", noop));
+
+        let error = graph.order().unwrap_err().to_string();
+        assert!(error.contains("\"a\", \"b\", \"c\""), "{}", error);
+    }
+
+    #[test]
+    fn reading_a_slot_nothing_wrote_fails() {
+        let mut graph = RenderGraph::default();
+        graph.add_target("normals", TargetDescriptor::new(TextureFormat::Rgba8Unorm));
+        graph.add_node(RenderNode::new("lighting", noop).reads("normals"));
+        graph.add_node(RenderNode::new("normals", noop).writes("normals"));
+
+        assert!(graph.order().is_err());
+    }
+}
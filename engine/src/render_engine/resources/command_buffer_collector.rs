@@ -6,6 +6,8 @@ use std::mem::swap;
 use std::ops::DerefMut;
 use wgpu::{CommandBuffer, CommandEncoder};
 
+/// Collects the command buffers of the render systems, `ecs_render_submit_commands` submits them
+/// in the order they were pushed, which follows the order of the systems in the render schedule
 #[derive(Resource)]
 pub struct CommandBufferCollector {
     inner: Mutex<Vec<CommandBuffer>>,
@@ -17,6 +19,12 @@ impl CommandBufferCollector {
         self.inner.lock().push(command_buffer);
     }
 
+    /// appends buffers that were encoded in parallel, they stay together and in the given order,
+    /// so the submission order doesn't depend on which thread finished first
+    pub fn push_buffers<I: IntoIterator<Item = CommandBuffer>>(&self, buffers: I) {
+        self.inner.lock().extend(buffers);
+    }
+
     pub fn take(&self) -> Vec<CommandBuffer> {
         let mut commands = Vec::new();
         let mut lock = self.inner.lock();
@@ -5,7 +5,14 @@ use wgpu::{SurfaceTexture, TextureView};
 #[derive(Resource)]
 pub struct FrameResources {
     pub output: Option<SurfaceTexture>,
+    /// the linear scene target the passes draw into, it's tonemapped into `output_view`
     pub view: TextureView,
+    /// the surface texture, only the tonemapping pass and the passes after it draw into it
+    ///
+    /// It's a view of the capture target of `FrameCapture` when the frame is captured.
+    pub output_view: TextureView,
+    /// the view of the surface texture when `output_view` is the capture target
+    pub surface_view: Option<TextureView>,
 }
 
 impl FrameResources {
@@ -16,6 +23,11 @@ impl FrameResources {
         }
     }
 
+    /// whether the frame is drawn into the capture target
+    pub fn is_captured(&self) -> bool {
+        self.surface_view.is_some()
+    }
+
     pub fn present(&mut self) {
         let mut old = None;
         swap(&mut old, &mut self.output);
@@ -0,0 +1,310 @@
+use crate::asset_management::{Uuid, UUID_NAMESPACE_ASSETS};
+use crate::pipelines::lighting::{build_bind_group_layout, NORMAL_TARGET_FORMAT};
+use crate::render_engine::buffer::{GpuBuffer, GpuUniformBuffer, Uniform};
+use crate::render_engine::texture::GpuTexture;
+use bevy_ecs::system::Resource;
+use bytemuck::Zeroable;
+use image::{DynamicImage, ImageBuffer, Rgba};
+use log::warn;
+use wgpu::*;
+use winit::dpi::PhysicalSize;
+
+pub const MAX_LIGHTS: usize = 128;
+pub const MAX_OCCLUDER_EDGES: usize = 1024;
+
+/// The light every pixel receives, regardless of the lights in the scene.
+/// When this is white and there are no lights, the lighting pass is skipped entirely.
+#[derive(Resource, Copy, Clone)]
+pub struct AmbientLight(pub [f32; 3]);
+
+impl Default for AmbientLight {
+    fn default() -> Self {
+        Self([1.0, 1.0, 1.0])
+    }
+}
+
+impl AmbientLight {
+    pub fn is_white(&self) -> bool {
+        self.0 == [1.0, 1.0, 1.0]
+    }
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LightKind {
+    Point = 0,
+    Spot = 1,
+    Directional = 2,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuLight {
+    /// xy = position, z = radius, w = kind
+    pub position: [f32; 4],
+    /// rgb = color, a = intensity
+    pub color: [f32; 4],
+    /// xyz = direction, w = height
+    pub direction: [f32; 4],
+    /// x = cos(inner angle), y = cos(outer angle)
+    pub cone: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuOccluderEdge {
+    /// xy = start, zw = end
+    pub points: [f32; 4],
+    /// x = softness, 0 for hard shadows
+    pub params: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightingUniform {
+    pub inv_view_proj: [[f32; 4]; 4],
+    pub ambient: [f32; 4],
+    pub light_count: u32,
+    pub edge_count: u32,
+    _padding: [u32; 2],
+}
+
+impl LightingUniform {
+    pub fn new(
+        inv_view_proj: [[f32; 4]; 4],
+        ambient: [f32; 3],
+        light_count: u32,
+        edge_count: u32,
+    ) -> Self {
+        Self {
+            inv_view_proj,
+            ambient: [ambient[0], ambient[1], ambient[2], 1.0],
+            light_count,
+            edge_count,
+            _padding: [0; 2],
+        }
+    }
+}
+
+impl Uniform for LightingUniform {
+    fn bind_group_layout_entry() -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct LightingResources {
+    normal_target: Texture,
+    normal_view: TextureView,
+    normal_size: PhysicalSize<u32>,
+    sampler: Sampler,
+    uniform_buf: GpuUniformBuffer<LightingUniform>,
+    light_buf: GpuBuffer,
+    edge_buf: GpuBuffer,
+    bind_group: BindGroup,
+    flat_normal: GpuTexture,
+}
+
+impl LightingResources {
+    pub fn new(device: &Device, queue: &Queue, size: PhysicalSize<u32>) -> Self {
+        let (normal_target, normal_view) = create_normal_target(device, size);
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Lighting normal sampler"),
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let uniform_buf =
+            GpuUniformBuffer::new(device, &[LightingUniform::zeroed()], Some("Lighting UB"));
+        let light_buf = GpuBuffer::new_with_data(
+            device,
+            &[GpuLight::zeroed(); MAX_LIGHTS],
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            Some("Lighting lights SB"),
+        );
+        let edge_buf = GpuBuffer::new_with_data(
+            device,
+            &[GpuOccluderEdge::zeroed(); MAX_OCCLUDER_EDGES],
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            Some("Lighting occluder edges SB"),
+        );
+
+        let bind_group = create_bind_group(
+            device,
+            &uniform_buf,
+            &light_buf,
+            &edge_buf,
+            &normal_view,
+            &sampler,
+        );
+
+        // sprites without a normal map are treated as if they're facing the camera
+        let flat_normal_image =
+            DynamicImage::ImageRgba8(ImageBuffer::from_pixel(1, 1, Rgba([128, 128, 255, 255])));
+        let flat_normal = GpuTexture::new_from_image(
+            device,
+            queue,
+            &flat_normal_image,
+            "Flat normal",
+            Uuid::new_v5(&UUID_NAMESPACE_ASSETS, b"flat-normal"),
+        );
+
+        Self {
+            normal_target,
+            normal_view,
+            normal_size: size,
+            sampler,
+            uniform_buf,
+            light_buf,
+            edge_buf,
+            bind_group,
+            flat_normal,
+        }
+    }
+
+    /// recreates the normal target if the size of the surface has changed
+    pub fn resize_if_needed(&mut self, device: &Device, size: PhysicalSize<u32>) {
+        if self.normal_size == size {
+            return;
+        }
+
+        puffin::profile_function!();
+        let (normal_target, normal_view) = create_normal_target(device, size);
+        self.bind_group = create_bind_group(
+            device,
+            &self.uniform_buf,
+            &self.light_buf,
+            &self.edge_buf,
+            &normal_view,
+            &self.sampler,
+        );
+        self.normal_target = normal_target;
+        self.normal_view = normal_view;
+        self.normal_size = size;
+    }
+
+    /// uploads the lights and occluder edges, anything over the limits is dropped
+    pub fn update(
+        &self,
+        queue: &Queue,
+        inv_view_proj: [[f32; 4]; 4],
+        ambient: AmbientLight,
+        lights: &[GpuLight],
+        edges: &[GpuOccluderEdge],
+    ) {
+        let lights = if lights.len() > MAX_LIGHTS {
+            warn!(
+                "{} lights in the scene, only the first {} will be drawn",
+                lights.len(),
+                MAX_LIGHTS
+            );
+            &lights[..MAX_LIGHTS]
+        } else {
+            lights
+        };
+
+        let edges = if edges.len() > MAX_OCCLUDER_EDGES {
+            warn!(
+                "{} occluder edges in the scene, only the first {} will cast shadows",
+                edges.len(),
+                MAX_OCCLUDER_EDGES
+            );
+            &edges[..MAX_OCCLUDER_EDGES]
+        } else {
+            edges
+        };
+
+        if !lights.is_empty() {
+            self.light_buf.update(queue, lights);
+        }
+        if !edges.is_empty() {
+            self.edge_buf.update(queue, edges);
+        }
+
+        self.uniform_buf.update(
+            queue,
+            &[LightingUniform::new(
+                inv_view_proj,
+                ambient.0,
+                lights.len() as u32,
+                edges.len() as u32,
+            )],
+        );
+    }
+
+    pub fn normal_view(&self) -> &TextureView {
+        &self.normal_view
+    }
+
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    pub fn flat_normal(&self) -> &GpuTexture {
+        &self.flat_normal
+    }
+}
+
+fn create_normal_target(device: &Device, size: PhysicalSize<u32>) -> (Texture, TextureView) {
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("Normal target TEX"),
+        size: Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: NORMAL_TARGET_FORMAT,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+    });
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    (texture, view)
+}
+
+fn create_bind_group(
+    device: &Device,
+    uniform_buf: &GpuUniformBuffer<LightingUniform>,
+    light_buf: &GpuBuffer,
+    edge_buf: &GpuBuffer,
+    normal_view: &TextureView,
+    sampler: &Sampler,
+) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+        layout: &build_bind_group_layout(device, "Lighting"),
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: uniform_buf.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: light_buf.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: edge_buf.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 3,
+                resource: BindingResource::TextureView(normal_view),
+            },
+            BindGroupEntry {
+                binding: 4,
+                resource: BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some("Lighting BG"),
+    })
+}
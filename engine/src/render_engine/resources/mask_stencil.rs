@@ -0,0 +1,38 @@
+use crate::pipelines::sprite_mask::MASK_STENCIL_FORMAT;
+use bevy_ecs::system::Resource;
+use wgpu::*;
+use winit::dpi::PhysicalSize;
+
+/// The stencil the `SpriteMask`s are written into, it's only created once a masked sprite is drawn
+#[derive(Resource, Default)]
+pub struct MaskStencil {
+    /// the texture, and the size and sample count it was created with
+    target: Option<(Texture, TextureView, PhysicalSize<u32>, u32)>,
+}
+
+impl MaskStencil {
+    /// the stencil matching the frame, it's recreated when the size or the MSAA sample count of
+    /// the frame changed
+    pub fn view(&mut self, device: &Device, size: PhysicalSize<u32>, samples: u32) -> &TextureView {
+        let outdated = !matches!(&self.target, Some((_, _, s, n)) if *s == size && *n == samples);
+        if outdated {
+            let texture = device.create_texture(&TextureDescriptor {
+                label: Some("Mask stencil TEX"),
+                size: Extent3d {
+                    width: size.width,
+                    height: size.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: samples,
+                dimension: TextureDimension::D2,
+                format: MASK_STENCIL_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT,
+            });
+            let view = texture.create_view(&TextureViewDescriptor::default());
+            self.target = Some((texture, view, size, samples));
+        }
+
+        &self.target.as_ref().unwrap().1
+    }
+}
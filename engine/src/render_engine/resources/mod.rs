@@ -1,5 +1,9 @@
 mod command_buffer_collector;
 pub mod frame;
+pub mod lighting;
+mod mask_stencil;
 
 pub use command_buffer_collector::CommandBufferCollector;
 pub use frame::FrameResources;
+pub use lighting::{AmbientLight, LightingResources};
+pub use mask_stencil::MaskStencil;
@@ -0,0 +1,198 @@
+use super::pose::Pose;
+use super::SkeletonData;
+
+/// How the value changes between a keyframe and the next one
+#[derive(Copy, Clone, Debug)]
+pub enum Curve {
+    Linear,
+    /// holds the value until the next keyframe
+    Stepped,
+    /// a cubic bezier from (0, 0) to (1, 1) with the two control points
+    Bezier(f32, f32, f32, f32),
+}
+
+impl Curve {
+    /// maps the linear progress between two keyframes to the eased progress
+    pub fn apply(&self, percent: f32) -> f32 {
+        match *self {
+            Curve::Linear => percent,
+            Curve::Stepped => 0.0,
+            Curve::Bezier(cx1, cy1, cx2, cy2) => {
+                // the x of the curve always increases, so the t belonging to x can be searched for
+                let (mut low, mut high) = (0.0, 1.0);
+                let mut t = percent;
+                for _ in 0..16 {
+                    let x = bezier(t, cx1, cx2);
+                    if (x - percent).abs() < 0.0001 {
+                        break;
+                    }
+                    if x < percent {
+                        low = t;
+                    } else {
+                        high = t;
+                    }
+                    t = (low + high) * 0.5;
+                }
+                bezier(t, cy1, cy2)
+            }
+        }
+    }
+}
+
+fn bezier(t: f32, p1: f32, p2: f32) -> f32 {
+    let inv = 1.0 - t;
+    3.0 * inv * inv * t * p1 + 3.0 * inv * t * t * p2 + t * t * t
+}
+
+/// Keyframes with any amount of channels, the curve of a keyframe is used up to the next one
+#[derive(Clone, Debug, Default)]
+pub struct Keyframes {
+    pub times: Vec<f32>,
+    pub curves: Vec<Curve>,
+    pub values: Vec<Vec<f32>>,
+}
+
+impl Keyframes {
+    pub fn push(&mut self, time: f32, curve: Curve, value: Vec<f32>) {
+        self.times.push(time);
+        self.curves.push(curve);
+        self.values.push(value);
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.0)
+    }
+
+    /// the interpolated value at `time`, the first and last keyframes are held
+    pub fn sample(&self, time: f32) -> Vec<f32> {
+        if time <= self.times[0] {
+            return self.values[0].clone();
+        }
+
+        let next = match self.times.iter().position(|t| *t > time) {
+            Some(next) => next,
+            None => return self.values[self.values.len() - 1].clone(),
+        };
+        let prev = next - 1;
+
+        let span = self.times[next] - self.times[prev];
+        let percent = self.curves[prev].apply((time - self.times[prev]) / span);
+
+        self.values[prev]
+            .iter()
+            .zip(self.values[next].iter())
+            .map(|(a, b)| a + (b - a) * percent)
+            .collect()
+    }
+
+    /// the index of the last keyframe at or before `time`, for values that can't be interpolated
+    pub fn index_at(&self, time: f32) -> Option<usize> {
+        self.times.iter().rposition(|t| *t <= time)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum BoneTimeline {
+    /// degrees, added to the setup pose
+    Rotate(Keyframes),
+    /// added to the setup pose
+    Translate(Keyframes),
+    /// multiplied with the setup pose
+    Scale(Keyframes),
+}
+
+#[derive(Clone, Debug)]
+pub enum SlotTimeline {
+    /// the keyframes only have a time, the names are stored separately
+    Attachment(Keyframes, Vec<Option<String>>),
+    /// RGBA, replaces the color of the slot
+    Color(Keyframes),
+}
+
+/// Offsets for the vertices of a mesh attachment, one x/y pair for every vertex, or for every
+/// bone influence if the mesh is weighted
+#[derive(Clone, Debug)]
+pub struct DeformTimeline {
+    pub skin: String,
+    pub slot: usize,
+    pub attachment: String,
+    pub keyframes: Keyframes,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Animation {
+    pub name: String,
+    pub duration: f32,
+    pub bones: Vec<(usize, BoneTimeline)>,
+    pub slots: Vec<(usize, SlotTimeline)>,
+    pub deforms: Vec<DeformTimeline>,
+}
+
+impl Animation {
+    /// mixes the animation at `time` into the pose, an `alpha` of 1 replaces the pose entirely
+    pub fn apply(&self, data: &SkeletonData, pose: &mut Pose, time: f32, alpha: f32, skin: &str) {
+        for (bone, timeline) in self.bones.iter() {
+            let setup = &data.bones[*bone].setup;
+            let current = &mut pose.bones[*bone];
+
+            match timeline {
+                BoneTimeline::Rotate(keys) => {
+                    let target = setup.rotation + keys.sample(time)[0];
+                    // rotate the shortest way around
+                    let diff = (target - current.rotation + 180.0).rem_euclid(360.0) - 180.0;
+                    current.rotation += diff * alpha;
+                }
+                BoneTimeline::Translate(keys) => {
+                    let value = keys.sample(time);
+                    current.x = mix(current.x, setup.x + value[0], alpha);
+                    current.y = mix(current.y, setup.y + value[1], alpha);
+                }
+                BoneTimeline::Scale(keys) => {
+                    let value = keys.sample(time);
+                    current.scale_x = mix(current.scale_x, setup.scale_x * value[0], alpha);
+                    current.scale_y = mix(current.scale_y, setup.scale_y * value[1], alpha);
+                }
+            }
+        }
+
+        for (slot, timeline) in self.slots.iter() {
+            match timeline {
+                SlotTimeline::Attachment(keys, names) => {
+                    // attachments can't be blended, so the animation with the most weight wins
+                    if alpha < 0.5 {
+                        continue;
+                    }
+                    if let Some(i) = keys.index_at(time) {
+                        pose.attachments[*slot] = names[i].clone();
+                    }
+                }
+                SlotTimeline::Color(keys) => {
+                    let value = keys.sample(time);
+                    let color = &mut pose.colors[*slot];
+                    for (c, v) in color.iter_mut().zip(value.iter()) {
+                        *c = mix(*c, *v, alpha);
+                    }
+                }
+            }
+        }
+
+        for deform in self.deforms.iter() {
+            if deform.skin != skin && deform.skin != super::DEFAULT_SKIN {
+                continue;
+            }
+
+            let value = deform.keyframes.sample(time);
+            let offsets = pose
+                .deforms
+                .entry((deform.slot, deform.attachment.clone()))
+                .or_insert_with(|| vec![0.0; value.len()]);
+            for (o, v) in offsets.iter_mut().zip(value.iter()) {
+                *o = mix(*o, *v, alpha);
+            }
+        }
+    }
+}
+
+fn mix(a: f32, b: f32, alpha: f32) -> f32 {
+    a + (b - a) * alpha
+}
@@ -0,0 +1,162 @@
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashMap;
+
+/// A region of a texture atlas page, as exported by Spine (the libGDX atlas format)
+#[derive(Clone, Debug)]
+pub struct AtlasRegion {
+    pub u: f32,
+    pub v: f32,
+    pub u2: f32,
+    pub v2: f32,
+    /// the region is stored rotated 90 degrees clockwise in the page
+    pub rotate: bool,
+    /// the size of the region in the page, before it was rotated
+    pub width: f32,
+    pub height: f32,
+    /// the size of the image before the whitespace was stripped
+    pub original_width: f32,
+    pub original_height: f32,
+    /// where the stripped region starts in the original image, from the bottom left
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+/// A Spine texture atlas, only atlases with a single page are supported
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    pub page: String,
+    pub width: u32,
+    pub height: u32,
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+impl Atlas {
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut lines = source.lines().map(str::trim_end).peekable();
+
+        // skip the empty lines before the page
+        while let Some(line) = lines.peek() {
+            if !line.trim().is_empty() {
+                break;
+            }
+            lines.next();
+        }
+
+        let page = lines
+            .next()
+            .ok_or_else(|| anyhow!("Atlas doesn't contain a page"))?
+            .trim()
+            .to_string();
+
+        let mut size = None;
+        // the page properties come first, indented lines belong to a region
+        while let Some(line) = lines.peek() {
+            match split_property(line) {
+                Some((key, value)) if !line.starts_with(char::is_whitespace) => {
+                    if key == "size" {
+                        let (w, h) = parse_pair(value)?;
+                        size = Some((w as u32, h as u32));
+                    }
+                    lines.next();
+                }
+                _ => break,
+            }
+        }
+
+        let (width, height) = size.ok_or_else(|| {
+            anyhow!(
+                "Atlas page {} doesn't have a size, export it with a newer version of Spine",
+                page
+            )
+        })?;
+
+        let mut regions = HashMap::new();
+        while let Some(line) = lines.next() {
+            if line.trim().is_empty() {
+                if lines.peek().map_or(false, |l| !l.trim().is_empty()) {
+                    bail!(
+                        "Atlas {} has more than one page, which isn't supported",
+                        page
+                    );
+                }
+                continue;
+            }
+
+            let name = line.trim().to_string();
+            let mut rotate = false;
+            let mut xy = (0.0, 0.0);
+            let mut region_size = (0.0, 0.0);
+            let mut orig = None;
+            let mut offset = (0.0, 0.0);
+
+            while let Some(line) = lines.peek() {
+                if !line.starts_with(char::is_whitespace) || line.trim().is_empty() {
+                    break;
+                }
+
+                if let Some((key, value)) = split_property(line) {
+                    match key {
+                        "rotate" => rotate = value == "true" || value == "90",
+                        "xy" => xy = parse_pair(value)?,
+                        "size" => region_size = parse_pair(value)?,
+                        "orig" => orig = Some(parse_pair(value)?),
+                        "offset" => offset = parse_pair(value)?,
+                        _ => (),
+                    }
+                }
+                lines.next();
+            }
+
+            let (x, y) = xy;
+            let (w, h) = region_size;
+            let (original_width, original_height) = orig.unwrap_or(region_size);
+            // a rotated region takes up height x width pixels in the page
+            let (page_w, page_h) = if rotate { (h, w) } else { (w, h) };
+
+            regions.insert(
+                name,
+                AtlasRegion {
+                    u: x / width as f32,
+                    v: y / height as f32,
+                    u2: (x + page_w) / width as f32,
+                    v2: (y + page_h) / height as f32,
+                    rotate,
+                    width: w,
+                    height: h,
+                    original_width,
+                    original_height,
+                    offset_x: offset.0,
+                    offset_y: offset.1,
+                },
+            );
+        }
+
+        Ok(Self {
+            page,
+            width,
+            height,
+            regions,
+        })
+    }
+
+    pub fn region(&self, name: &str) -> Result<&AtlasRegion> {
+        self.regions
+            .get(name)
+            .ok_or_else(|| anyhow!("Atlas page {} doesn't contain region {}", self.page, name))
+    }
+}
+
+fn split_property(line: &str) -> Option<(&str, &str)> {
+    let mut parts = line.trim().splitn(2, ':');
+    let key = parts.next()?.trim();
+    let value = parts.next()?.trim();
+    Some((key, value))
+}
+
+fn parse_pair(value: &str) -> Result<(f32, f32)> {
+    let mut parts = value.split(',').map(|v| v.trim().parse::<f32>());
+    match (parts.next(), parts.next()) {
+        (Some(Ok(a)), Some(Ok(b))) => Ok((a, b)),
+        _ => Err(anyhow!("Invalid atlas value {}", value)),
+    }
+}
@@ -0,0 +1,146 @@
+//! Skeletal animation, imported from Spine JSON exports
+//!
+//! A skeleton is a tree of bones, every slot is attached to a bone and shows one of its
+//! attachments, either a textured quad (region) or a mesh that can be deformed and weighted to
+//! multiple bones. Every frame the animations are sampled into a `Pose`, which is turned into a
+//! single `MeshData` so the whole skeleton is drawn by the mesh pipeline in one draw call.
+//!
+//! IK, path and transform constraints, clipping and draw order keys aren't supported.
+
+pub mod animation;
+pub mod atlas;
+pub mod pose;
+mod spine;
+
+use crate::asset_management::AssetLoader;
+use animation::Animation;
+use anyhow::Result;
+use atlas::Atlas;
+use std::collections::HashMap;
+
+/// attachments that aren't in the active skin are looked up in this one
+pub const DEFAULT_SKIN: &str = "default";
+
+/// The local transform of a bone, relative to its parent
+#[derive(Copy, Clone, Debug)]
+pub struct BoneTransform {
+    pub x: f32,
+    pub y: f32,
+    /// degrees, counter clockwise
+    pub rotation: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub shear_x: f32,
+    pub shear_y: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct BoneData {
+    pub name: String,
+    /// the parent always comes before the bone itself
+    pub parent: Option<usize>,
+    pub setup: BoneTransform,
+}
+
+#[derive(Clone, Debug)]
+pub struct SlotData {
+    pub name: String,
+    pub bone: usize,
+    /// the attachment visible in the setup pose
+    pub attachment: Option<String>,
+    pub color: [f32; 4],
+}
+
+#[derive(Clone, Debug)]
+pub struct RegionAttachment {
+    /// the corners relative to the bone, bottom left, top left, top right, bottom right
+    pub corners: [[f32; 2]; 4],
+    pub uvs: [[f32; 2]; 4],
+    pub color: [f32; 4],
+}
+
+/// One bone a vertex of a weighted mesh follows
+#[derive(Copy, Clone, Debug)]
+pub struct BoneWeight {
+    pub bone: usize,
+    /// the position relative to the bone
+    pub x: f32,
+    pub y: f32,
+    pub weight: f32,
+}
+
+#[derive(Clone, Debug)]
+pub enum MeshVertices {
+    /// the positions relative to the bone of the slot
+    Unweighted(Vec<[f32; 2]>),
+    Weighted(Vec<Vec<BoneWeight>>),
+}
+
+#[derive(Clone, Debug)]
+pub struct MeshAttachment {
+    pub vertices: MeshVertices,
+    pub uvs: Vec<[f32; 2]>,
+    pub triangles: Vec<u32>,
+    pub color: [f32; 4],
+}
+
+impl MeshAttachment {
+    /// the amount of floats in a deform keyframe of this mesh
+    pub fn deform_len(&self) -> usize {
+        match &self.vertices {
+            MeshVertices::Unweighted(vertices) => vertices.len() * 2,
+            MeshVertices::Weighted(vertices) => vertices.iter().map(Vec::len).sum::<usize>() * 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Attachment {
+    Region(RegionAttachment),
+    Mesh(MeshAttachment),
+}
+
+/// The setup pose, attachments and animations of a skeleton, shared by every entity using it
+#[derive(Clone, Debug)]
+pub struct SkeletonData {
+    pub bones: Vec<BoneData>,
+    pub slots: Vec<SlotData>,
+    /// the attachments of every slot by name, for every skin
+    pub skins: HashMap<String, Vec<HashMap<String, Attachment>>>,
+    pub animations: HashMap<String, Animation>,
+    /// the id of the texture the attachments are packed into
+    pub atlas_page: String,
+}
+
+impl SkeletonData {
+    /// loads a Spine JSON export and its atlas from the archives, the atlas page is loaded
+    /// separately as a texture (see `Skeleton::load`)
+    pub fn load_spine(json_id: &str, atlas_id: &str) -> Result<Self> {
+        let atlas = AssetLoader::get_asset(atlas_id)?;
+        let atlas = Atlas::parse(&String::from_utf8_lossy(&atlas))?;
+        let json = AssetLoader::get_asset(json_id)?;
+        spine::import(&json, &atlas)
+    }
+
+    pub fn bone_index(&self, name: &str) -> Option<usize> {
+        self.bones.iter().position(|b| b.name == name)
+    }
+
+    pub fn slot_index(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|s| s.name == name)
+    }
+
+    pub fn animation(&self, name: &str) -> Option<&Animation> {
+        self.animations.get(name)
+    }
+
+    /// looks the attachment up in `skin`, and then in the default skin
+    pub fn attachment(&self, skin: &str, slot: usize, name: &str) -> Option<&Attachment> {
+        let find = |skin: &str| {
+            self.skins
+                .get(skin)
+                .and_then(|attachments| attachments[slot].get(name))
+        };
+        find(skin).or_else(|| find(DEFAULT_SKIN))
+    }
+}
@@ -0,0 +1,179 @@
+use super::{Attachment, BoneTransform, MeshVertices, SkeletonData};
+use crate::render_engine::mesh::MeshData;
+use crate::render_engine::vertex::MeshVertex;
+use std::collections::HashMap;
+
+/// The local transforms of the bones and the state of the slots, at one point in time
+#[derive(Clone, Debug)]
+pub struct Pose {
+    pub bones: Vec<BoneTransform>,
+    pub attachments: Vec<Option<String>>,
+    pub colors: Vec<[f32; 4]>,
+    /// vertex offsets of deformed mesh attachments, by slot and attachment name
+    pub deforms: HashMap<(usize, String), Vec<f32>>,
+}
+
+impl Pose {
+    pub fn setup(data: &SkeletonData) -> Self {
+        Self {
+            bones: data.bones.iter().map(|b| b.setup).collect(),
+            attachments: data.slots.iter().map(|s| s.attachment.clone()).collect(),
+            colors: data.slots.iter().map(|s| s.color).collect(),
+            deforms: HashMap::new(),
+        }
+    }
+
+    /// the transforms of all bones relative to the skeleton, parents always come before
+    /// their children
+    pub fn world_transforms(&self, data: &SkeletonData) -> Vec<Affine2> {
+        let mut world: Vec<Affine2> = Vec::with_capacity(self.bones.len());
+        for (bone, local) in data.bones.iter().zip(self.bones.iter()) {
+            let local = Affine2::from_bone(local);
+            world.push(match bone.parent {
+                Some(parent) => world[parent].mul(&local),
+                None => local,
+            });
+        }
+        world
+    }
+
+    /// builds one mesh out of the attachments of all slots, in draw order
+    pub fn build_mesh(&self, data: &SkeletonData, skin: &str, tint: [f32; 4]) -> MeshData {
+        let world = self.world_transforms(data);
+        let mut mesh = MeshData::default();
+
+        for (slot_index, slot) in data.slots.iter().enumerate() {
+            let name = match &self.attachments[slot_index] {
+                Some(name) => name,
+                None => continue,
+            };
+            let attachment = match data.attachment(skin, slot_index, name) {
+                Some(attachment) => attachment,
+                None => continue,
+            };
+
+            let slot_color = self.colors[slot_index];
+            let bone = &world[slot.bone];
+            let base = mesh.vertices.len() as u32;
+
+            match attachment {
+                Attachment::Region(region) => {
+                    let color = multiply(multiply(tint, slot_color), region.color);
+                    for (corner, uv) in region.corners.iter().zip(region.uvs.iter()) {
+                        mesh.vertices.push(MeshVertex {
+                            position: bone.transform(*corner),
+                            tex_coord: *uv,
+                            color,
+                        });
+                    }
+                    mesh.indices.extend_from_slice(&[
+                        base,
+                        base + 1,
+                        base + 2,
+                        base + 2,
+                        base + 3,
+                        base,
+                    ]);
+                }
+                Attachment::Mesh(attachment) => {
+                    let color = multiply(multiply(tint, slot_color), attachment.color);
+                    let deform = self.deforms.get(&(slot_index, name.clone()));
+                    let offset = |i: usize| match deform {
+                        Some(d) if d.len() >= i * 2 + 2 => [d[i * 2], d[i * 2 + 1]],
+                        _ => [0.0, 0.0],
+                    };
+
+                    match &attachment.vertices {
+                        MeshVertices::Unweighted(vertices) => {
+                            for (i, (vertex, uv)) in
+                                vertices.iter().zip(attachment.uvs.iter()).enumerate()
+                            {
+                                let o = offset(i);
+                                mesh.vertices.push(MeshVertex {
+                                    position: bone.transform([vertex[0] + o[0], vertex[1] + o[1]]),
+                                    tex_coord: *uv,
+                                    color,
+                                });
+                            }
+                        }
+                        MeshVertices::Weighted(vertices) => {
+                            // the offsets of weighted meshes are stored per bone influence
+                            let mut influence = 0;
+                            for (vertex, uv) in vertices.iter().zip(attachment.uvs.iter()) {
+                                let mut position = [0.0, 0.0];
+                                for weight in vertex.iter() {
+                                    let o = offset(influence);
+                                    influence += 1;
+                                    let p = world[weight.bone]
+                                        .transform([weight.x + o[0], weight.y + o[1]]);
+                                    position[0] += p[0] * weight.weight;
+                                    position[1] += p[1] * weight.weight;
+                                }
+                                mesh.vertices.push(MeshVertex {
+                                    position,
+                                    tex_coord: *uv,
+                                    color,
+                                });
+                            }
+                        }
+                    }
+
+                    mesh.indices
+                        .extend(attachment.triangles.iter().map(|i| base + i));
+                }
+            }
+        }
+
+        mesh
+    }
+}
+
+fn multiply(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
+}
+
+/// A 2D affine transform, `x' = a * x + b * y + tx` and `y' = c * x + d * y + ty`
+#[derive(Copy, Clone, Debug)]
+pub struct Affine2 {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+impl Affine2 {
+    pub fn from_bone(bone: &BoneTransform) -> Self {
+        let rotation_x = (bone.rotation + bone.shear_x).to_radians();
+        let rotation_y = (bone.rotation + 90.0 + bone.shear_y).to_radians();
+
+        Self {
+            a: rotation_x.cos() * bone.scale_x,
+            b: rotation_y.cos() * bone.scale_y,
+            c: rotation_x.sin() * bone.scale_x,
+            d: rotation_y.sin() * bone.scale_y,
+            tx: bone.x,
+            ty: bone.y,
+        }
+    }
+
+    /// applies `other` first, then `self`
+    pub fn mul(&self, other: &Affine2) -> Affine2 {
+        Affine2 {
+            a: self.a * other.a + self.b * other.c,
+            b: self.a * other.b + self.b * other.d,
+            c: self.c * other.a + self.d * other.c,
+            d: self.c * other.b + self.d * other.d,
+            tx: self.a * other.tx + self.b * other.ty + self.tx,
+            ty: self.c * other.tx + self.d * other.ty + self.ty,
+        }
+    }
+
+    pub fn transform(&self, point: [f32; 2]) -> [f32; 2] {
+        [
+            self.a * point[0] + self.b * point[1] + self.tx,
+            self.c * point[0] + self.d * point[1] + self.ty,
+        ]
+    }
+}
@@ -0,0 +1,579 @@
+//! The Spine JSON format (3.7 and 3.8), see http://esotericsoftware.com/spine-json-format
+
+use super::animation::{Animation, BoneTimeline, Curve, DeformTimeline, Keyframes, SlotTimeline};
+use super::atlas::{Atlas, AtlasRegion};
+use super::*;
+use anyhow::{anyhow, bail, Result};
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+
+type SkinAttachments = HashMap<String, HashMap<String, SpineAttachment>>;
+
+#[derive(Deserialize)]
+struct SpineSkeleton {
+    #[serde(default)]
+    bones: Vec<SpineBone>,
+    #[serde(default)]
+    slots: Vec<SpineSlot>,
+    #[serde(default)]
+    skins: SpineSkins,
+    #[serde(default)]
+    animations: HashMap<String, SpineAnimation>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SpineSkins {
+    /// 3.8 and newer
+    List(Vec<SpineSkin>),
+    /// 3.7 and older, by name
+    Map(HashMap<String, SkinAttachments>),
+}
+
+impl Default for SpineSkins {
+    fn default() -> Self {
+        SpineSkins::List(Vec::new())
+    }
+}
+
+#[derive(Deserialize)]
+struct SpineSkin {
+    name: String,
+    #[serde(default)]
+    attachments: SkinAttachments,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpineBone {
+    name: String,
+    parent: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "one")]
+    scale_x: f32,
+    #[serde(default = "one")]
+    scale_y: f32,
+    #[serde(default)]
+    shear_x: f32,
+    #[serde(default)]
+    shear_y: f32,
+    transform: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SpineSlot {
+    name: String,
+    bone: String,
+    attachment: Option<String>,
+    color: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpineAttachment {
+    #[serde(rename = "type", default = "region")]
+    kind: String,
+    name: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "one")]
+    scale_x: f32,
+    #[serde(default = "one")]
+    scale_y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    color: Option<String>,
+    #[serde(default)]
+    uvs: Vec<f32>,
+    #[serde(default)]
+    triangles: Vec<u32>,
+    #[serde(default)]
+    vertices: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct SpineAnimation {
+    #[serde(default)]
+    bones: HashMap<String, SpineBoneTimelines>,
+    #[serde(default)]
+    slots: HashMap<String, SpineSlotTimelines>,
+    /// skin, slot, attachment
+    #[serde(default, alias = "ffd")]
+    deform: HashMap<String, HashMap<String, HashMap<String, Vec<SpineKey>>>>,
+}
+
+#[derive(Deserialize)]
+struct SpineBoneTimelines {
+    #[serde(default)]
+    rotate: Vec<SpineKey>,
+    #[serde(default)]
+    translate: Vec<SpineKey>,
+    #[serde(default)]
+    scale: Vec<SpineKey>,
+}
+
+#[derive(Deserialize)]
+struct SpineSlotTimelines {
+    #[serde(default)]
+    attachment: Vec<SpineKey>,
+    #[serde(default)]
+    color: Vec<SpineKey>,
+}
+
+/// every kind of keyframe, only the fields of the timeline it's in are used
+#[derive(Deserialize)]
+struct SpineKey {
+    #[serde(default)]
+    time: f32,
+    #[serde(default)]
+    angle: f32,
+    x: Option<f32>,
+    y: Option<f32>,
+    name: Option<String>,
+    color: Option<String>,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    vertices: Vec<f32>,
+    curve: Option<Value>,
+    c2: Option<f32>,
+    c3: Option<f32>,
+    c4: Option<f32>,
+}
+
+impl SpineKey {
+    fn curve(&self) -> Curve {
+        match &self.curve {
+            Some(Value::String(s)) if s == "stepped" => Curve::Stepped,
+            // 3.8 stores the first value in curve, and the others in c2, c3 and c4
+            Some(Value::Number(cx1)) => Curve::Bezier(
+                cx1.as_f64().unwrap_or(0.0) as f32,
+                self.c2.unwrap_or(0.0),
+                self.c3.unwrap_or(1.0),
+                self.c4.unwrap_or(1.0),
+            ),
+            Some(Value::Array(values)) if values.len() == 4 => {
+                let v = |i: usize| values[i].as_f64().unwrap_or(0.0) as f32;
+                Curve::Bezier(v(0), v(1), v(2), v(3))
+            }
+            _ => Curve::Linear,
+        }
+    }
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn region() -> String {
+    "region".to_string()
+}
+
+pub(super) fn import(json: &[u8], atlas: &Atlas) -> Result<SkeletonData> {
+    let skeleton: SpineSkeleton = serde_json::from_slice(json)?;
+
+    let mut bones: Vec<BoneData> = Vec::with_capacity(skeleton.bones.len());
+    for bone in skeleton.bones.iter() {
+        let parent = match &bone.parent {
+            Some(name) => Some(
+                bones
+                    .iter()
+                    .position(|b| &b.name == name)
+                    .ok_or_else(|| anyhow!("Parent {} of bone {} not found", name, bone.name))?,
+            ),
+            None => None,
+        };
+
+        if let Some(mode) = bone.transform.as_deref().filter(|m| *m != "normal") {
+            warn!(
+                "Bone {} uses transform mode {}, which isn't supported",
+                bone.name, mode
+            );
+        }
+
+        bones.push(BoneData {
+            name: bone.name.clone(),
+            parent,
+            setup: BoneTransform {
+                x: bone.x,
+                y: bone.y,
+                rotation: bone.rotation,
+                scale_x: bone.scale_x,
+                scale_y: bone.scale_y,
+                shear_x: bone.shear_x,
+                shear_y: bone.shear_y,
+            },
+        });
+    }
+    let bone_index = |name: &str| {
+        bones
+            .iter()
+            .position(|b| b.name == name)
+            .ok_or_else(|| anyhow!("Bone {} not found", name))
+    };
+
+    let mut slots = Vec::with_capacity(skeleton.slots.len());
+    for slot in skeleton.slots.iter() {
+        slots.push(SlotData {
+            name: slot.name.clone(),
+            bone: bone_index(&slot.bone)?,
+            attachment: slot.attachment.clone(),
+            color: parse_color(slot.color.as_deref())?,
+        });
+    }
+    let slot_index = |name: &str| {
+        slots
+            .iter()
+            .position(|s: &SlotData| s.name == name)
+            .ok_or_else(|| anyhow!("Slot {} not found", name))
+    };
+
+    let raw_skins: Vec<(String, SkinAttachments)> = match skeleton.skins {
+        SpineSkins::List(skins) => skins.into_iter().map(|s| (s.name, s.attachments)).collect(),
+        SpineSkins::Map(skins) => skins.into_iter().collect(),
+    };
+
+    let mut skins = HashMap::new();
+    for (skin, slot_attachments) in raw_skins {
+        let mut attachments = vec![HashMap::new(); slots.len()];
+        for (slot, slot_attachments) in slot_attachments {
+            let slot = slot_index(&slot)?;
+            for (name, attachment) in slot_attachments {
+                if let Some(converted) = convert_attachment(&name, &attachment, atlas, &bones)? {
+                    attachments[slot].insert(name, converted);
+                }
+            }
+        }
+        skins.insert(skin, attachments);
+    }
+
+    let mut animations = HashMap::new();
+    for (name, raw) in skeleton.animations {
+        let mut animation = Animation {
+            name: name.clone(),
+            ..Default::default()
+        };
+
+        for (bone, timelines) in raw.bones {
+            let bone = bone_index(&bone)?;
+            if !timelines.rotate.is_empty() {
+                let keys = keyframes(&timelines.rotate, |k| vec![k.angle]);
+                animation.bones.push((bone, BoneTimeline::Rotate(keys)));
+            }
+            if !timelines.translate.is_empty() {
+                let keys = keyframes(&timelines.translate, |k| {
+                    vec![k.x.unwrap_or(0.0), k.y.unwrap_or(0.0)]
+                });
+                animation.bones.push((bone, BoneTimeline::Translate(keys)));
+            }
+            if !timelines.scale.is_empty() {
+                let keys = keyframes(&timelines.scale, |k| {
+                    vec![k.x.unwrap_or(1.0), k.y.unwrap_or(1.0)]
+                });
+                animation.bones.push((bone, BoneTimeline::Scale(keys)));
+            }
+        }
+
+        for (slot, timelines) in raw.slots {
+            let slot = slot_index(&slot)?;
+            if !timelines.attachment.is_empty() {
+                let keys = keyframes(&timelines.attachment, |_| Vec::new());
+                let names = timelines
+                    .attachment
+                    .iter()
+                    .map(|k| k.name.clone())
+                    .collect();
+                animation
+                    .slots
+                    .push((slot, SlotTimeline::Attachment(keys, names)));
+            }
+            if !timelines.color.is_empty() {
+                let mut keys = Keyframes::default();
+                for key in timelines.color.iter() {
+                    let color = parse_color(key.color.as_deref())?;
+                    keys.push(key.time, key.curve(), color.to_vec());
+                }
+                animation.slots.push((slot, SlotTimeline::Color(keys)));
+            }
+        }
+
+        for (skin, slot_deforms) in raw.deform {
+            for (slot_name, attachment_deforms) in slot_deforms {
+                let slot = slot_index(&slot_name)?;
+                for (attachment, keys) in attachment_deforms {
+                    let mesh = skins
+                        .get(&skin)
+                        .and_then(|s: &Vec<HashMap<String, Attachment>>| s[slot].get(&attachment));
+                    let len = match mesh {
+                        Some(Attachment::Mesh(mesh)) => mesh.deform_len(),
+                        _ => bail!(
+                            "Animation {} deforms {} in slot {}, which isn't a mesh",
+                            name,
+                            attachment,
+                            slot_name
+                        ),
+                    };
+
+                    // the keys only store the offsets that aren't zero, starting at `offset`
+                    let keyframes = keyframes(&keys, |k| {
+                        let mut offsets = vec![0.0; len];
+                        for (i, v) in k.vertices.iter().enumerate() {
+                            if let Some(o) = offsets.get_mut(k.offset + i) {
+                                *o = *v;
+                            }
+                        }
+                        offsets
+                    });
+
+                    animation.deforms.push(DeformTimeline {
+                        skin: skin.clone(),
+                        slot,
+                        attachment,
+                        keyframes,
+                    });
+                }
+            }
+        }
+
+        let bone_durations = animation.bones.iter().map(|(_, t)| match t {
+            BoneTimeline::Rotate(k) | BoneTimeline::Translate(k) | BoneTimeline::Scale(k) => {
+                k.duration()
+            }
+        });
+        let slot_durations = animation.slots.iter().map(|(_, t)| match t {
+            SlotTimeline::Attachment(k, _) | SlotTimeline::Color(k) => k.duration(),
+        });
+        let deform_durations = animation.deforms.iter().map(|d| d.keyframes.duration());
+        animation.duration = bone_durations
+            .chain(slot_durations)
+            .chain(deform_durations)
+            .fold(0.0, f32::max);
+
+        animations.insert(name, animation);
+    }
+
+    Ok(SkeletonData {
+        bones,
+        slots,
+        skins,
+        animations,
+        atlas_page: atlas.page.clone(),
+    })
+}
+
+fn keyframes<F: Fn(&SpineKey) -> Vec<f32>>(keys: &[SpineKey], value: F) -> Keyframes {
+    let mut keyframes = Keyframes::default();
+    for key in keys {
+        keyframes.push(key.time, key.curve(), value(key));
+    }
+    keyframes
+}
+
+/// Spine stores colors as RRGGBBAA hex strings, white if there is none
+fn parse_color(color: Option<&str>) -> Result<[f32; 4]> {
+    let color = match color {
+        Some(color) => color,
+        None => return Ok([1.0; 4]),
+    };
+
+    if color.len() != 8 {
+        bail!("Invalid color {}", color);
+    }
+
+    let mut out = [0.0; 4];
+    for (i, c) in out.iter_mut().enumerate() {
+        let byte = u8::from_str_radix(&color[i * 2..i * 2 + 2], 16)
+            .map_err(|_| anyhow!("Invalid color {}", color))?;
+        *c = byte as f32 / 255.0;
+    }
+    Ok(out)
+}
+
+fn convert_attachment(
+    name: &str,
+    attachment: &SpineAttachment,
+    atlas: &Atlas,
+    bones: &[BoneData],
+) -> Result<Option<Attachment>> {
+    // the region in the atlas can be named differently than the attachment itself
+    let region_name = attachment
+        .path
+        .as_deref()
+        .or_else(|| attachment.name.as_deref())
+        .unwrap_or(name);
+    let color = parse_color(attachment.color.as_deref())?;
+
+    match attachment.kind.as_str() {
+        "region" => {
+            let region = atlas.region(region_name)?;
+            Ok(Some(Attachment::Region(convert_region(
+                attachment, region, color,
+            ))))
+        }
+        "mesh" => {
+            let region = atlas.region(region_name)?;
+            Ok(Some(Attachment::Mesh(convert_mesh(
+                name, attachment, region, atlas, color, bones,
+            )?)))
+        }
+        // bounding boxes, points and paths aren't drawn
+        "boundingbox" | "point" | "path" => Ok(None),
+        kind => {
+            warn!(
+                "Attachment {} has type {}, which isn't supported",
+                name, kind
+            );
+            Ok(None)
+        }
+    }
+}
+
+fn convert_region(
+    attachment: &SpineAttachment,
+    region: &AtlasRegion,
+    color: [f32; 4],
+) -> RegionAttachment {
+    // the whitespace that was stripped when packing has to be added back
+    let region_scale_x = attachment.width / region.original_width * attachment.scale_x;
+    let region_scale_y = attachment.height / region.original_height * attachment.scale_y;
+    let x1 = -attachment.width / 2.0 * attachment.scale_x + region.offset_x * region_scale_x;
+    let y1 = -attachment.height / 2.0 * attachment.scale_y + region.offset_y * region_scale_y;
+    let x2 = x1 + region.width * region_scale_x;
+    let y2 = y1 + region.height * region_scale_y;
+
+    let (sin, cos) = attachment.rotation.to_radians().sin_cos();
+    let corner = |x: f32, y: f32| {
+        [
+            x * cos - y * sin + attachment.x,
+            x * sin + y * cos + attachment.y,
+        ]
+    };
+
+    let (u, v, u2, v2) = (region.u, region.v, region.u2, region.v2);
+    let uvs = if region.rotate {
+        [[u2, v2], [u, v2], [u, v], [u2, v]]
+    } else {
+        [[u, v2], [u, v], [u2, v], [u2, v2]]
+    };
+
+    RegionAttachment {
+        corners: [
+            corner(x1, y1),
+            corner(x1, y2),
+            corner(x2, y2),
+            corner(x2, y1),
+        ],
+        uvs,
+        color,
+    }
+}
+
+fn convert_mesh(
+    name: &str,
+    attachment: &SpineAttachment,
+    region: &AtlasRegion,
+    atlas: &Atlas,
+    color: [f32; 4],
+    bones: &[BoneData],
+) -> Result<MeshAttachment> {
+    let vertex_count = attachment.uvs.len() / 2;
+    let (page_w, page_h) = (atlas.width as f32, atlas.height as f32);
+
+    // the uvs are relative to the original image, before it was packed
+    let uvs = attachment
+        .uvs
+        .chunks_exact(2)
+        .map(|uv| {
+            if region.rotate {
+                let u =
+                    region.u - (region.original_height - region.offset_y - region.height) / page_w;
+                let v =
+                    region.v - (region.original_width - region.offset_x - region.width) / page_h;
+                [
+                    u + uv[1] * region.original_height / page_w,
+                    v + (1.0 - uv[0]) * region.original_width / page_h,
+                ]
+            } else {
+                let u = region.u - region.offset_x / page_w;
+                let v =
+                    region.v - (region.original_height - region.offset_y - region.height) / page_h;
+                [
+                    u + uv[0] * region.original_width / page_w,
+                    v + uv[1] * region.original_height / page_h,
+                ]
+            }
+        })
+        .collect();
+
+    // unweighted meshes only store a position for every uv
+    let vertices = if attachment.vertices.len() == attachment.uvs.len() {
+        MeshVertices::Unweighted(
+            attachment
+                .vertices
+                .chunks_exact(2)
+                .map(|v| [v[0], v[1]])
+                .collect(),
+        )
+    } else {
+        // the amount of bones, followed by the bone index, x, y and weight for each of them
+        let mut weighted = Vec::with_capacity(vertex_count);
+        let mut data = attachment.vertices.iter().copied();
+        for _ in 0..vertex_count {
+            let count = data
+                .next()
+                .ok_or_else(|| anyhow!("Weighted mesh {} is missing vertices", name))?
+                as usize;
+            let mut weights = Vec::with_capacity(count);
+            for _ in 0..count {
+                match (data.next(), data.next(), data.next(), data.next()) {
+                    (Some(bone), Some(x), Some(y), Some(weight))
+                        if (bone as usize) < bones.len() =>
+                    {
+                        weights.push(BoneWeight {
+                            bone: bone as usize,
+                            x,
+                            y,
+                            weight,
+                        })
+                    }
+                    _ => bail!("Weighted mesh {} has invalid vertices", name),
+                }
+            }
+            weighted.push(weights);
+        }
+        MeshVertices::Weighted(weighted)
+    };
+
+    if let Some(index) = attachment
+        .triangles
+        .iter()
+        .find(|i| **i as usize >= vertex_count)
+    {
+        bail!(
+            "Mesh {} has index {}, but only {} vertices",
+            name,
+            index,
+            vertex_count
+        );
+    }
+
+    Ok(MeshAttachment {
+        vertices,
+        uvs,
+        triangles: attachment.triangles.clone(),
+        color,
+    })
+}
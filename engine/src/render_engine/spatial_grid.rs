@@ -0,0 +1,141 @@
+use ahash::AHashMap;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::system::Resource;
+use cgmath::Vector2;
+
+/// the size of a cell in world units, a few sprites wide so most sprites are in a single cell
+pub const DEFAULT_CELL_SIZE: f32 = 512.0;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct CellRange {
+    min: (i32, i32),
+    max: (i32, i32),
+}
+
+impl CellRange {
+    fn cells(self) -> impl Iterator<Item = (i32, i32)> {
+        (self.min.0..=self.max.0).flat_map(move |x| (self.min.1..=self.max.1).map(move |y| (x, y)))
+    }
+
+    fn contains(&self, cell: (i32, i32)) -> bool {
+        (self.min.0..=self.max.0).contains(&cell.0) && (self.min.1..=self.max.1).contains(&cell.1)
+    }
+}
+
+/// A uniform grid of the bounds of entities, used to find the ones in view without visiting
+/// all of them
+///
+/// Entities are only moved between cells when their bounds change, so the grid is kept up to
+/// date by `ecs_update_spatial_grid` from the positions that changed since the last frame.
+#[derive(Resource)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: AHashMap<(i32, i32), Vec<Entity>>,
+    entries: AHashMap<Entity, CellRange>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "The cell size has to be positive");
+        Self {
+            cell_size,
+            cells: AHashMap::new(),
+            entries: AHashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// the amount of entities in the grid
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// inserts the entity, or moves it if it was already in the grid
+    pub fn insert(&mut self, entity: Entity, min: Vector2<f32>, max: Vector2<f32>) {
+        let range = CellRange {
+            min: self.cell(min),
+            max: self.cell(max),
+        };
+
+        match self.entries.insert(entity, range) {
+            Some(old) if old == range => return,
+            Some(old) => self.remove_from_cells(entity, old),
+            None => (),
+        }
+
+        for cell in range.cells() {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(range) = self.entries.remove(&entity) {
+            self.remove_from_cells(entity, range);
+        }
+    }
+
+    /// the entities in the cells overlapping the rectangle, sorted so the order doesn't depend
+    /// on the cells they're in, they can be slightly outside of the rectangle
+    pub fn query(&self, min: Vector2<f32>, max: Vector2<f32>) -> Vec<Entity> {
+        let range = CellRange {
+            min: self.cell(min),
+            max: self.cell(max),
+        };
+
+        let mut entities = Vec::new();
+        let cell_count =
+            (range.max.0 - range.min.0 + 1) as usize * (range.max.1 - range.min.1 + 1) as usize;
+        if cell_count > self.cells.len() {
+            // zoomed out far enough that most of the cells in the range are empty
+            for (cell, cell_entities) in self.cells.iter() {
+                if range.contains(*cell) {
+                    entities.extend_from_slice(cell_entities);
+                }
+            }
+        } else {
+            for cell in range.cells() {
+                if let Some(cell_entities) = self.cells.get(&cell) {
+                    entities.extend_from_slice(cell_entities);
+                }
+            }
+        }
+
+        // entities spanning multiple cells are found more than once
+        entities.sort_unstable();
+        entities.dedup();
+        entities
+    }
+
+    fn cell(&self, point: Vector2<f32>) -> (i32, i32) {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn remove_from_cells(&mut self, entity: Entity, range: CellRange) {
+        for cell in range.cells() {
+            if let Some(entities) = self.cells.get_mut(&cell) {
+                if let Some(i) = entities.iter().position(|e| *e == entity) {
+                    entities.swap_remove(i);
+                }
+                if entities.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::asset_management::Uuid;
+use crate::pipelines::sprite::SpritePushConstant;
+use crate::render_engine::components::position::Position;
+use crate::render_engine::components::texture::Texture;
+use crate::render_engine::RenderEngineResources;
+use ahash::AHashMap;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::system::Resource;
+use cgmath::{Vector2, Vector3};
+use wgpu::{
+    RenderBundle, RenderBundleDescriptor, RenderBundleEncoderDescriptor, RenderPipeline,
+    ShaderStages, TextureFormat,
+};
+
+/// the size of a chunk in world units, bigger chunks mean less bundles but more re-recording
+/// when something in them changes
+pub const CHUNK_SIZE: f32 = 1024.0;
+
+/// The static sprites in one chunk, and the bundle drawing them
+#[derive(Default)]
+pub struct StaticChunk {
+    entities: Vec<Entity>,
+    bundle: Option<RenderBundle>,
+    /// the texture every entity had when the bundle was recorded, textures that are still
+    /// loading are swapped out later, which needs a new bundle
+    textures: Vec<Uuid>,
+    /// the MSAA sample count of the pass the bundle was recorded for
+    samples: u32,
+    /// the scene format of the pass the bundle was recorded for
+    format: Option<TextureFormat>,
+}
+
+impl StaticChunk {
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn bundle(&self) -> Option<&RenderBundle> {
+        self.bundle.as_ref()
+    }
+
+    pub fn invalidate(&mut self) {
+        self.bundle = None;
+        self.textures.clear();
+    }
+
+    /// whether the bundle is missing or was recorded with textures that have been swapped since,
+    /// or for another sample count or scene format
+    pub fn is_outdated<'a, I: Iterator<Item = &'a Texture>>(
+        &self,
+        samples: u32,
+        format: TextureFormat,
+        textures: I,
+    ) -> bool {
+        self.bundle.is_none()
+            || self.samples != samples
+            || self.format != Some(format)
+            || textures
+                .map(|t| t.0.uuid())
+                .ne(self.textures.iter().copied())
+    }
+
+    /// records the draws for `sprites`, which are the entities of the chunk in order
+    pub(crate) fn record<'a, I: Iterator<Item = (&'a Position, &'a Texture)>>(
+        &mut self,
+        engine: &RenderEngineResources,
+        pipeline: &RenderPipeline,
+        sprites: I,
+    ) {
+        let mut encoder =
+            engine
+                .device
+                .create_render_bundle_encoder(&RenderBundleEncoderDescriptor {
+                    label: Some("Static sprite bundle encoder"),
+                    color_formats: &[engine.scene_format()],
+                    depth_stencil: None,
+                    sample_count: engine.msaa_samples(),
+                    multiview: None,
+                });
+
+        encoder.set_pipeline(pipeline);
+        encoder.set_vertex_buffer(0, engine.sprite_square_vertex_buf.slice(..));
+        encoder.set_index_buffer(
+            engine.sprite_square_index_buf.slice(..),
+            engine.sprite_square_index_buf.index_format(),
+        );
+        encoder.set_bind_group(0, engine.camera.bind_group(), &[]);
+
+        // the bind groups have to outlive the encoder, the bundle keeps the textures alive after
+        let textures: Vec<_> = sprites.map(|(pos, tex)| (pos.0, tex.0.load())).collect();
+        self.textures = textures.iter().map(|(_, tex)| tex.uuid()).collect();
+
+        for (pos, tex) in textures.iter() {
+            let uniform = SpritePushConstant::new(crate::sprite::compute_model_matrix(*pos), pos.z);
+            encoder.set_push_constants(ShaderStages::VERTEX, 0, &uniform.as_bytes());
+            encoder.set_bind_group(1, tex.bind_group(), &[]);
+            encoder.draw_indexed(0..engine.sprite_square_index_buf.data_count(), 0, 0..1);
+        }
+
+        self.samples = engine.msaa_samples();
+        self.format = Some(engine.scene_format());
+        self.bundle = Some(encoder.finish(&RenderBundleDescriptor {
+            label: Some("Static sprite bundle"),
+        }));
+    }
+}
+
+/// Caches the draws of sprites marked `Static` in render bundles, one for every chunk of the
+/// world, so they don't have to be encoded every frame
+///
+/// `ecs_update_static_bundles` invalidates the chunks that had a static entity added, removed
+/// or changed, they're re-recorded the next time they're visible.
+#[derive(Resource, Default)]
+pub struct StaticSpriteBundles {
+    chunks: AHashMap<(i32, i32), StaticChunk>,
+    entity_chunks: AHashMap<Entity, (i32, i32)>,
+}
+
+impl StaticSpriteBundles {
+    /// adds the entity, or moves it to the chunk its position is in now
+    pub fn insert(&mut self, entity: Entity, position: Vector3<f32>) {
+        let key = chunk_of(position.truncate());
+        match self.entity_chunks.insert(entity, key) {
+            // still in the same chunk, only the bundle changes
+            Some(old) if old == key => {
+                if let Some(chunk) = self.chunks.get_mut(&key) {
+                    chunk.invalidate();
+                }
+                return;
+            }
+            Some(old) => self.remove_from_chunk(entity, old),
+            None => (),
+        }
+
+        let chunk = self.chunks.entry(key).or_default();
+        chunk.entities.push(entity);
+        chunk.invalidate();
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(chunk) = self.entity_chunks.remove(&entity) {
+            self.remove_from_chunk(entity, chunk);
+        }
+    }
+
+    /// makes every chunk record its bundle again
+    pub fn invalidate_all(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            chunk.invalidate();
+        }
+    }
+
+    /// the amount of static entities
+    pub fn len(&self) -> usize {
+        self.entity_chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entity_chunks.is_empty()
+    }
+
+    /// the chunks with sprites that can overlap the rectangle
+    pub fn visible_chunks(&self, min: Vector2<f32>, max: Vector2<f32>) -> Vec<(i32, i32)> {
+        // sprites stick out of the chunk their center is in by half their size
+        let margin = crate::sprite::half_size();
+        let (min, max) = (chunk_of(min - margin), chunk_of(max + margin));
+
+        let range_len = (max.0 - min.0 + 1) as usize * (max.1 - min.1 + 1) as usize;
+        let mut visible: Vec<_> = if range_len > self.chunks.len() {
+            self.chunks
+                .keys()
+                .filter(|c| c.0 >= min.0 && c.0 <= max.0 && c.1 >= min.1 && c.1 <= max.1)
+                .copied()
+                .collect()
+        } else {
+            (min.0..=max.0)
+                .flat_map(|x| (min.1..=max.1).map(move |y| (x, y)))
+                .filter(|c| self.chunks.contains_key(c))
+                .collect()
+        };
+        // keeps the order the chunks are drawn in stable
+        visible.sort_unstable();
+        visible
+    }
+
+    pub fn chunk(&self, chunk: (i32, i32)) -> Option<&StaticChunk> {
+        self.chunks.get(&chunk)
+    }
+
+    pub fn chunk_mut(&mut self, chunk: (i32, i32)) -> Option<&mut StaticChunk> {
+        self.chunks.get_mut(&chunk)
+    }
+
+    fn remove_from_chunk(&mut self, entity: Entity, key: (i32, i32)) {
+        if let Some(chunk) = self.chunks.get_mut(&key) {
+            if let Some(i) = chunk.entities.iter().position(|e| *e == entity) {
+                // keep the order, so sprites don't change which one is on top
+                chunk.entities.remove(i);
+            }
+            chunk.invalidate();
+
+            if chunk.entities.is_empty() {
+                self.chunks.remove(&key);
+            }
+        }
+    }
+}
+
+fn chunk_of(point: Vector2<f32>) -> (i32, i32) {
+    (
+        (point.x / CHUNK_SIZE).floor() as i32,
+        (point.y / CHUNK_SIZE).floor() as i32,
+    )
+}
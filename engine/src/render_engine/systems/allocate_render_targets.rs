@@ -0,0 +1,11 @@
+use crate::render_engine::render_graph::RenderTargets;
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::system::{Res, ResMut};
+
+pub fn ecs_allocate_render_targets(
+    engine: Res<RenderEngineResources>,
+    mut targets: ResMut<RenderTargets>,
+) {
+    puffin::profile_function!();
+    targets.resize_if_needed(&engine.device, engine.size);
+}
@@ -0,0 +1,129 @@
+use crate::ecs::resources::FixedTimestep;
+use crate::render_engine::capture::{CaptureJob, FrameCapture};
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use std::num::NonZeroU32;
+use wgpu::{
+    BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d, ImageCopyBuffer,
+    ImageDataLayout, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+/// the simulation steps at the frame rate of the recording while recording
+pub fn ecs_sync_fixed_timestep(capture: Res<FrameCapture>, mut timestep: ResMut<FixedTimestep>) {
+    if timestep.0 != capture.timestep() {
+        timestep.0 = capture.timestep();
+    }
+}
+
+/// copies the frame before the debug UI is drawn
+pub fn ecs_capture_scene(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    mut capture: ResMut<FrameCapture>,
+) {
+    puffin::profile_function!();
+    capture_frame(&engine, &frame, &command_collector, &mut capture, false);
+}
+
+/// copies the frame with the debug UI
+pub fn ecs_capture_frame(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    mut capture: ResMut<FrameCapture>,
+) {
+    puffin::profile_function!();
+    capture_frame(&engine, &frame, &command_collector, &mut capture, true);
+}
+
+/// draws the captured frame into the surface, it wasn't drawn into it directly
+pub fn ecs_present_capture(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    capture: Res<FrameCapture>,
+) {
+    puffin::profile_function!();
+    let (surface_view, target) = match (&frame.surface_view, capture.target()) {
+        (Some(view), Some(target)) => (view, target),
+        _ => return,
+    };
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_present_capture_encoder"),
+        });
+    target.blit(&mut encoder, surface_view);
+    command_collector.push(encoder);
+}
+
+fn capture_frame(
+    engine: &RenderEngineResources,
+    frame: &FrameResources,
+    command_collector: &CommandBufferCollector,
+    capture: &mut FrameCapture,
+    include_ui: bool,
+) {
+    // a screenshot requested after the frame was created is taken next frame
+    if !frame.is_captured() {
+        return;
+    }
+    let paths = capture.take_paths(include_ui);
+    if paths.is_empty() {
+        return;
+    }
+
+    let (width, height) = (engine.config.width, engine.config.height);
+    let padded_row = {
+        let row = width * 4;
+        let align = COPY_BYTES_PER_ROW_ALIGNMENT;
+        row + (align - row % align) % align
+    };
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_capture_frame_encoder"),
+        });
+
+    // every path gets its own buffer, the jobs run on different workers
+    for path in paths {
+        let buffer = engine.device.create_buffer(&BufferDescriptor {
+            label: Some("Frame capture BUF"),
+            size: padded_row as u64 * height as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            capture.target().unwrap().texture().as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_row),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        capture.push_pending(CaptureJob {
+            buffer,
+            path,
+            width,
+            height,
+            padded_row,
+            format: engine.config.format,
+        });
+    }
+
+    command_collector.push(encoder);
+}
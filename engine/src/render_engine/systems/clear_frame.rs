@@ -0,0 +1,36 @@
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use wgpu::{Color, CommandEncoderDescriptor, LoadOp, RenderPassDescriptor};
+
+/// clears the scene target, it outlives the frame unlike the surface texture
+pub fn ecs_clear_frame(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+) {
+    puffin::profile_function!();
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_clear_frame_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("clear");
+    gpu_scope.begin(&mut encoder);
+
+    {
+        let mut attachment = engine.frame_color_attachment(&frame.view);
+        attachment.ops.load = LoadOp::Clear(Color::TRANSPARENT);
+        encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Clear pass"),
+            color_attachments: &[attachment],
+            depth_stencil_attachment: None,
+        });
+    }
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
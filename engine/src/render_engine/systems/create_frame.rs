@@ -1,3 +1,4 @@
+use crate::render_engine::capture::FrameCapture;
 use crate::render_engine::resources::FrameResources;
 use crate::render_engine::RenderEngineResources;
 use bevy_ecs::system::{Commands, Res, ResMut};
@@ -7,9 +8,15 @@ use wgpu::{SurfaceError, TextureViewDescriptor};
 pub fn ecs_render_create_frame_resource(
     mut commands: Commands,
     engine: Res<RenderEngineResources>,
+    mut capture: ResMut<FrameCapture>,
     //mut frame: ResMut<Option<FrameResources>>,
 ) {
     puffin::profile_function!();
+    if engine.is_device_lost() {
+        // nothing can be drawn until ecs_recover_lost_device has recreated the device
+        return;
+    }
+
     let output = {
         puffin::profile_scope!("get_surface_texture");
         match engine.surface.get_current_texture() {
@@ -20,8 +27,9 @@ pub fn ecs_render_create_frame_resource(
                 return;
             }
             Err(SurfaceError::OutOfMemory) => {
-                error!("Got SurfaceError::OutOfMemory");
-                panic!("Got out of memory error!");
+                error!("Got SurfaceError::OutOfMemory, recreating the device");
+                engine.mark_device_lost();
+                return;
             }
             Err(e) => {
                 error!("Got {}, skipping frame", e);
@@ -30,16 +38,43 @@ pub fn ecs_render_create_frame_resource(
         }
     };
 
-    let view = {
+    let (view, output_view) = {
         puffin::profile_scope!("create_output_view");
-        output
-            .texture
-            .create_view(&TextureViewDescriptor::default())
+        (
+            engine
+                .tonemapper
+                .scene_target()
+                .create_view(&TextureViewDescriptor::default()),
+            output
+                .texture
+                .create_view(&TextureViewDescriptor::default()),
+        )
+    };
+
+    // a captured frame is drawn into the capture target, and from there into the surface
+    let (output_view, surface_view) = if capture.is_capturing() {
+        let target = capture.prepare_target(
+            &engine.device,
+            engine.config.width,
+            engine.config.height,
+            engine.config.format,
+        );
+        (
+            target
+                .texture()
+                .create_view(&TextureViewDescriptor::default()),
+            Some(output_view),
+        )
+    } else {
+        capture.drop_target();
+        (output_view, None)
     };
 
     //*frame = Some(FrameResources { output, view });
     commands.insert_resource(FrameResources {
         output: Some(output),
         view,
+        output_view,
+        surface_view,
     });
 }
@@ -1,5 +1,26 @@
+pub mod allocate_render_targets;
+pub mod capture_frame;
+pub mod clear_frame;
 pub mod create_frame;
+pub mod recover_lost_device;
 pub mod render_egui_ui;
+pub mod render_gizmos;
+pub mod render_lighting;
+pub mod render_masked_sprites;
+pub mod render_meshes;
+pub mod render_normals;
+pub mod render_palette_sprites;
+pub mod render_parallax;
+pub mod render_particles;
 pub mod render_sprites;
+pub mod render_trails;
+pub mod scroll_parallax_layers;
+pub mod simulate_particles;
 pub mod submit_commands;
+pub mod tonemap;
 pub mod update;
+pub mod update_skeletons;
+pub mod update_spatial_grid;
+pub mod update_static_bundles;
+pub mod update_trails;
+pub mod upload_dynamic_textures;
@@ -0,0 +1,68 @@
+use crate::render_engine::capture::FrameCapture;
+use crate::render_engine::components::mesh::Mesh2D;
+use crate::render_engine::components::trail::Trail;
+use crate::render_engine::dynamic_texture::DynamicTexture;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::particles::{ParticleBuffers, ParticleJobs};
+use crate::render_engine::render_graph::RenderTargets;
+use crate::render_engine::resources::{LightingResources, MaskStencil};
+use crate::render_engine::static_bundles::StaticSpriteBundles;
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use log::{error, info};
+use std::sync::Arc;
+
+/// recreates everything living on the GPU after the device was lost or recreated for a wgpu trace,
+/// the entities and their components stay as they are, so the game carries on where it was
+pub fn ecs_recover_lost_device(world: &mut World) {
+    if !world.resource::<RenderEngineResources>().needs_new_device() {
+        return;
+    }
+
+    puffin::profile_function!();
+    let mut engine = world.resource_mut::<RenderEngineResources>();
+    if let Err(e) = engine.recover_device() {
+        // the device stays marked as lost, so it's tried again next frame
+        error!("Failed to recreate the device: {}", e);
+        return;
+    }
+    let (device, queue, size, compute) = (
+        Arc::clone(&engine.device),
+        Arc::clone(&engine.queue),
+        engine.size,
+        engine.compute_supported(),
+    );
+
+    world.insert_resource(LightingResources::new(&device, &queue, size));
+    world.insert_resource(GpuProfiler::new(&device, &queue));
+    world.insert_resource(ParticleJobs::default());
+    world.insert_resource(MaskStencil::default());
+    if let Some(mut targets) = world.get_resource_mut::<RenderTargets>() {
+        targets.clear();
+    }
+    if let Some(mut bundles) = world.get_resource_mut::<StaticSpriteBundles>() {
+        bundles.invalidate_all();
+    }
+    if let Some(mut capture) = world.get_resource_mut::<FrameCapture>() {
+        capture.drop_target();
+    }
+
+    for mut texture in world.query::<&mut DynamicTexture>().iter_mut(world) {
+        texture.recreate(&device);
+    }
+    // the trails keep their points, their meshes are built again next frame
+    for mut trail in world.query::<&mut Trail>().iter_mut(world) {
+        *trail.mesh_mut() = None;
+    }
+
+    // particles simulated on the CPU carry on, the ones on the GPU start over
+    for mut buffers in world.query::<&mut ParticleBuffers>().iter_mut(world) {
+        *buffers = buffers.recreate(&device, compute);
+    }
+
+    for mut mesh in world.query::<&mut Mesh2D>().iter_mut(world) {
+        mesh.recreate(&device);
+    }
+
+    info!("Recreated the resources on the new device");
+}
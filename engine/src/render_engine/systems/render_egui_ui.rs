@@ -1,3 +1,4 @@
+use crate::render_engine::gpu_profiler::GpuProfiler;
 use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
 use crate::render_engine::RenderEngineResources;
 use bevy_ecs::system::Res;
@@ -9,6 +10,7 @@ pub fn ecs_render_egui_ui(
     engine: Res<RenderEngineResources>,
     frame: Res<FrameResources>,
     command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
 ) {
     puffin::profile_function!();
     //let frame = match frame.deref() {
@@ -21,16 +23,19 @@ pub fn ecs_render_egui_ui(
         .create_command_encoder(&CommandEncoderDescriptor {
             label: Some("ecs_render_egui_ui_encoder"),
         });
+    let gpu_scope = gpu_profiler.scope("egui");
+    gpu_scope.begin(&mut encoder);
 
     engine.egui_integration.lock().render(
         &engine.window,
         &mut encoder,
         &engine.device,
         &engine.queue,
-        &frame.view,
+        &frame.output_view,
         &engine.config,
         engine.egui_debug_ui.write().deref_mut(),
     );
 
+    gpu_scope.end(&mut encoder);
     command_collector.push(encoder);
 }
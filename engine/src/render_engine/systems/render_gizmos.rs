@@ -0,0 +1,65 @@
+use crate::asset_management::ToUuid;
+use crate::ecs::resources::DeltaTime;
+use crate::pipelines;
+use crate::render_engine::buffer::GpuVertexBuffer;
+use crate::render_engine::gizmos::Gizmos;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use wgpu::{CommandEncoderDescriptor, RenderPassDescriptor};
+
+pub fn ecs_render_gizmos(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+    gizmos: Res<Gizmos>,
+    dt: Res<DeltaTime>,
+) {
+    puffin::profile_function!();
+
+    let vertices = gizmos.vertices();
+    gizmos.tick(dt.as_secs_f32());
+
+    if vertices.is_empty() {
+        return;
+    }
+
+    let vertex_buf = {
+        puffin::profile_scope!("create_vertex_buffer");
+        GpuVertexBuffer::new(&engine.device, &vertices, Some("Gizmo VB"))
+    };
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_render_gizmos_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("gizmos");
+    gpu_scope.begin(&mut encoder);
+
+    let pipeline = {
+        puffin::profile_scope!("get_render_pipeline");
+        engine
+            .pipelines
+            .get_render_pipeline(pipelines::gizmo::GizmoRenderPipeline.uuid())
+    };
+
+    {
+        puffin::profile_scope!("draw_gizmos");
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Gizmo pass"),
+            color_attachments: &[engine.frame_color_attachment(&frame.view)],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &engine.camera.bind_group(), &[]);
+        render_pass.set_vertex_buffer(0, vertex_buf.slice(..));
+        render_pass.draw(0..vertex_buf.data_count(), 0..1);
+    }
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
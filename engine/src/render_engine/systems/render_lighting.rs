@@ -0,0 +1,161 @@
+use crate::asset_management::ToUuid;
+use crate::pipelines;
+use crate::render_engine::components::light::{DirectionalLight, PointLight, SpotLight};
+use crate::render_engine::components::occluder::{Occluder, ShadowMode};
+use crate::render_engine::components::position::Position;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::resources::lighting::{GpuLight, GpuOccluderEdge, LightKind};
+use crate::render_engine::resources::{
+    AmbientLight, CommandBufferCollector, FrameResources, LightingResources,
+};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use cgmath::{InnerSpace, SquareMatrix};
+use wgpu::{CommandEncoderDescriptor, RenderPassDescriptor};
+
+pub fn ecs_render_lighting(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    lighting: Res<LightingResources>,
+    ambient: Res<AmbientLight>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+    point_lights: Query<(&Position, &PointLight)>,
+    spot_lights: Query<(&Position, &SpotLight)>,
+    directional_lights: Query<&DirectionalLight>,
+    occluders: Query<(&Position, &Occluder)>,
+) {
+    puffin::profile_function!();
+
+    let lights = {
+        puffin::profile_scope!("collect_lights");
+        let mut lights = Vec::new();
+
+        for (pos, light) in point_lights.iter() {
+            lights.push(GpuLight {
+                position: [
+                    pos.0.x,
+                    pos.0.y,
+                    light.radius,
+                    LightKind::Point as u32 as f32,
+                ],
+                color: [
+                    light.color[0],
+                    light.color[1],
+                    light.color[2],
+                    light.intensity,
+                ],
+                direction: [0.0, 0.0, 0.0, light.height],
+                cone: [0.0; 4],
+            });
+        }
+
+        for (pos, light) in spot_lights.iter() {
+            let direction = light.direction.normalize();
+            lights.push(GpuLight {
+                position: [
+                    pos.0.x,
+                    pos.0.y,
+                    light.radius,
+                    LightKind::Spot as u32 as f32,
+                ],
+                color: [
+                    light.color[0],
+                    light.color[1],
+                    light.color[2],
+                    light.intensity,
+                ],
+                direction: [direction.x, direction.y, 0.0, light.height],
+                cone: [light.inner_angle.cos(), light.outer_angle.cos(), 0.0, 0.0],
+            });
+        }
+
+        for light in directional_lights.iter() {
+            let direction = cgmath::Vector3::from(light.direction).normalize();
+            lights.push(GpuLight {
+                position: [0.0, 0.0, 0.0, LightKind::Directional as u32 as f32],
+                color: [
+                    light.color[0],
+                    light.color[1],
+                    light.color[2],
+                    light.intensity,
+                ],
+                direction: [direction.x, direction.y, direction.z, 0.0],
+                cone: [0.0; 4],
+            });
+        }
+
+        lights
+    };
+
+    if lights.is_empty() && ambient.is_white() {
+        // the lighting pass wouldn't change anything
+        return;
+    }
+
+    let edges = {
+        puffin::profile_scope!("collect_occluders");
+        let mut edges = Vec::new();
+        for (pos, occluder) in occluders.iter() {
+            let softness = match occluder.mode {
+                ShadowMode::Hard => 0.0,
+                ShadowMode::Soft => occluder.softness.max(f32::EPSILON),
+            };
+
+            for [a, b] in occluder.edges(pos.0.truncate()) {
+                edges.push(GpuOccluderEdge {
+                    points: [a.x, a.y, b.x, b.y],
+                    params: [softness, 0.0, 0.0, 0.0],
+                });
+            }
+        }
+        edges
+    };
+
+    {
+        puffin::profile_scope!("upload_lights");
+        let inv_view_proj = engine
+            .camera
+            .ortho_proj_matrix()
+            .invert()
+            .expect("Ortho projection matrix to be inversible");
+        lighting.update(
+            &engine.queue,
+            inv_view_proj.into(),
+            *ambient,
+            &lights,
+            &edges,
+        );
+    }
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_render_lighting_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("lighting");
+    gpu_scope.begin(&mut encoder);
+
+    let pipeline = {
+        puffin::profile_scope!("get_render_pipeline");
+        engine
+            .pipelines
+            .get_render_pipeline(pipelines::lighting::LightingRenderPipeline.uuid())
+    };
+
+    {
+        puffin::profile_scope!("lighting_pass");
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Lighting pass"),
+            color_attachments: &[engine.frame_color_attachment(&frame.view)],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, lighting.bind_group(), &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
@@ -0,0 +1,280 @@
+use crate::asset_management::ToUuid;
+use crate::pipelines;
+use crate::pipelines::sprite::SpritePushConstant;
+use crate::pipelines::sprite_mask::SpriteMaskPushConstant;
+use crate::render_engine::camera::Camera;
+use crate::render_engine::components::mask::{MaskShape, MaskedBy, SpriteMask};
+use crate::render_engine::components::mesh::Mesh2D;
+use crate::render_engine::components::nine_slice::NineSlice;
+use crate::render_engine::components::palette_swap::PaletteSwap;
+use crate::render_engine::components::parallax::ParallaxLayer;
+use crate::render_engine::components::position::Position;
+use crate::render_engine::components::texture::Texture;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources, MaskStencil};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use cgmath::Vector2;
+use log::debug;
+use std::collections::BTreeMap;
+use wgpu::{
+    CommandEncoderDescriptor, LoadOp, Operations, RenderPass, RenderPassDepthStencilAttachment,
+    RenderPassDescriptor, ShaderStages,
+};
+use winit::dpi::PhysicalSize;
+
+/// masks nested deeper than this aren't drawn, which also catches masks that mask each other
+const MAX_MASK_DEPTH: usize = 32;
+
+type MaskQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Position,
+        &'static Texture,
+        &'static SpriteMask,
+        Option<&'static MaskedBy>,
+    ),
+>;
+
+/// draws the sprites that are `MaskedBy` a `SpriteMask`, over the other sprites, static sprites
+/// are masked too, they aren't in the static bundles
+///
+/// Masks that are rectangles all the way up are clipped with a scissor rect in one pass. The
+/// other masks get a pass each, which clears the stencil and writes every mask around the sprites
+/// into it, so they're meant for a handful of scroll views and reveal effects, not for every
+/// sprite in the scene.
+pub fn ecs_render_masked_sprites(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+    mut stencil: ResMut<MaskStencil>,
+    masks: MaskQuery,
+    sprites: Query<
+        (&Position, &Texture, &MaskedBy),
+        (
+            Without<SpriteMask>,
+            Without<NineSlice>,
+            Without<Mesh2D>,
+            Without<ParallaxLayer>,
+            Without<PaletteSwap>,
+        ),
+    >,
+) {
+    puffin::profile_function!();
+
+    let half_view = engine.camera.view_size() / 2.0;
+    let (view_min, view_max) = (
+        engine.camera.position - half_view,
+        engine.camera.position + half_view,
+    );
+
+    // the visible sprites of every mask, ordered by the mask so they're drawn in the same order
+    // every frame
+    let mut groups: BTreeMap<Entity, Vec<(&Position, &Texture)>> = BTreeMap::new();
+    for (pos, tex, masked_by) in sprites.iter() {
+        let (min, max) = crate::sprite::compute_bounds(pos.0);
+        if max.x >= view_min.x && min.x <= view_max.x && max.y >= view_min.y && min.y <= view_max.y
+        {
+            groups.entry(masked_by.0).or_default().push((pos, tex));
+        }
+    }
+    if groups.is_empty() {
+        return;
+    }
+
+    let mut scissored = Vec::new();
+    let mut stenciled = Vec::new();
+    for (mask, sprites) in groups {
+        let chain = match mask_chain(&masks, mask) {
+            Some(chain) => chain,
+            None => continue,
+        };
+        if chain
+            .iter()
+            .all(|(_, _, m)| m.shape == MaskShape::Rectangle)
+        {
+            // a rect outside of the view has nothing to draw
+            if let Some(rect) = scissor_rect(&engine.camera, engine.size, &chain) {
+                scissored.push((rect, sprites));
+            }
+        } else {
+            stenciled.push((chain, sprites));
+        }
+    }
+
+    let (pipeline, mask_pipeline, masked_pipeline) = {
+        puffin::profile_scope!("get_render_pipeline");
+        (
+            engine
+                .pipelines
+                .get_render_pipeline(pipelines::sprite::SpriteRenderPipeline.uuid()),
+            engine
+                .pipelines
+                .get_render_pipeline(pipelines::sprite_mask::SpriteMaskRenderPipeline.uuid()),
+            engine
+                .pipelines
+                .get_render_pipeline(pipelines::sprite_mask::MaskedSpriteRenderPipeline.uuid()),
+        )
+    };
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_render_masked_sprites_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("masked_sprites");
+    gpu_scope.begin(&mut encoder);
+
+    if !scissored.is_empty() {
+        puffin::profile_scope!("draw_scissored_sprites");
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Scissored sprite pass"),
+            color_attachments: &[engine.frame_color_attachment(&frame.view)],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&pipeline);
+        set_sprite_buffers(&mut render_pass, &engine);
+        for ((x, y, width, height), sprites) in scissored.iter() {
+            render_pass.set_scissor_rect(*x, *y, *width, *height);
+            draw_sprites(&mut render_pass, &engine, sprites);
+        }
+    }
+
+    if !stenciled.is_empty() {
+        puffin::profile_scope!("draw_stenciled_sprites");
+        let stencil_view = stencil.view(&engine.device, engine.size, engine.msaa_samples());
+        for (chain, sprites) in stenciled.iter() {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Masked sprite pass"),
+                color_attachments: &[engine.frame_color_attachment(&frame.view)],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: stencil_view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: Some(Operations {
+                        load: LoadOp::Clear(0),
+                        store: false,
+                    }),
+                }),
+            });
+
+            // every mask only increments the stencil where the masks around it were written
+            render_pass.set_pipeline(&mask_pipeline);
+            set_sprite_buffers(&mut render_pass, &engine);
+            for (depth, (pos, tex, mask)) in chain.iter().enumerate() {
+                let cutoff = match mask.shape {
+                    MaskShape::Alpha { cutoff } => cutoff,
+                    MaskShape::Rectangle => -1.0,
+                };
+                let uniform = SpriteMaskPushConstant::new(
+                    SpritePushConstant::new(crate::sprite::compute_model_matrix(pos.0), pos.0.z),
+                    cutoff,
+                );
+                render_pass.set_stencil_reference(depth as u32);
+                render_pass.set_push_constants(
+                    ShaderStages::VERTEX_FRAGMENT,
+                    0,
+                    &uniform.as_bytes(),
+                );
+                render_pass.set_bind_group(1, unsafe { tex.0.load().static_bind_group() }, &[]);
+                render_pass.draw_indexed(0..engine.sprite_square_index_buf.data_count(), 0, 0..1);
+            }
+
+            render_pass.set_pipeline(&masked_pipeline);
+            set_sprite_buffers(&mut render_pass, &engine);
+            render_pass.set_stencil_reference(chain.len() as u32);
+            draw_sprites(&mut render_pass, &engine, sprites);
+        }
+    }
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
+
+/// the masks the sprites of `mask` are in, from the outermost one to `mask`, `None` when one of
+/// them is gone
+fn mask_chain<'a>(
+    masks: &'a MaskQuery,
+    mask: Entity,
+) -> Option<Vec<(&'a Position, &'a Texture, &'a SpriteMask)>> {
+    let mut chain = Vec::new();
+    let mut next = Some(mask);
+    while let Some(entity) = next {
+        if chain.len() == MAX_MASK_DEPTH {
+            debug!(
+                "The masks around {:?} are nested more than {} deep",
+                mask, MAX_MASK_DEPTH
+            );
+            return None;
+        }
+
+        let (pos, tex, sprite_mask, masked_by) = masks.get(entity).ok()?;
+        chain.push((pos, tex, sprite_mask));
+        next = masked_by.map(|m| m.0);
+    }
+
+    chain.reverse();
+    Some(chain)
+}
+
+/// the pixels inside all the rectangle masks, `None` when they don't overlap on the screen
+fn scissor_rect(
+    camera: &Camera,
+    size: PhysicalSize<u32>,
+    chain: &[(&Position, &Texture, &SpriteMask)],
+) -> Option<(u32, u32, u32, u32)> {
+    let (mut min, mut max) = (
+        Vector2::new(f32::MIN, f32::MIN),
+        Vector2::new(f32::MAX, f32::MAX),
+    );
+    for (pos, _, _) in chain {
+        let (mask_min, mask_max) = crate::sprite::compute_bounds(pos.0);
+        min = Vector2::new(min.x.max(mask_min.x), min.y.max(mask_min.y));
+        max = Vector2::new(max.x.min(mask_max.x), max.y.min(mask_max.y));
+    }
+
+    // the top of the world is the top of the screen, but the y of the pixels goes down
+    let top_left = camera.world_to_screen_space(Vector2::new(min.x, max.y));
+    let bottom_right = camera.world_to_screen_space(Vector2::new(max.x, min.y));
+    let clamp = |v: f32, max: u32| v.round().max(0.0).min(max as f32) as u32;
+    let (left, top) = (
+        clamp(top_left.x, size.width),
+        clamp(top_left.y, size.height),
+    );
+    let (right, bottom) = (
+        clamp(bottom_right.x, size.width),
+        clamp(bottom_right.y, size.height),
+    );
+
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some((left, top, right - left, bottom - top))
+}
+
+fn set_sprite_buffers<'a>(render_pass: &mut RenderPass<'a>, engine: &'a RenderEngineResources) {
+    render_pass.set_vertex_buffer(0, engine.sprite_square_vertex_buf.slice(..));
+    render_pass.set_index_buffer(
+        engine.sprite_square_index_buf.slice(..),
+        engine.sprite_square_index_buf.index_format(),
+    );
+    render_pass.set_bind_group(0, engine.camera.bind_group(), &[]);
+}
+
+fn draw_sprites<'a>(
+    render_pass: &mut RenderPass<'a>,
+    engine: &'a RenderEngineResources,
+    sprites: &[(&'a Position, &'a Texture)],
+) {
+    for (pos, tex) in sprites {
+        let uniform = SpritePushConstant::new(crate::sprite::compute_model_matrix(pos.0), pos.0.z);
+        render_pass.set_push_constants(ShaderStages::VERTEX, 0, &uniform.as_bytes());
+        render_pass.set_bind_group(1, unsafe { tex.0.load().static_bind_group() }, &[]);
+        render_pass.draw_indexed(0..engine.sprite_square_index_buf.data_count(), 0, 0..1);
+    }
+}
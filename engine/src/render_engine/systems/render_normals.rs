@@ -0,0 +1,135 @@
+use crate::asset_management::ToUuid;
+use crate::pipelines;
+use crate::pipelines::sprite::SpritePushConstant;
+use crate::render_engine::components::light::{DirectionalLight, PointLight, SpotLight};
+use crate::render_engine::components::mask::SpriteMask;
+use crate::render_engine::components::normal_map::NormalMap;
+use crate::render_engine::components::parallax::ParallaxLayer;
+use crate::render_engine::components::position::Position;
+use crate::render_engine::components::texture::Texture;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources, LightingResources};
+use crate::render_engine::spatial_grid::SpatialGrid;
+use crate::render_engine::static_bundles::StaticSpriteBundles;
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use wgpu::{
+    Color, CommandEncoderDescriptor, LoadOp, Operations, RenderPassColorAttachment,
+    RenderPassDescriptor, ShaderStages,
+};
+
+pub fn ecs_render_normals(
+    engine: Res<RenderEngineResources>,
+    _frame: Res<FrameResources>,
+    mut lighting: ResMut<LightingResources>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+    grid: Res<SpatialGrid>,
+    static_bundles: Res<StaticSpriteBundles>,
+    // parallax layers keep the cleared normal, as they have no depth to shade
+    // masks aren't drawn, so they don't have normals either
+    sprites: Query<
+        (&Position, &Texture, Option<&NormalMap>),
+        (Without<ParallaxLayer>, Without<SpriteMask>),
+    >,
+    lights: Query<(), Or<(With<PointLight>, With<SpotLight>, With<DirectionalLight>)>>,
+) {
+    puffin::profile_function!();
+
+    lighting.resize_if_needed(&engine.device, engine.size);
+
+    if lights.is_empty() {
+        // nothing reads the normals when there's only ambient light
+        return;
+    }
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_render_normals_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("normals");
+    gpu_scope.begin(&mut encoder);
+
+    let pipeline = {
+        puffin::profile_scope!("get_render_pipeline");
+        engine
+            .pipelines
+            .get_render_pipeline(pipelines::lighting::NormalRenderPipeline.uuid())
+    };
+
+    let mut render_pass = {
+        puffin::profile_scope!("begin_render_pass");
+        let mut rp = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Normal pass"),
+            color_attachments: &[RenderPassColorAttachment {
+                view: lighting.normal_view(),
+                resolve_target: None,
+                ops: Operations {
+                    // a normal pointing straight at the camera
+                    load: LoadOp::Clear(Color {
+                        r: 0.5,
+                        g: 0.5,
+                        b: 1.0,
+                        a: 1.0,
+                    }),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        rp.set_pipeline(&pipeline);
+        rp.set_vertex_buffer(0, engine.sprite_square_vertex_buf.slice(..));
+        rp.set_index_buffer(
+            engine.sprite_square_index_buf.slice(..),
+            engine.sprite_square_index_buf.index_format(),
+        );
+        rp.set_bind_group(0, &engine.camera.bind_group(), &[]);
+        rp
+    };
+
+    {
+        puffin::profile_scope!("draw_normals");
+        // the same sprites as in ecs_render_sprites, the ones off-screen don't need normals
+        let half_view = engine.camera.view_size() / 2.0;
+        let (view_min, view_max) = (
+            engine.camera.position - half_view,
+            engine.camera.position + half_view,
+        );
+        // static sprites aren't in the grid, and are below the others
+        let static_entities: Vec<_> = static_bundles
+            .visible_chunks(view_min, view_max)
+            .into_iter()
+            .filter_map(|c| static_bundles.chunk(c))
+            .flat_map(|c| c.entities().iter().copied())
+            .collect();
+        let visible = static_entities
+            .into_iter()
+            .chain(grid.query(view_min, view_max));
+        for (pos, tex, normal) in visible.filter_map(|e| sprites.get(e).ok()) {
+            let uniform =
+                SpritePushConstant::new(crate::sprite::compute_model_matrix(pos.0), pos.0.z);
+
+            render_pass.set_push_constants(ShaderStages::VERTEX, 0, &uniform.as_bytes());
+            render_pass.set_bind_group(1, unsafe { tex.0.load().static_bind_group() }, &[]);
+            match normal {
+                Some(normal) => render_pass.set_bind_group(
+                    2,
+                    unsafe { normal.0.load().static_bind_group() },
+                    &[],
+                ),
+                None => render_pass.set_bind_group(2, lighting.flat_normal().bind_group(), &[]),
+            }
+            render_pass.draw_indexed(0..engine.sprite_square_index_buf.data_count(), 0, 0..1);
+        }
+    }
+
+    {
+        puffin::profile_scope!("end_render_pass");
+        drop(render_pass);
+    }
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
@@ -0,0 +1,120 @@
+use crate::asset_management::ToUuid;
+use crate::pipelines;
+use crate::pipelines::palette_sprite::PaletteSpritePushConstant;
+use crate::pipelines::sprite::SpritePushConstant;
+use crate::render_engine::components::mask::{MaskedBy, SpriteMask};
+use crate::render_engine::components::mesh::Mesh2D;
+use crate::render_engine::components::nine_slice::NineSlice;
+use crate::render_engine::components::palette_swap::PaletteSwap;
+use crate::render_engine::components::parallax::ParallaxLayer;
+use crate::render_engine::components::position::Position;
+use crate::render_engine::components::texture::Texture;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::palettes::Palettes;
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use log::debug;
+use wgpu::{CommandEncoderDescriptor, RenderPassDescriptor, ShaderStages};
+
+type PaletteSpriteQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Position, &'static Texture, &'static PaletteSwap),
+    (
+        Without<NineSlice>,
+        Without<Mesh2D>,
+        Without<ParallaxLayer>,
+        Without<SpriteMask>,
+        // the masked pass can't draw palettes, so masked palette sprites aren't drawn at all
+        Without<MaskedBy>,
+    ),
+>;
+
+/// draws the sprites with a `PaletteSwap` over the other sprites, static ones included
+pub fn ecs_render_palette_sprites(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+    palettes: Res<Palettes>,
+    sprites: PaletteSpriteQuery,
+) {
+    puffin::profile_function!();
+
+    let half_view = engine.camera.view_size() / 2.0;
+    let (view_min, view_max) = (
+        engine.camera.position - half_view,
+        engine.camera.position + half_view,
+    );
+
+    let visible: Vec<_> = {
+        puffin::profile_scope!("cull_sprites");
+        sprites
+            .iter()
+            .filter(|(pos, _, _)| {
+                let (min, max) = crate::sprite::compute_bounds(pos.0);
+                max.x >= view_min.x
+                    && min.x <= view_max.x
+                    && max.y >= view_min.y
+                    && min.y <= view_max.y
+            })
+            // the textures are loaded here, so they live until the pass is encoded
+            .filter_map(|(pos, tex, swap)| match palettes.get(&swap.palette) {
+                Some(palette) => Some((pos, tex.0.load(), palette.load(), swap.row)),
+                None => {
+                    debug!("There's no palette named '{}'", swap.palette);
+                    None
+                }
+            })
+            .collect()
+    };
+    if visible.is_empty() {
+        return;
+    }
+
+    let pipeline = {
+        puffin::profile_scope!("get_render_pipeline");
+        engine
+            .pipelines
+            .get_render_pipeline(pipelines::palette_sprite::PaletteSpriteRenderPipeline.uuid())
+    };
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_render_palette_sprites_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("palette_sprites");
+    gpu_scope.begin(&mut encoder);
+
+    {
+        puffin::profile_scope!("draw_palette_sprites");
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Palette sprite pass"),
+            color_attachments: &[engine.frame_color_attachment(&frame.view)],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_vertex_buffer(0, engine.sprite_square_vertex_buf.slice(..));
+        render_pass.set_index_buffer(
+            engine.sprite_square_index_buf.slice(..),
+            engine.sprite_square_index_buf.index_format(),
+        );
+        render_pass.set_bind_group(0, engine.camera.bind_group(), &[]);
+        for (pos, texture, palette, row) in visible.iter() {
+            let uniform = PaletteSpritePushConstant::new(
+                SpritePushConstant::new(crate::sprite::compute_model_matrix(pos.0), pos.0.z),
+                *row,
+            );
+            render_pass.set_push_constants(ShaderStages::VERTEX_FRAGMENT, 0, &uniform.as_bytes());
+            render_pass.set_bind_group(1, texture.bind_group(), &[]);
+            render_pass.set_bind_group(2, palette.bind_group(), &[]);
+            render_pass.draw_indexed(0..engine.sprite_square_index_buf.data_count(), 0, 0..1);
+        }
+    }
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
@@ -0,0 +1,102 @@
+use crate::asset_management::ToUuid;
+use crate::pipelines;
+use crate::render_engine::buffer::GpuVertexBuffer;
+use crate::render_engine::components::parallax::ParallaxLayer;
+use crate::render_engine::components::position::Position;
+use crate::render_engine::components::texture::Texture;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use wgpu::{CommandEncoderDescriptor, RenderPassDescriptor};
+
+pub fn ecs_render_parallax(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+    layers: Query<(&Position, &Texture, &ParallaxLayer)>,
+) {
+    puffin::profile_function!();
+
+    if layers.is_empty() {
+        return;
+    }
+
+    // the layers furthest away (that follow the camera the least) are drawn first
+    let mut sorted: Vec<_> = layers.iter().collect();
+    sorted.sort_by(|a, b| {
+        let a = a.2.scroll_factor.x + a.2.scroll_factor.y;
+        let b = b.2.scroll_factor.x + b.2.scroll_factor.y;
+        a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let (instances, draws) = {
+        puffin::profile_scope!("generate_parallax_tiles");
+        let mut instances = Vec::new();
+        let mut draws = Vec::new();
+        for (pos, tex, layer) in sorted {
+            let texture = tex.0.load();
+            let size = texture.size();
+
+            let start = instances.len() as u32;
+            instances.extend(layer.instances(pos.0, &engine.camera, (size.width, size.height)));
+            draws.push((
+                unsafe { texture.static_bind_group() },
+                start..instances.len() as u32,
+            ));
+        }
+        (instances, draws)
+    };
+
+    if instances.is_empty() {
+        return;
+    }
+
+    let instance_buf =
+        GpuVertexBuffer::new(&engine.device, &instances, Some("Parallax instance VB"));
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_render_parallax_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("parallax");
+    gpu_scope.begin(&mut encoder);
+
+    let pipeline = {
+        puffin::profile_scope!("get_render_pipeline");
+        engine
+            .pipelines
+            .get_render_pipeline(pipelines::instanced_sprite::InstancedSpriteRenderPipeline.uuid())
+    };
+
+    {
+        puffin::profile_scope!("draw_parallax_layers");
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Parallax pass"),
+            color_attachments: &[engine.frame_color_attachment(&frame.view)],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &engine.camera.bind_group(), &[]);
+        render_pass.set_vertex_buffer(0, engine.sprite_square_vertex_buf.slice(..));
+        render_pass.set_vertex_buffer(1, instance_buf.slice(..));
+        render_pass.set_index_buffer(
+            engine.sprite_square_index_buf.slice(..),
+            engine.sprite_square_index_buf.index_format(),
+        );
+
+        for (bind_group, range) in draws {
+            if range.is_empty() {
+                continue;
+            }
+            render_pass.set_bind_group(1, bind_group, &[]);
+            render_pass.draw_indexed(0..engine.sprite_square_index_buf.data_count(), 0, range);
+        }
+    }
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
@@ -0,0 +1,83 @@
+use crate::asset_management::ToUuid;
+use crate::pipelines;
+use crate::render_engine::components::particle_emitter::ParticleEmitter;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::particles::{ParticleBuffers, ParticleJobs};
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use log::warn;
+use wgpu::{CommandEncoderDescriptor, RenderPassDescriptor};
+
+pub fn ecs_render_particles(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+    jobs: Res<ParticleJobs>,
+    emitters: Query<(&ParticleEmitter, &ParticleBuffers)>,
+) {
+    puffin::profile_function!();
+
+    {
+        puffin::profile_scope!("wait_for_cpu_simulation");
+        for job in jobs.take() {
+            if job.flush().is_err() {
+                warn!("Particle simulation job failed, particles may be out of date");
+            }
+        }
+    }
+
+    if emitters.is_empty() {
+        return;
+    }
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_render_particles_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("particles");
+    gpu_scope.begin(&mut encoder);
+
+    let pipeline = {
+        puffin::profile_scope!("get_render_pipeline");
+        engine
+            .pipelines
+            .get_render_pipeline(pipelines::instanced_sprite::InstancedSpriteRenderPipeline.uuid())
+    };
+
+    {
+        puffin::profile_scope!("draw_particles");
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Particle pass"),
+            color_attachments: &[engine.frame_color_attachment(&frame.view)],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_vertex_buffer(0, engine.sprite_square_vertex_buf.slice(..));
+        render_pass.set_index_buffer(
+            engine.sprite_square_index_buf.slice(..),
+            engine.sprite_square_index_buf.index_format(),
+        );
+        render_pass.set_bind_group(0, &engine.camera.bind_group(), &[]);
+
+        for (emitter, buffers) in emitters.iter() {
+            render_pass.set_bind_group(
+                1,
+                unsafe { emitter.texture.load().static_bind_group() },
+                &[],
+            );
+            render_pass.set_vertex_buffer(1, buffers.instances().slice(..));
+            render_pass.draw_indexed(
+                0..engine.sprite_square_index_buf.data_count(),
+                0,
+                0..buffers.capacity(),
+            );
+        }
+    }
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
@@ -1,23 +1,55 @@
 use crate::asset_management::ToUuid;
 use crate::pipelines;
 use crate::pipelines::sprite::SpritePushConstant;
+use crate::render_engine::buffer::GpuVertexBuffer;
+use crate::render_engine::components::mask::{MaskedBy, SpriteMask};
+use crate::render_engine::components::mesh::Mesh2D;
+use crate::render_engine::components::nine_slice::NineSlice;
+use crate::render_engine::components::palette_swap::PaletteSwap;
+use crate::render_engine::components::parallax::ParallaxLayer;
 use crate::render_engine::components::position::Position;
+use crate::render_engine::components::static_sprite::Static;
 use crate::render_engine::components::texture::Texture;
+use crate::render_engine::gpu_profiler::GpuProfiler;
 use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
+use crate::render_engine::spatial_grid::SpatialGrid;
+use crate::render_engine::static_bundles::StaticSpriteBundles;
 use crate::render_engine::RenderEngineResources;
 use bevy_ecs::prelude::*;
+use bevy_tasks::{ComputeTaskPool, TaskPool};
 use parking_lot::Mutex;
 use std::ops::Deref;
 use wgpu::{
-    Color, CommandBuffer, CommandEncoderDescriptor, LoadOp, Operations, RenderPassColorAttachment,
+    Color, CommandBuffer, CommandEncoder, CommandEncoderDescriptor, RenderPass,
     RenderPassDescriptor, ShaderStages,
 };
 
+/// the visible sprites are split into batches of this size, which are encoded in parallel
+const SPRITES_PER_ENCODER: usize = 2048;
+
+/// the sprites drawn as a plain quad, static or not, the others are drawn by passes of their own
+pub type PlainSpriteFilter = (
+    Without<NineSlice>,
+    Without<Mesh2D>,
+    Without<ParallaxLayer>,
+    // masks aren't drawn, and masked sprites are drawn by ecs_render_masked_sprites
+    Without<SpriteMask>,
+    Without<MaskedBy>,
+    // palette swapped sprites are drawn by ecs_render_palette_sprites
+    Without<PaletteSwap>,
+);
+
 pub fn ecs_render_sprites(
     engine: Res<RenderEngineResources>,
     frame: Res<FrameResources>,
     command_collector: Res<CommandBufferCollector>,
-    sprites: Query<(&Position, &Texture)>,
+    gpu_profiler: Res<GpuProfiler>,
+    grid: Res<SpatialGrid>,
+    mut static_bundles: ResMut<StaticSpriteBundles>,
+    sprites: Query<(&Position, &Texture), (PlainSpriteFilter, Without<Static>)>,
+    static_sprites: Query<(&Position, &Texture), (PlainSpriteFilter, With<Static>)>,
+    // the masked pass can't draw slices, so masked nine-slices aren't drawn at all
+    nine_slices: Query<(&Position, &Texture, &NineSlice), Without<MaskedBy>>,
 ) {
     puffin::profile_function!();
 
@@ -26,71 +58,233 @@ pub fn ecs_render_sprites(
     //    None => panic!("FrameResources not initialized!"),
     //};
 
-    let mut encoder = engine
-        .device
-        .create_command_encoder(&CommandEncoderDescriptor {
-            label: Some("ecs_render_sprites_encoder"),
-        });
-
-    let pipeline = {
+    let (pipeline, instanced_pipeline) = {
         puffin::profile_scope!("get_render_pipeline");
-        engine
-            .pipelines
-            .get_render_pipeline(pipelines::sprite::SpriteRenderPipeline.uuid())
+        (
+            engine
+                .pipelines
+                .get_render_pipeline(pipelines::sprite::SpriteRenderPipeline.uuid()),
+            engine.pipelines.get_render_pipeline(
+                pipelines::instanced_sprite::InstancedSpriteRenderPipeline.uuid(),
+            ),
+        )
     };
 
-    //let tex = engine.sprites.get(&0).unwrap().texture.load();
+    // the slices of all nine-slice sprites go into one instance buffer, with a range per sprite
+    let (slice_instances, slice_draws) = {
+        puffin::profile_scope!("generate_nine_slices");
+        let mut instances = Vec::new();
+        let mut draws = Vec::new();
+        for (pos, tex, nine_slice) in nine_slices.iter() {
+            let texture = tex.0.load();
+            let insets = nine_slice
+                .insets
+                .or(texture.metadata().nine_slice)
+                .unwrap_or_default();
+            let size = texture.size();
 
-    let mut render_pass = {
-        puffin::profile_scope!("begin_render_pass");
-        let mut rp = encoder.begin_render_pass(&RenderPassDescriptor {
-            label: None,
-            color_attachments: &[RenderPassColorAttachment {
-                view: &frame.view,
-                resolve_target: None,
-                ops: Operations {
-                    load: LoadOp::Load,
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: None,
-        });
-
-        rp.set_pipeline(&pipeline);
-        rp.set_vertex_buffer(0, engine.sprite_square_vertex_buf.slice(..));
-        rp.set_index_buffer(
-            engine.sprite_square_index_buf.slice(..),
-            engine.sprite_square_index_buf.index_format(),
-        );
-        rp.set_bind_group(0, &engine.camera.bind_group(), &[]);
-        rp
+            let start = instances.len() as u32;
+            instances.extend(nine_slice.instances(pos.0, insets, (size.width, size.height)));
+            draws.push((
+                unsafe { texture.static_bind_group() },
+                start..instances.len() as u32,
+            ));
+        }
+        (instances, draws)
+    };
+
+    let slice_instance_buf = if slice_instances.is_empty() {
+        None
+    } else {
+        Some(GpuVertexBuffer::new(
+            &engine.device,
+            &slice_instances,
+            Some("Nine-slice instance VB"),
+        ))
     };
 
-    {
-        puffin::profile_scope!("draw_sprites");
-        for (pos, tex) in sprites.iter() {
-            let uniform =
-                SpritePushConstant::new(crate::sprite::compute_model_matrix(pos.0), pos.0.z);
-
-            //let model_mat = sprite.model_matrix();
-            //let model: [u8; 64] =
-            //    unsafe { std::mem::transmute(model_mat * cgmath::Matrix4::from_scale(200.0)) };
-            //render_pass.set_push_constants(ShaderStages::VERTEX, 0, &model);
-            //render_pass.set_push_constants(
-            //    ShaderStages::VERTEX,
-            //    std::mem::size_of_val(&model) as u32,
-            //    &[sprite.position.z as u8, 0, 0, 0],
-            //);
-            render_pass.set_push_constants(ShaderStages::VERTEX, 0, &uniform.as_bytes());
-            render_pass.set_bind_group(1, unsafe { tex.0.load().static_bind_group() }, &[]);
-            render_pass.draw_indexed(0..engine.sprite_square_index_buf.data_count(), 0, 0..1);
+    //let tex = engine.sprites.get(&0).unwrap().texture.load();
+
+    let half_view = engine.camera.view_size() / 2.0;
+    let (view_min, view_max) = (
+        engine.camera.position - half_view,
+        engine.camera.position + half_view,
+    );
+
+    // only the chunks that changed since they were last visible are recorded again
+    let static_chunks = {
+        puffin::profile_scope!("record_static_bundles");
+        let chunks = static_bundles.visible_chunks(view_min, view_max);
+        for key in chunks.iter() {
+            let chunk = static_bundles.chunk_mut(*key).unwrap();
+            let sprites: Vec<_> = chunk
+                .entities()
+                .iter()
+                .filter_map(|e| static_sprites.get(*e).ok())
+                .collect();
+            if chunk.is_outdated(
+                engine.msaa_samples(),
+                engine.scene_format(),
+                sprites.iter().map(|(_, tex)| *tex),
+            ) {
+                chunk.record(&engine, &pipeline, sprites.into_iter());
+            }
         }
-    }
+        chunks
+    };
+
+    // only the sprites in the cells the camera can see are visited
+    let visible: Vec<_> = {
+        puffin::profile_scope!("cull_sprites");
+        grid.query(view_min, view_max)
+            .iter()
+            .filter_map(|e| sprites.get(*e).ok())
+            .filter(|(pos, _)| {
+                // the cells can stick out of the view
+                let (min, max) = crate::sprite::compute_bounds(pos.0);
+                max.x >= view_min.x
+                    && min.x <= view_max.x
+                    && max.y >= view_min.y
+                    && min.y <= view_max.y
+            })
+            .collect()
+    };
+    let drawn = visible.len();
+
+    // the static bundles go in the first batch, so there's always one even without sprites
+    let batches: Vec<_> = if visible.is_empty() {
+        vec![&visible[..]]
+    } else {
+        visible.chunks(SPRITES_PER_ENCODER).collect()
+    };
 
-    {
-        puffin::profile_scope!("end_render_pass");
-        drop(render_pass);
-    }
+    // the batches are timed as one pass, from the start of the first to the end of the last
+    let gpu_scope = gpu_profiler.scope("sprites");
+    let last_batch = batches.len() - 1;
+
+    let command_buffers = {
+        puffin::profile_scope!("encode_sprite_batches");
+        let (engine, frame, static_bundles) = (&*engine, &*frame, &*static_bundles);
+        let (pipeline, instanced_pipeline) = (&pipeline, &instanced_pipeline);
+        let (static_chunks, slice_instance_buf) = (&static_chunks, &slice_instance_buf);
+
+        // the results are in the order the tasks are spawned, which is the draw order
+        ComputeTaskPool::get_or_init(TaskPool::default).scope(|s| {
+            for (i, batch) in batches.into_iter().enumerate() {
+                s.spawn(async move {
+                    puffin::profile_scope!("encode_sprite_batch");
+                    let mut encoder =
+                        engine
+                            .device
+                            .create_command_encoder(&CommandEncoderDescriptor {
+                                label: Some("ecs_render_sprites_encoder"),
+                            });
+                    if i == 0 {
+                        gpu_scope.begin(&mut encoder);
+                    }
+                    let mut render_pass = begin_sprite_pass(&mut encoder, engine, frame);
+
+                    if i == 0 {
+                        // static sprites are decoration, so they're below the others, executing
+                        // bundles resets the state of the pass so they go first
+                        render_pass.execute_bundles(
+                            static_chunks
+                                .iter()
+                                .filter_map(|c| static_bundles.chunk(*c).and_then(|c| c.bundle())),
+                        );
+                    }
+
+                    render_pass.set_pipeline(pipeline);
+                    set_sprite_buffers(&mut render_pass, engine);
+                    for (pos, tex) in batch {
+                        let uniform = SpritePushConstant::new(
+                            crate::sprite::compute_model_matrix(pos.0),
+                            pos.0.z,
+                        );
+                        render_pass.set_push_constants(
+                            ShaderStages::VERTEX,
+                            0,
+                            &uniform.as_bytes(),
+                        );
+                        render_pass.set_bind_group(
+                            1,
+                            unsafe { tex.0.load().static_bind_group() },
+                            &[],
+                        );
+                        render_pass.draw_indexed(
+                            0..engine.sprite_square_index_buf.data_count(),
+                            0,
+                            0..1,
+                        );
+                    }
+
+                    drop(render_pass);
+                    if i == last_batch && slice_instance_buf.is_none() {
+                        gpu_scope.end(&mut encoder);
+                    }
+                    encoder.finish()
+                });
+            }
+
+            // nine-slices were always drawn over the other sprites
+            if let Some(instance_buf) = slice_instance_buf {
+                let slice_draws = &slice_draws;
+                s.spawn(async move {
+                    puffin::profile_scope!("encode_nine_slices");
+                    let mut encoder =
+                        engine
+                            .device
+                            .create_command_encoder(&CommandEncoderDescriptor {
+                                label: Some("ecs_render_nine_slices_encoder"),
+                            });
+                    let mut render_pass = begin_sprite_pass(&mut encoder, engine, frame);
+
+                    render_pass.set_pipeline(instanced_pipeline);
+                    set_sprite_buffers(&mut render_pass, engine);
+                    render_pass.set_vertex_buffer(1, instance_buf.slice(..));
+                    for (bind_group, instances) in slice_draws.iter() {
+                        render_pass.set_bind_group(1, bind_group, &[]);
+                        render_pass.draw_indexed(
+                            0..engine.sprite_square_index_buf.data_count(),
+                            0,
+                            instances.clone(),
+                        );
+                    }
+
+                    drop(render_pass);
+                    gpu_scope.end(&mut encoder);
+                    encoder.finish()
+                });
+            }
+        })
+    };
+
+    engine.with_debug_ui(|d| {
+        let window = d.culling_window_mut();
+        window.set_counts(drawn, grid.len().saturating_sub(drawn));
+        window.set_static_counts(static_bundles.len(), static_chunks.len());
+    });
+
+    command_collector.push_buffers(command_buffers);
+}
+
+fn begin_sprite_pass<'a>(
+    encoder: &'a mut CommandEncoder,
+    engine: &'a RenderEngineResources,
+    frame: &'a FrameResources,
+) -> RenderPass<'a> {
+    encoder.begin_render_pass(&RenderPassDescriptor {
+        label: None,
+        color_attachments: &[engine.frame_color_attachment(&frame.view)],
+        depth_stencil_attachment: None,
+    })
+}
 
-    command_collector.push(encoder);
+fn set_sprite_buffers<'a>(render_pass: &mut RenderPass<'a>, engine: &'a RenderEngineResources) {
+    render_pass.set_vertex_buffer(0, engine.sprite_square_vertex_buf.slice(..));
+    render_pass.set_index_buffer(
+        engine.sprite_square_index_buf.slice(..),
+        engine.sprite_square_index_buf.index_format(),
+    );
+    render_pass.set_bind_group(0, engine.camera.bind_group(), &[]);
 }
@@ -0,0 +1,71 @@
+use crate::asset_management::ToUuid;
+use crate::pipelines;
+use crate::pipelines::mesh::MeshPushConstant;
+use crate::render_engine::components::position::Position;
+use crate::render_engine::components::trail::Trail;
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use cgmath::{Matrix4, SquareMatrix};
+use wgpu::{CommandEncoderDescriptor, RenderPassDescriptor, ShaderStages};
+
+/// draws the trails with the mesh pipeline, their vertices are already in world space
+pub fn ecs_render_trails(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+    trails: Query<(&Position, &Trail)>,
+) {
+    puffin::profile_function!();
+
+    if trails.iter().all(|(_, trail)| trail.mesh().is_none()) {
+        return;
+    }
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_render_trails_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("trails");
+    gpu_scope.begin(&mut encoder);
+
+    let pipeline = {
+        puffin::profile_scope!("get_render_pipeline");
+        engine
+            .pipelines
+            .get_render_pipeline(pipelines::mesh::MeshRenderPipeline.uuid())
+    };
+
+    {
+        puffin::profile_scope!("draw_trails");
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Trail pass"),
+            color_attachments: &[engine.frame_color_attachment(&frame.view)],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, engine.camera.bind_group(), &[]);
+
+        for (pos, trail) in trails.iter() {
+            let mesh = match trail.mesh() {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+            let index_buf = mesh.index_buffer();
+
+            let push_constant = MeshPushConstant::new(Matrix4::identity(), pos.0.z);
+            render_pass.set_push_constants(ShaderStages::VERTEX, 0, &push_constant.as_bytes());
+            render_pass.set_bind_group(1, unsafe { trail.texture.load().static_bind_group() }, &[]);
+            render_pass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+            render_pass.set_index_buffer(index_buf.slice(..), index_buf.index_format());
+            render_pass.draw_indexed(0..index_buf.data_count(), 0, 0..1);
+        }
+    }
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
@@ -0,0 +1,21 @@
+use crate::ecs::resources::DeltaTime;
+use crate::render_engine::components::parallax::ParallaxLayer;
+use crate::render_engine::components::texture::Texture;
+use bevy_ecs::prelude::*;
+
+pub fn ecs_scroll_parallax_layers(
+    dt: Res<DeltaTime>,
+    mut layers: Query<(&mut ParallaxLayer, &Texture)>,
+) {
+    puffin::profile_function!();
+    let dt = dt.as_secs_f32();
+
+    for (mut layer, tex) in layers.iter_mut() {
+        if layer.velocity.x == 0.0 && layer.velocity.y == 0.0 {
+            continue;
+        }
+
+        let size = tex.0.load().size();
+        layer.advance(dt, (size.width, size.height));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::asset_management::ToUuid;
+use crate::ecs::resources::DeltaTime;
+use crate::pipelines;
+use crate::render_engine::components::particle_emitter::ParticleEmitter;
+use crate::render_engine::components::position::Position;
+use crate::render_engine::particles::{EmitterUniform, ParticleBuffers, ParticleJobs};
+use crate::render_engine::resources::CommandBufferCollector;
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use wgpu::CommandEncoderDescriptor;
+
+pub fn simulate_particles(
+    mut commands: Commands,
+    engine: Res<RenderEngineResources>,
+    dt: Res<DeltaTime>,
+    command_collector: Res<CommandBufferCollector>,
+    jobs: Res<ParticleJobs>,
+    mut emitters: Query<(
+        Entity,
+        &Position,
+        &mut ParticleEmitter,
+        Option<&ParticleBuffers>,
+    )>,
+) {
+    puffin::profile_function!();
+    let dt = dt.as_secs_f32();
+    let compute = engine.compute_supported();
+
+    let mut encoder = None;
+
+    for (entity, pos, mut emitter, buffers) in emitters.iter_mut() {
+        let buffers = match buffers {
+            Some(b) if b.capacity() == emitter.max_particles => b,
+            _ => {
+                // the buffers will be there next frame
+                commands.entity(entity).insert(ParticleBuffers::new(
+                    &engine.device,
+                    emitter.max_particles,
+                    compute,
+                ));
+                emitter.reset_cursor();
+                continue;
+            }
+        };
+
+        let spawned = emitter.spawn(pos.0, dt);
+        let uniform = EmitterUniform::new(&emitter, dt);
+
+        if compute {
+            let encoder = encoder.get_or_insert_with(|| {
+                engine
+                    .device
+                    .create_command_encoder(&CommandEncoderDescriptor {
+                        label: Some("simulate_particles_encoder"),
+                    })
+            });
+            let pipeline = engine
+                .pipelines
+                .get_compute_pipeline(pipelines::particles::ParticleComputePipeline.uuid());
+            buffers.simulate_gpu(&engine.queue, encoder, &pipeline, uniform, &spawned);
+        } else {
+            jobs.push(buffers.simulate_cpu(uniform, spawned));
+        }
+    }
+
+    if let Some(encoder) = encoder {
+        command_collector.push(encoder);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::render_engine::capture::FrameCapture;
+use crate::render_engine::gpu_profiler::GpuProfiler;
 use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
 use crate::render_engine::RenderEngineResources;
 use bevy_ecs::system::{Commands, Res, ResMut};
@@ -12,11 +14,30 @@ pub fn ecs_render_submit_commands(
     command_buffers: Res<CommandBufferCollector>,
     engine: Res<RenderEngineResources>,
     mut frame: ResMut<FrameResources>,
+    mut gpu_profiler: ResMut<GpuProfiler>,
+    mut capture: ResMut<FrameCapture>,
 ) {
     puffin::profile_function!();
-    let collected_commands = command_buffers.take();
+    let mut collected_commands = command_buffers.take();
+    if engine.is_device_lost() {
+        // the commands can't be submitted to a lost device, the frame is dropped without
+        // presenting it
+        commands.remove_resource::<FrameResources>();
+        capture.drop_pending();
+        return;
+    }
+
+    // the timestamps are resolved after every pass that wrote them
+    collected_commands.extend(gpu_profiler.resolve(&engine.device));
     engine.queue.submit(collected_commands);
+    // the copies of the frame can only be mapped once they're submitted
+    capture.submit_pending();
 
     frame.present();
     commands.remove_resource::<FrameResources>();
+
+    gpu_profiler.collect(&engine.device);
+    if gpu_profiler.is_supported() {
+        engine.with_debug_ui(|d| d.fps_window_mut().set_gpu_times(gpu_profiler.last_times()));
+    }
 }
@@ -0,0 +1,28 @@
+use crate::render_engine::gpu_profiler::GpuProfiler;
+use crate::render_engine::resources::{CommandBufferCollector, FrameResources};
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+use wgpu::CommandEncoderDescriptor;
+
+/// maps the linear scene into the range of the surface and encodes it as sRGB
+pub fn ecs_tonemap(
+    engine: Res<RenderEngineResources>,
+    frame: Res<FrameResources>,
+    command_collector: Res<CommandBufferCollector>,
+    gpu_profiler: Res<GpuProfiler>,
+) {
+    puffin::profile_function!();
+
+    let mut encoder = engine
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ecs_tonemap_encoder"),
+        });
+    let gpu_scope = gpu_profiler.scope("tonemap");
+    gpu_scope.begin(&mut encoder);
+
+    engine.tonemapper.draw(&mut encoder, &frame.output_view);
+
+    gpu_scope.end(&mut encoder);
+    command_collector.push(encoder);
+}
@@ -5,20 +5,67 @@ pub fn update_render_engine(mut engine: ResMut<RenderEngineResources>) {
     puffin::profile_function!();
     engine.camera.update_uniform_buffer(&engine.queue);
 
-    if engine.config.present_mode != engine.egui_debug_ui.read().fps_window().present_mode() {
+    let requested = engine
+        .egui_debug_ui
+        .write()
+        .display_window_mut()
+        .take_requested();
+    if let Some(settings) = requested {
+        info!("Applying display settings {:?} (reason: DebugUi)", settings);
+        let result = engine.apply_display_settings(settings);
+        engine
+            .egui_debug_ui
+            .write()
+            .display_window_mut()
+            .set_apply_result(result);
+    }
+
+    let requested = engine
+        .egui_debug_ui
+        .write()
+        .fps_window_mut()
+        .take_requested_frame_limiter();
+    if let Some(settings) = requested {
         info!(
-            "Updating present mode from {:?} to {:?} (reason: DebugUi)",
-            engine.config.present_mode,
-            engine.egui_debug_ui.read().fps_window().present_mode()
+            "Applying frame limiter settings {:?} (reason: DebugUi)",
+            settings
         );
-        let mode = engine
-            .egui_debug_ui
-            .read()
-            .fps_window()
-            .present_mode()
-            .clone();
-        engine.config.present_mode = mode;
-        engine.reconfigure_surface();
+        engine.frame_limiter.set_settings(settings);
+    }
+
+    engine.api_trace.frame_finished();
+    let requested = engine
+        .egui_debug_ui
+        .write()
+        .api_trace_window_mut()
+        .take_requested();
+    if let Some((dir, frames)) = requested {
+        info!(
+            "Arming a wgpu trace of {} frames in {} (reason: DebugUi)",
+            frames,
+            dir.display()
+        );
+        engine.api_trace.arm(&dir, frames);
+    }
+
+    {
+        let mut debug_ui = engine.egui_debug_ui.write();
+        debug_ui.display_window_mut().set_current(
+            &engine.display,
+            &engine.supported_present_modes,
+            &engine.supported_surface_formats,
+            &engine.supported_msaa_samples,
+        );
+        debug_ui
+            .fps_window_mut()
+            .set_present_mode(engine.config.present_mode);
+        debug_ui.fps_window_mut().set_frame_limiter(
+            engine.frame_limiter.settings(),
+            engine.frame_limiter.idle_time(),
+        );
+        debug_ui
+            .api_trace_window_mut()
+            .set_recording(engine.api_trace.status());
     }
 
     engine.egui_debug_ui.write().cache_window_mut().update(
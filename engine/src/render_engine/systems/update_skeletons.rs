@@ -0,0 +1,36 @@
+use crate::ecs::resources::DeltaTime;
+use crate::render_engine::components::mesh::Mesh2D;
+use crate::render_engine::components::skeleton::Skeleton;
+use crate::render_engine::components::texture::Texture;
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+
+pub fn ecs_update_skeletons(
+    mut commands: Commands,
+    engine: Res<RenderEngineResources>,
+    dt: Res<DeltaTime>,
+    mut skeletons: Query<(Entity, &mut Skeleton, Option<&mut Mesh2D>, Option<&Texture>)>,
+) {
+    puffin::profile_function!();
+    let dt = dt.as_secs_f32();
+
+    for (entity, mut skeleton, mesh, texture) in skeletons.iter_mut() {
+        skeleton.advance(dt);
+        let data = skeleton.build_mesh();
+
+        match mesh {
+            Some(mut mesh) => mesh.update(&engine.device, &engine.queue, &data),
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(Mesh2D::new(&engine.device, &data));
+            }
+        }
+
+        if texture.is_none() {
+            commands
+                .entity(entity)
+                .insert(Texture(skeleton.texture().clone()));
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::render_engine::components::mesh::Mesh2D;
+use crate::render_engine::components::nine_slice::NineSlice;
+use crate::render_engine::components::parallax::ParallaxLayer;
+use crate::render_engine::components::position::Position;
+use crate::render_engine::components::static_sprite::Static;
+use crate::render_engine::components::texture::Texture;
+use crate::render_engine::spatial_grid::SpatialGrid;
+use bevy_ecs::prelude::*;
+
+/// moves the sprites whose position changed since the last frame to their new cells
+pub fn ecs_update_spatial_grid(
+    mut grid: ResMut<SpatialGrid>,
+    moved: Query<
+        (Entity, &Position),
+        (
+            With<Texture>,
+            Or<(Changed<Position>, Added<Texture>)>,
+            Without<NineSlice>,
+            Without<Mesh2D>,
+            Without<ParallaxLayer>,
+            Without<Static>,
+        ),
+    >,
+    sprites: Query<
+        &Position,
+        (
+            With<Texture>,
+            Without<NineSlice>,
+            Without<Mesh2D>,
+            Without<ParallaxLayer>,
+        ),
+    >,
+    became_static: Query<Entity, Added<Static>>,
+    mut removed_static: RemovedComponents<Static>,
+    mut removed_positions: RemovedComponents<Position>,
+    mut removed_textures: RemovedComponents<Texture>,
+) {
+    puffin::profile_function!();
+
+    // despawned entities show up here as well
+    for entity in removed_positions.read().chain(removed_textures.read()) {
+        grid.remove(entity);
+    }
+
+    // static sprites are drawn from their bundles instead
+    for entity in became_static.iter() {
+        grid.remove(entity);
+    }
+
+    for (entity, pos) in moved.iter() {
+        let (min, max) = crate::sprite::compute_bounds(pos.0);
+        grid.insert(entity, min, max);
+    }
+
+    // sprites that stopped being static are drawn like the others again
+    for entity in removed_static.read() {
+        if let Ok(pos) = sprites.get(entity) {
+            let (min, max) = crate::sprite::compute_bounds(pos.0);
+            grid.insert(entity, min, max);
+        }
+    }
+}
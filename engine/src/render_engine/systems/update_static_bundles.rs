@@ -0,0 +1,37 @@
+use crate::render_engine::components::position::Position;
+use crate::render_engine::components::static_sprite::Static;
+use crate::render_engine::components::texture::Texture;
+use crate::render_engine::static_bundles::StaticSpriteBundles;
+use crate::render_engine::systems::render_sprites::PlainSpriteFilter;
+use bevy_ecs::prelude::*;
+
+/// invalidates the bundles of the chunks with static sprites that were added, moved or changed
+pub fn ecs_update_static_bundles(
+    mut bundles: ResMut<StaticSpriteBundles>,
+    changed: Query<
+        (Entity, &Position),
+        (
+            With<Static>,
+            With<Texture>,
+            PlainSpriteFilter,
+            Or<(Changed<Position>, Changed<Texture>, Added<Static>)>,
+        ),
+    >,
+    mut removed_static: RemovedComponents<Static>,
+    mut removed_positions: RemovedComponents<Position>,
+    mut removed_textures: RemovedComponents<Texture>,
+) {
+    puffin::profile_function!();
+
+    for entity in removed_static
+        .read()
+        .chain(removed_positions.read())
+        .chain(removed_textures.read())
+    {
+        bundles.remove(entity);
+    }
+
+    for (entity, pos) in changed.iter() {
+        bundles.insert(entity, pos.0);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::ecs::resources::DeltaTime;
+use crate::render_engine::components::mesh::Mesh2D;
+use crate::render_engine::components::position::Position;
+use crate::render_engine::components::trail::Trail;
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+
+pub fn ecs_update_trails(
+    engine: Res<RenderEngineResources>,
+    dt: Res<DeltaTime>,
+    mut trails: Query<(&Position, &mut Trail)>,
+) {
+    puffin::profile_function!();
+    let dt = dt.as_secs_f32();
+
+    for (pos, mut trail) in trails.iter_mut() {
+        let head = pos.0.truncate();
+        trail.record(head, dt);
+        let data = trail.build_mesh(head);
+
+        let mesh = trail.mesh_mut();
+        if data.indices.is_empty() {
+            *mesh = None;
+            continue;
+        }
+        match mesh {
+            Some(mesh) => mesh.update(&engine.device, &engine.queue, &data),
+            None => *mesh = Some(Mesh2D::new(&engine.device, &data)),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use crate::render_engine::dynamic_texture::DynamicTexture;
+use crate::render_engine::RenderEngineResources;
+use bevy_ecs::prelude::*;
+
+pub fn ecs_upload_dynamic_textures(
+    engine: Res<RenderEngineResources>,
+    mut textures: Query<&mut DynamicTexture>,
+) {
+    puffin::profile_function!();
+
+    for mut texture in textures.iter_mut() {
+        // avoid triggering change detection when there's nothing to upload
+        if texture.is_dirty() {
+            texture.upload(&engine.queue);
+        }
+    }
+}
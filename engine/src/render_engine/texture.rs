@@ -1,6 +1,10 @@
-use crate::asset_management::Uuid;
+use crate::asset_management::{AssetMetadata, Uuid};
+use crate::render_engine::compressed_texture::{self, CompressedImage, Container};
+use crate::render_engine::indexed_texture::IndexedImage;
 use anyhow::Result;
 use image::{DynamicImage, GenericImageView, ImageFormat};
+use log::warn;
+use serde::Deserialize;
 use std::num::NonZeroU32;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -9,6 +13,8 @@ use wgpu::*;
 pub struct GpuTexture {
     inner: Texture,
     uuid: Uuid,
+    size: Extent3d,
+    metadata: AssetMetadata,
     pub(crate) bind_group: Arc<BindGroup>,
 }
 
@@ -19,14 +25,40 @@ impl GpuTexture {
         data: &[u8],
         label: Option<&str>,
         uuid: Uuid,
+        metadata: AssetMetadata,
     ) -> Result<Self> {
+        if metadata.texture.indexed {
+            let image = IndexedImage::load(data, None)?;
+            return Ok(Self::new_from_indexed(
+                device,
+                queue,
+                &image,
+                label.unwrap_or("unnamed"),
+                uuid,
+                metadata,
+            ));
+        }
+
+        if Container::detect(data).is_some() {
+            let image = CompressedImage::load(data)?;
+            return Self::new_from_compressed(
+                device,
+                queue,
+                &image,
+                label.unwrap_or("unnamed"),
+                uuid,
+                metadata,
+            );
+        }
+
         let image = image::load_from_memory(data)?;
-        Ok(Self::new_from_image(
+        Ok(Self::new_from_image_with_metadata(
             device,
             queue,
             &image,
             label.unwrap_or("unnamed"),
             uuid,
+            metadata,
         ))
     }
 
@@ -37,14 +69,28 @@ impl GpuTexture {
         format: ImageFormat,
         label: Option<&str>,
         uuid: Uuid,
+        metadata: AssetMetadata,
     ) -> Result<Self> {
+        if metadata.texture.indexed {
+            let image = IndexedImage::load(data, Some(format))?;
+            return Ok(Self::new_from_indexed(
+                device,
+                queue,
+                &image,
+                label.unwrap_or("unnamed"),
+                uuid,
+                metadata,
+            ));
+        }
+
         let image = image::load_from_memory_with_format(data, format)?;
-        Ok(Self::new_from_image(
+        Ok(Self::new_from_image_with_metadata(
             device,
             queue,
             &image,
             label.unwrap_or("unnamed"),
             uuid,
+            metadata,
         ))
     }
 
@@ -55,19 +101,50 @@ impl GpuTexture {
         label: &str,
         uuid: Uuid,
     ) -> Self {
+        Self::new_from_image_with_metadata(
+            device,
+            queue,
+            image,
+            label,
+            uuid,
+            AssetMetadata::default(),
+        )
+    }
+
+    pub fn new_from_image_with_metadata(
+        device: &Device,
+        queue: &Queue,
+        image: &DynamicImage,
+        label: &str,
+        uuid: Uuid,
+        metadata: AssetMetadata,
+    ) -> Self {
+        let settings = &metadata.texture;
         let texture_size = Extent3d {
             width: image.width(),
             height: image.height(),
             depth_or_array_layers: 1,
         };
 
+        let mip_level_count = if settings.mipmaps {
+            mip_level_count(texture_size)
+        } else {
+            1
+        };
+
+        let mut usage = TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST;
+        if mip_level_count > 1 {
+            // the mip levels are rendered from the level above
+            usage |= TextureUsages::RENDER_ATTACHMENT;
+        }
+
         let texture = device.create_texture(&TextureDescriptor {
             size: texture_size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: TextureDimension::D2,
             format: TextureFormat::Rgba8UnormSrgb,
-            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            usage,
             label: Some(&format!("{} TEX", label)),
         });
 
@@ -89,17 +166,180 @@ impl GpuTexture {
             texture_size,
         );
 
-        let texture_view = texture.create_view(&TextureViewDescriptor::default());
-        let texture_sampler = device.create_sampler(&SamplerDescriptor {
-            address_mode_u: AddressMode::ClampToEdge,
-            address_mode_v: AddressMode::ClampToEdge,
-            address_mode_w: AddressMode::ClampToEdge,
-            mag_filter: FilterMode::Nearest,
-            min_filter: FilterMode::Nearest,
-            mipmap_filter: FilterMode::Nearest,
-            ..Default::default()
+        if mip_level_count > 1 {
+            generate_mipmaps(
+                device,
+                queue,
+                &texture,
+                TextureFormat::Rgba8UnormSrgb,
+                mip_level_count,
+            );
+        }
+
+        Self::from_texture(device, texture, texture_size, label, uuid, metadata)
+    }
+
+    /// uploads the indices as `R8Unorm`, a sampled index is `index / 255`
+    ///
+    /// Blending between indices makes no sense, so the texture is always sampled with the nearest
+    /// filter and without mipmaps.
+    pub fn new_from_indexed(
+        device: &Device,
+        queue: &Queue,
+        image: &IndexedImage,
+        label: &str,
+        uuid: Uuid,
+        mut metadata: AssetMetadata,
+    ) -> Self {
+        if metadata.texture.filter != TextureFilter::Nearest || metadata.texture.mipmaps {
+            warn!(
+                "{} is indexed, it's sampled with the nearest filter and without mipmaps",
+                label
+            );
+            metadata.texture.filter = TextureFilter::Nearest;
+            metadata.texture.mipmaps = false;
+        }
+
+        let texture_size = Extent3d {
+            width: image.width,
+            height: image.height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&TextureDescriptor {
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::R8Unorm,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            label: Some(&format!("{} TEX", label)),
         });
 
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &image.indices,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(image.width),
+                rows_per_image: NonZeroU32::new(image.height),
+            },
+            texture_size,
+        );
+
+        Self::from_texture(device, texture, texture_size, label, uuid, metadata)
+    }
+
+    /// uploads a KTX2 or DDS texture as is if the device supports its format, otherwise
+    /// it's decompressed to RGBA8 first
+    pub fn new_from_compressed(
+        device: &Device,
+        queue: &Queue,
+        image: &CompressedImage,
+        label: &str,
+        uuid: Uuid,
+        metadata: AssetMetadata,
+    ) -> Result<Self> {
+        let texture_size = image.size();
+        let (format, levels) = if image.is_supported(device.features()) {
+            (image.format, image.levels.clone())
+        } else {
+            warn!(
+                "{:?} isn't supported by the device, decompressing {} on the CPU",
+                image.format, label
+            );
+            (image.decompressed_format(), image.decompress()?)
+        };
+
+        let texture = device.create_texture(&TextureDescriptor {
+            size: texture_size,
+            mip_level_count: levels.len() as u32,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            label: Some(&format!("{} TEX", label)),
+        });
+
+        for (level, data) in levels.iter().enumerate() {
+            let level = level as u32;
+            let (bytes_per_row, rows) =
+                compressed_texture::level_layout(format, image.width, image.height, level);
+
+            queue.write_texture(
+                ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: level,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                data,
+                ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(bytes_per_row),
+                    rows_per_image: NonZeroU32::new(rows),
+                },
+                // the copy has to cover whole blocks, even when the level is smaller than a block
+                texture_size
+                    .mip_level_size(level, false)
+                    .physical_size(format),
+            );
+        }
+
+        Ok(Self::from_texture(
+            device,
+            texture,
+            texture_size,
+            label,
+            uuid,
+            metadata,
+        ))
+    }
+
+    /// an RGBA8 texture that's filled in later with `queue.write_texture`, see `DynamicTexture`
+    pub fn new_empty(
+        device: &Device,
+        width: u32,
+        height: u32,
+        label: &str,
+        uuid: Uuid,
+        metadata: AssetMetadata,
+    ) -> Self {
+        let texture_size = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&TextureDescriptor {
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            label: Some(&format!("{} TEX", label)),
+        });
+
+        Self::from_texture(device, texture, texture_size, label, uuid, metadata)
+    }
+
+    fn from_texture(
+        device: &Device,
+        texture: Texture,
+        size: Extent3d,
+        label: &str,
+        uuid: Uuid,
+        metadata: AssetMetadata,
+    ) -> Self {
+        let texture_view = texture.create_view(&TextureViewDescriptor::default());
+        let texture_sampler = metadata.texture.create_sampler(device, label);
+
         let texture_bind_group_layout = Self::build_bind_group_layout(&device, label);
 
         let texture_bind_group = device.create_bind_group(&BindGroupDescriptor {
@@ -120,6 +360,8 @@ impl GpuTexture {
         Self {
             inner: texture,
             uuid,
+            size,
+            metadata,
             bind_group: Arc::new(texture_bind_group),
         }
     }
@@ -160,6 +402,15 @@ impl GpuTexture {
     pub fn uuid(&self) -> Uuid {
         self.uuid
     }
+
+    /// the size of the texture in pixels
+    pub fn size(&self) -> Extent3d {
+        self.size
+    }
+
+    pub fn metadata(&self) -> &AssetMetadata {
+        &self.metadata
+    }
 }
 
 impl Deref for GpuTexture {
@@ -169,3 +420,152 @@ impl Deref for GpuTexture {
         &self.inner
     }
 }
+
+#[derive(Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TextureFilter {
+    /// keeps hard pixel edges, for pixel art
+    Nearest,
+    Linear,
+}
+
+impl From<TextureFilter> for FilterMode {
+    fn from(filter: TextureFilter) -> Self {
+        match filter {
+            TextureFilter::Nearest => FilterMode::Nearest,
+            TextureFilter::Linear => FilterMode::Linear,
+        }
+    }
+}
+
+/// What happens when a texture is sampled outside of the 0-1 range
+#[derive(Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TextureAddressMode {
+    ClampToEdge,
+    Repeat,
+    MirrorRepeat,
+}
+
+impl From<TextureAddressMode> for AddressMode {
+    fn from(mode: TextureAddressMode) -> Self {
+        match mode {
+            TextureAddressMode::ClampToEdge => AddressMode::ClampToEdge,
+            TextureAddressMode::Repeat => AddressMode::Repeat,
+            TextureAddressMode::MirrorRepeat => AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+/// How a texture is sampled, set through the `texture` field of the asset's metadata
+#[derive(Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(default)]
+pub struct TextureImportSettings {
+    pub filter: TextureFilter,
+    pub address_mode: TextureAddressMode,
+    /// generate a full mip chain on the GPU when the texture is loaded
+    pub mipmaps: bool,
+    /// the texture is an indexed PNG or a greyscale image of palette indices, drawn with the
+    /// colors of a `PaletteSwap`
+    pub indexed: bool,
+}
+
+impl Default for TextureImportSettings {
+    fn default() -> Self {
+        Self {
+            filter: TextureFilter::Nearest,
+            address_mode: TextureAddressMode::ClampToEdge,
+            mipmaps: false,
+            indexed: false,
+        }
+    }
+}
+
+impl TextureImportSettings {
+    pub fn create_sampler(&self, device: &Device, label: &str) -> Sampler {
+        let address_mode = self.address_mode.into();
+        device.create_sampler(&SamplerDescriptor {
+            label: Some(&format!("{} SMP", label)),
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: self.filter.into(),
+            min_filter: self.filter.into(),
+            mipmap_filter: self.filter.into(),
+            ..Default::default()
+        })
+    }
+}
+
+/// the amount of mip levels needed to go down to a 1x1 texture
+pub fn mip_level_count(size: Extent3d) -> u32 {
+    32 - size.width.max(size.height).max(1).leading_zeros()
+}
+
+/// fills mip levels 1 and up by downsampling each level from the one above it
+pub fn generate_mipmaps(
+    device: &Device,
+    queue: &Queue,
+    texture: &Texture,
+    format: TextureFormat,
+    mip_level_count: u32,
+) {
+    puffin::profile_function!();
+    let pipeline = crate::pipelines::mipmap::get(device, format);
+    let bind_group_layout = pipeline.get_bind_group_layout(0);
+    let sampler = device.create_sampler(&SamplerDescriptor {
+        label: Some("Mipmap SMP"),
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let views: Vec<TextureView> = (0..mip_level_count)
+        .map(|level| {
+            texture.create_view(&TextureViewDescriptor {
+                label: Some("Mipmap TV"),
+                base_mip_level: level,
+                mip_level_count: NonZeroU32::new(1),
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("generate_mipmaps_encoder"),
+    });
+
+    for level in 1..mip_level_count as usize {
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Mipmap BG"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&views[level - 1]),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Mipmap pass"),
+            color_attachments: &[RenderPassColorAttachment {
+                view: &views[level],
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    queue.submit(std::iter::once(encoder.finish()));
+}
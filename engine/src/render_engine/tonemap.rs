@@ -0,0 +1,210 @@
+use crate::pipelines::tonemap::{build_bind_group_layout, init};
+use crate::render_engine::buffer::{GpuUniformBuffer, Uniform};
+use crate::render_engine::display::{ColorSettings, Tonemapping};
+use wgpu::*;
+use winit::dpi::PhysicalSize;
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TonemapUniform {
+    pub exposure: f32,
+    /// 0 = none, 1 = reinhard, 2 = aces
+    pub mode: u32,
+    /// 1 when the surface format isn't sRGB
+    pub encode_srgb: u32,
+    _padding: u32,
+}
+
+impl TonemapUniform {
+    pub fn new(color: ColorSettings, surface_format: TextureFormat) -> Self {
+        Self {
+            exposure: color.exposure,
+            mode: match color.tonemapping {
+                Tonemapping::None => 0,
+                Tonemapping::Reinhard => 1,
+                Tonemapping::Aces => 2,
+            },
+            encode_srgb: !surface_format.describe().srgb as u32,
+            _padding: 0,
+        }
+    }
+}
+
+impl Uniform for TonemapUniform {
+    fn bind_group_layout_entry() -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }
+    }
+}
+
+/// Owns the linear scene target the passes draw into, and the pass writing it to the surface
+pub struct Tonemapper {
+    scene_target: Texture,
+    scene_view: TextureView,
+    scene_format: TextureFormat,
+    size: PhysicalSize<u32>,
+    surface_format: TextureFormat,
+    bind_group_layout: BindGroupLayout,
+    pipeline: RenderPipeline,
+    uniform_buf: GpuUniformBuffer<TonemapUniform>,
+    bind_group: BindGroup,
+}
+
+impl Tonemapper {
+    pub fn new(
+        device: &Device,
+        size: PhysicalSize<u32>,
+        surface_format: TextureFormat,
+        color: ColorSettings,
+    ) -> Self {
+        let scene_format = color.scene_format();
+        let (scene_target, scene_view) = create_scene_target(device, size, scene_format);
+        let bind_group_layout = build_bind_group_layout(device, "Tonemapper");
+        let pipeline = init(device, surface_format, &bind_group_layout);
+        let uniform_buf = GpuUniformBuffer::new(
+            device,
+            &[TonemapUniform::new(color, surface_format)],
+            Some("Tonemap UB"),
+        );
+        let bind_group = create_bind_group(device, &bind_group_layout, &uniform_buf, &scene_view);
+
+        Self {
+            scene_target,
+            scene_view,
+            scene_format,
+            size,
+            surface_format,
+            bind_group_layout,
+            pipeline,
+            uniform_buf,
+            bind_group,
+        }
+    }
+
+    pub fn scene_target(&self) -> &Texture {
+        &self.scene_target
+    }
+
+    pub fn scene_format(&self) -> TextureFormat {
+        self.scene_format
+    }
+
+    /// recreates the scene target if the size of the surface has changed
+    pub fn resize(&mut self, device: &Device, size: PhysicalSize<u32>) {
+        if self.size != size {
+            self.recreate_scene_target(device, size, self.scene_format);
+        }
+    }
+
+    /// recreates whatever depends on the formats that changed and uploads the other settings
+    pub fn reconfigure(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        surface_format: TextureFormat,
+        color: ColorSettings,
+    ) {
+        if self.surface_format != surface_format {
+            self.pipeline = init(device, surface_format, &self.bind_group_layout);
+            self.surface_format = surface_format;
+        }
+        if self.scene_format != color.scene_format() {
+            self.recreate_scene_target(device, self.size, color.scene_format());
+        }
+        self.uniform_buf
+            .update(queue, &[TonemapUniform::new(color, surface_format)]);
+    }
+
+    /// draws the scene into `output_view`, which has to be a view of the surface texture
+    pub fn draw(&self, encoder: &mut CommandEncoder, output_view: &TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Tonemap pass"),
+            color_attachments: &[RenderPassColorAttachment {
+                view: output_view,
+                resolve_target: None,
+                ops: Operations {
+                    // every pixel is overwritten
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn recreate_scene_target(
+        &mut self,
+        device: &Device,
+        size: PhysicalSize<u32>,
+        format: TextureFormat,
+    ) {
+        puffin::profile_function!();
+        let (scene_target, scene_view) = create_scene_target(device, size, format);
+        self.bind_group = create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.uniform_buf,
+            &scene_view,
+        );
+        self.scene_target = scene_target;
+        self.scene_view = scene_view;
+        self.scene_format = format;
+        self.size = size;
+    }
+}
+
+fn create_scene_target(
+    device: &Device,
+    size: PhysicalSize<u32>,
+    format: TextureFormat,
+) -> (Texture, TextureView) {
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("Scene target TEX"),
+        size: Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+    });
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    (texture, view)
+}
+
+fn create_bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    uniform_buf: &GpuUniformBuffer<TonemapUniform>,
+    scene_view: &TextureView,
+) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+        layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: uniform_buf.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::TextureView(scene_view),
+            },
+        ],
+        label: Some("Tonemap BG"),
+    })
+}
@@ -1,4 +1,5 @@
 use bytemuck::{Pod, Zeroable};
+use serde::Deserialize;
 use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
 
 #[repr(C)]
@@ -47,3 +48,126 @@ impl super::buffer::GpuVertexBufferLayout for Vertex2 {
         }
     }
 }
+
+/// Per-instance data for the instanced sprite pipeline, every field is a vec4 so the layout
+/// matches the one compute shaders write into storage buffers
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub struct SpriteInstance {
+    /// xyz = position, w = rotation in radians
+    pub position: [f32; 4],
+    /// xy = size in world units, a size of zero hides the instance
+    pub size: [f32; 4],
+    pub color: [f32; 4],
+    /// xy = offset, zw = scale of the texture coordinates, used for sprite sheets
+    pub uv_rect: [f32; 4],
+}
+
+impl SpriteInstance {
+    /// an instance with zero size, which isn't rasterized
+    pub const HIDDEN: SpriteInstance = SpriteInstance {
+        position: [0.0; 4],
+        size: [0.0; 4],
+        color: [0.0; 4],
+        uv_rect: [0.0; 4],
+    };
+}
+
+impl super::buffer::GpuVertexBufferLayout for SpriteInstance {
+    fn layout() -> VertexBufferLayout<'static> {
+        const VEC4_SIZE: BufferAddress = std::mem::size_of::<[f32; 4]>() as BufferAddress;
+
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteInstance>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            // locations 0 and 1 are used by Vertex2
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: VEC4_SIZE,
+                    shader_location: 3,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: VEC4_SIZE * 2,
+                    shader_location: 4,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: VEC4_SIZE * 3,
+                    shader_location: 5,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub struct ColorVertex2 {
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl super::buffer::GpuVertexBufferLayout for ColorVertex2 {
+    fn layout() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<ColorVertex2>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// A vertex of a `Mesh2D`, the position is relative to the entity's `Position`
+#[repr(C)]
+#[derive(Pod, Zeroable, Deserialize, Copy, Clone, Debug)]
+pub struct MeshVertex {
+    pub position: [f32; 2],
+    pub tex_coord: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl super::buffer::GpuVertexBufferLayout for MeshVertex {
+    fn layout() -> VertexBufferLayout<'static> {
+        const VEC2_SIZE: BufferAddress = std::mem::size_of::<[f32; 2]>() as BufferAddress;
+
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<MeshVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: VEC2_SIZE,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: VEC2_SIZE * 2,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::asset_management::ToUuid;
 use anyhow::Result;
+use arc_swap::ArcSwap;
 use crossbeam::queue::SegQueue;
 use lazy_static::lazy_static;
 use log::{info, warn};
@@ -55,15 +56,17 @@ impl JobStateTracker {
     }
 
     pub fn flush(&self) -> Result<(), ()> {
-        self.condvar.0.wait(&mut self.condvar.1.lock());
+        let mut lock = self.condvar.1.lock();
 
         loop {
-            if self.state() == JobState::Succeeded {
-                return Ok(());
-            }
-            if self.state() == JobState::Failed {
-                return Err(());
+            // the job may already be done before we start waiting, in which case nobody will notify us
+            match self.state() {
+                JobState::Succeeded => return Ok(()),
+                JobState::Failed => return Err(()),
+                _ => (),
             }
+
+            self.condvar.0.wait_for(&mut lock, Duration::from_millis(1));
         }
     }
 }
@@ -102,8 +105,8 @@ pub struct JobScheduler {
     handles: Vec<(JoinHandle<()>, ThreadStateTracker)>,
     job_queue: Arc<SegQueue<(JobStateTracker, Box<dyn Job>)>>,
     terminate: Arc<AtomicBool>,
-    device: Option<Arc<Device>>,
-    queue: Option<Arc<Queue>>,
+    /// shared with the workers, so they pick up a new device after the old one was lost
+    gpu: Arc<ArcSwap<Option<(Arc<Device>, Arc<Queue>)>>>,
     condvar: Arc<(Condvar, Mutex<()>)>,
 }
 
@@ -113,17 +116,15 @@ impl JobScheduler {
             handles: Vec::new(),
             job_queue: Arc::new(SegQueue::new()),
             terminate: Arc::new(AtomicBool::new(false)),
-            device: None,
-            queue: None,
+            gpu: Arc::new(ArcSwap::new(Arc::new(None))),
             condvar: Arc::new((Condvar::new(), Mutex::new(()))),
         }
     }
 
+    /// also used to rebind the workers to a new device, jobs that are already running finish on
+    /// the old one
     pub fn init_device_queue(device: Arc<Device>, queue: Arc<Queue>) {
-        Self::with_lock(|scheduler| {
-            scheduler.device = Some(device);
-            scheduler.queue = Some(queue);
-        })
+        Self::with_lock(|scheduler| scheduler.gpu.store(Arc::new(Some((device, queue)))))
     }
 
     fn with_lock<R, F: FnOnce(&mut JobScheduler) -> R>(fun: F) -> R {
@@ -132,12 +133,14 @@ impl JobScheduler {
     }
 
     pub fn spawn_worker() -> Result<()> {
-        let (job_queue, terminate, device, queue, condvar) = Self::with_lock(|scheduler| {
+        let (job_queue, terminate, gpu, condvar) = Self::with_lock(|scheduler| {
+            if scheduler.gpu.load().is_none() {
+                panic!("expected a initialized job scheduler");
+            }
             (
                 Arc::clone(&scheduler.job_queue),
                 Arc::clone(&scheduler.terminate),
-                clone_or_panic(&scheduler.device, "expected a initialized job scheduler"),
-                clone_or_panic(&scheduler.queue, "expected a initialized job scheduler"),
+                Arc::clone(&scheduler.gpu),
                 Arc::clone(&scheduler.condvar),
             )
         });
@@ -146,16 +149,7 @@ impl JobScheduler {
         let t_thread_state_tracker = Arc::clone(&thread_state_tracker);
         let handle = thread::Builder::new()
             .name("JobWorker".to_string())
-            .spawn(|| {
-                worker_main(
-                    device,
-                    queue,
-                    condvar,
-                    job_queue,
-                    terminate,
-                    t_thread_state_tracker,
-                )
-            })?;
+            .spawn(|| worker_main(gpu, condvar, job_queue, terminate, t_thread_state_tracker))?;
 
         Self::with_lock(|scheduler| {
             scheduler.handles.push((
@@ -207,16 +201,8 @@ impl JobScheduler {
     }
 }
 
-fn clone_or_panic<T>(v: &Option<Arc<T>>, msg: &str) -> Arc<T> {
-    match v.as_ref() {
-        Some(x) => Arc::clone(x),
-        None => panic!("{}", msg),
-    }
-}
-
 fn worker_main(
-    device: Arc<Device>,
-    queue: Arc<Queue>,
+    gpu: Arc<ArcSwap<Option<(Arc<Device>, Arc<Queue>)>>>,
     condvar: Arc<(Condvar, Mutex<()>)>,
     job_queue: Arc<SegQueue<(JobStateTracker, Box<dyn Job>)>>,
     terminate: Arc<AtomicBool>,
@@ -231,7 +217,9 @@ fn worker_main(
                     .state
                     .store(JobState::Processing as u8, Ordering::Relaxed);
                 let start = Instant::now();
-                match job.run(&device, &queue) {
+                let gpu = gpu.load_full();
+                let (device, queue) = gpu.as_ref().as_ref().unwrap();
+                match job.run(device, queue) {
                     Ok(()) => {
                         if job.get_freq() != JobFrequency::Frame {
                             // we don't want to flood the logs with jobs that happen every frame
@@ -0,0 +1,107 @@
+use super::LuaVMExtension;
+use crate::render_engine::gizmos::{GizmoDrawer, Gizmos, WHITE};
+use anyhow::Result;
+use cgmath::Vector2;
+use mlua::{Lua, Table};
+use std::time::Duration;
+
+pub struct GizmosExtension;
+
+/// runs `f` with a drawer for the `Gizmos` resource that was given to the VM
+fn with_drawer<F: FnOnce(GizmoDrawer)>(vm: &Lua, duration: Option<f64>, f: F) -> mlua::Result<()> {
+    let gizmos = vm.app_data_ref::<Gizmos>().ok_or_else(|| {
+        mlua::Error::RuntimeError("No Gizmos resource was given to the Lua VM".to_string())
+    })?;
+
+    let duration = Duration::from_secs_f64(duration.unwrap_or(0.0).max(0.0));
+    (f)(gizmos.for_duration(duration));
+    Ok(())
+}
+
+/// converts an optional `{r, g, b, a}` table to a colour, defaulting to white
+fn color(color: Option<Vec<f32>>) -> [f32; 4] {
+    match color {
+        Some(c) => [
+            c.get(0).copied().unwrap_or(1.0),
+            c.get(1).copied().unwrap_or(1.0),
+            c.get(2).copied().unwrap_or(1.0),
+            c.get(3).copied().unwrap_or(1.0),
+        ],
+        None => WHITE,
+    }
+}
+
+impl LuaVMExtension for GizmosExtension {
+    fn namespace(&self) -> &'static str {
+        "gizmos"
+    }
+
+    fn build_table<'a>(&'a self, lua: &'a Lua) -> Result<Table> {
+        let lua_line_fn =
+            lua.create_function(
+                |vm,
+                 (x1, y1, x2, y2, c, duration): (
+                    f32,
+                    f32,
+                    f32,
+                    f32,
+                    Option<Vec<f32>>,
+                    Option<f64>,
+                )| {
+                    with_drawer(vm, duration, |d| {
+                        d.line(Vector2::new(x1, y1), Vector2::new(x2, y2), color(c))
+                    })
+                },
+            )?;
+
+        let lua_rect_fn = lua.create_function(
+            |vm, (x, y, w, h, c, duration): (f32, f32, f32, f32, Option<Vec<f32>>, Option<f64>)| {
+                with_drawer(vm, duration, |d| {
+                    d.rect(Vector2::new(x, y), Vector2::new(w, h), color(c))
+                })
+            },
+        )?;
+
+        let lua_circle_fn = lua.create_function(
+            |vm, (x, y, radius, c, duration): (f32, f32, f32, Option<Vec<f32>>, Option<f64>)| {
+                with_drawer(vm, duration, |d| {
+                    d.circle(Vector2::new(x, y), radius, color(c))
+                })
+            },
+        )?;
+
+        let lua_arrow_fn =
+            lua.create_function(
+                |vm,
+                 (x1, y1, x2, y2, c, duration): (
+                    f32,
+                    f32,
+                    f32,
+                    f32,
+                    Option<Vec<f32>>,
+                    Option<f64>,
+                )| {
+                    with_drawer(vm, duration, |d| {
+                        d.arrow(Vector2::new(x1, y1), Vector2::new(x2, y2), color(c))
+                    })
+                },
+            )?;
+
+        let lua_cross_fn = lua.create_function(
+            |vm, (x, y, size, c, duration): (f32, f32, f32, Option<Vec<f32>>, Option<f64>)| {
+                with_drawer(vm, duration, |d| {
+                    d.cross(Vector2::new(x, y), size, color(c))
+                })
+            },
+        )?;
+
+        let table = lua.create_table()?;
+        table.set("line", lua_line_fn)?;
+        table.set("rect", lua_rect_fn)?;
+        table.set("circle", lua_circle_fn)?;
+        table.set("arrow", lua_arrow_fn)?;
+        table.set("cross", lua_cross_fn)?;
+
+        Ok(table)
+    }
+}
@@ -1,4 +1,6 @@
+mod gizmos;
 mod log;
+mod palettes;
 mod print_hook;
 mod time;
 
@@ -11,7 +13,9 @@ use std::fmt::Debug;
 
 lazy_static! {
     static ref EXTENSIONS: Vec<Box<dyn LuaVMExtension>> = vec![
+        Box::new(gizmos::GizmosExtension),
         Box::new(log::LogExtension),
+        Box::new(palettes::PalettesExtension),
         Box::new(print_hook::PrintHookExtension),
         Box::new(time::TimeExtension)
     ];
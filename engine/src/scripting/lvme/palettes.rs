@@ -0,0 +1,45 @@
+use super::{AnyhowResultToLuaResult, LuaVMExtension};
+use crate::render_engine::palettes::Palettes;
+use anyhow::Result;
+use mlua::{Lua, Table};
+
+pub struct PalettesExtension;
+
+/// runs `f` with the `Palettes` resource that was given to the VM
+fn with_palettes<T, F: FnOnce(&Palettes) -> T>(vm: &Lua, f: F) -> mlua::Result<T> {
+    let palettes = vm.app_data_ref::<Palettes>().ok_or_else(|| {
+        mlua::Error::RuntimeError("No Palettes resource was given to the Lua VM".to_string())
+    })?;
+
+    Ok((f)(&palettes))
+}
+
+impl LuaVMExtension for PalettesExtension {
+    fn namespace(&self) -> &'static str {
+        "palettes"
+    }
+
+    fn build_table<'a>(&'a self, lua: &'a Lua) -> Result<Table<'a>> {
+        // every sprite with a PaletteSwap using the name is recolored from the next frame on
+        let lua_load_fn = lua.create_function(|vm, (name, asset): (String, String)| {
+            with_palettes(vm, |p| p.load(&name, &asset))?.to_mlua()
+        })?;
+
+        let lua_remove_fn = lua
+            .create_function(|vm, name: String| with_palettes(vm, |p| p.remove(&name).is_some()))?;
+
+        let lua_rows_fn =
+            lua.create_function(|vm, name: String| with_palettes(vm, |p| p.rows(&name)))?;
+
+        let lua_exists_fn =
+            lua.create_function(|vm, name: String| with_palettes(vm, |p| p.get(&name).is_some()))?;
+
+        let table = lua.create_table()?;
+        table.set("load", lua_load_fn)?;
+        table.set("remove", lua_remove_fn)?;
+        table.set("rows", lua_rows_fn)?;
+        table.set("exists", lua_exists_fn)?;
+
+        Ok(table)
+    }
+}
@@ -1,4 +1,6 @@
 use crate::ecs::EcsWorld;
+use crate::render_engine::gizmos::Gizmos;
+use crate::render_engine::palettes::Palettes;
 use bevy_ecs::prelude::*;
 mod update_scripts;
 
@@ -8,5 +10,14 @@ pub fn initialize_systems_in_world(world: &mut EcsWorld) {
         update_scripts::update_scripts.after(crate::ecs::systems::delta_time::update_delta_time),
     );
 
-    world.world.insert_non_send_resource(super::create_lua_vm());
+    let vm = super::create_lua_vm();
+    // scripts draw into the same gizmos as the rest of the engine
+    if let Some(gizmos) = world.world.get_resource::<Gizmos>() {
+        vm.set_app_data(gizmos.clone());
+    }
+    // and swap the palettes the renderer uses
+    if let Some(palettes) = world.world.get_resource::<Palettes>() {
+        vm.set_app_data(palettes.clone());
+    }
+    world.world.insert_non_send_resource(vm);
 }
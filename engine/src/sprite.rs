@@ -1,7 +1,22 @@
-use cgmath::{Matrix4, Vector3};
+use cgmath::{Matrix4, Vector2, Vector3};
 
 pub fn compute_model_matrix(position: Vector3<f32>) -> Matrix4<f32> {
     let pos_mat = Matrix4::from_translation(position);
     let scale_mat = Matrix4::from_nonuniform_scale(1.0, 0.5, 1.0);
     pos_mat * scale_mat
 }
+
+/// half of the size of a sprite in world units, matching the scale in `compute_model_matrix`
+/// and `SpritePushConstant`
+pub fn half_size() -> Vector2<f32> {
+    Vector2::new(100.0, 50.0)
+}
+
+/// the corners of the area a sprite covers
+pub fn compute_bounds(position: Vector3<f32>) -> (Vector2<f32>, Vector2<f32>) {
+    let half_size = half_size();
+    (
+        position.truncate() - half_size,
+        position.truncate() + half_size,
+    )
+}
@@ -0,0 +1,78 @@
+use super::{EguiWindow, MenuCategory};
+use egui::Ui;
+use std::path::{Path, PathBuf};
+
+/// Arms a wgpu API trace for the next frames, `update_render_engine` starts it when the user
+/// presses record
+pub struct ApiTraceWindow {
+    dir: String,
+    frames: u32,
+    /// the directory and frames left of the trace being recorded
+    recording: Option<(PathBuf, Option<u32>)>,
+    requested: Option<(PathBuf, u32)>,
+}
+
+impl Default for ApiTraceWindow {
+    fn default() -> Self {
+        Self {
+            dir: "./wgpu-traces".to_string(),
+            frames: 10,
+            recording: None,
+            requested: None,
+        }
+    }
+}
+
+impl EguiWindow for ApiTraceWindow {
+    fn title(&self) -> &'static str {
+        "wgpu trace"
+    }
+
+    fn menu_category(&self) -> MenuCategory {
+        MenuCategory::Debug
+    }
+
+    fn draw(&mut self, ui: &mut Ui) {
+        puffin::profile_function!("ApiTraceWindow");
+        match &self.recording {
+            Some((dir, Some(frames))) => {
+                ui.label(format!(
+                    "Recording into {}, {} frames left",
+                    dir.display(),
+                    frames
+                ));
+            }
+            Some((dir, None)) => {
+                ui.label(format!("Recording into {} until exit", dir.display()));
+            }
+            None => {
+                ui.horizontal(|ui| {
+                    ui.label("Directory");
+                    ui.text_edit_singleline(&mut self.dir);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Frames");
+                    ui.add(egui::DragValue::new(&mut self.frames).clamp_range(1..=10000));
+                });
+                ui.label(
+                    "Starting and stopping the trace recreates the device, like after it was \
+                     lost: particles simulated on the GPU start over and textures that weren't \
+                     loaded from an asset have to be created again",
+                );
+                if ui.button("Record").clicked() && self.requested.is_none() {
+                    self.requested = Some((PathBuf::from(self.dir.trim()), self.frames));
+                }
+            }
+        }
+    }
+}
+
+impl ApiTraceWindow {
+    pub fn set_recording(&mut self, recording: Option<(&Path, Option<u32>)>) {
+        self.recording = recording.map(|(dir, frames)| (dir.to_path_buf(), frames));
+    }
+
+    pub fn take_requested(&mut self) -> Option<(PathBuf, u32)> {
+        self.requested.take()
+    }
+}
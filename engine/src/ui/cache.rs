@@ -48,6 +48,18 @@ impl CacheDebugUi {
     }
 }
 
+impl CacheDebugUi {
+    /// forgets the textures registered with egui, they're registered again on the next update
+    pub fn clear(&mut self) {
+        for uuid in self.egui_textures.keys() {
+            if AssetLoader::texture_from_cache(uuid).is_ok() {
+                GpuTextureRef::deregister_inner(uuid);
+            }
+        }
+        self.egui_textures.clear();
+    }
+}
+
 impl Default for CacheDebugUi {
     fn default() -> Self {
         AssetLoader::add_to_active_cache_debug_ui();
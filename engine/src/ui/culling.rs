@@ -0,0 +1,49 @@
+use super::EguiWindow;
+use crate::ui::MenuCategory;
+use egui::Ui;
+
+#[derive(Default)]
+pub struct CullingDebugWindow {
+    visible: usize,
+    culled: usize,
+    static_sprites: usize,
+    static_bundles: usize,
+}
+
+impl EguiWindow for CullingDebugWindow {
+    fn title(&self) -> &'static str {
+        "Culling"
+    }
+
+    fn menu_category(&self) -> MenuCategory {
+        MenuCategory::Performance
+    }
+
+    fn draw(&mut self, ui: &mut Ui) {
+        puffin::profile_function!("CullingDebugWindow");
+        let total = self.visible + self.culled;
+        ui.label(format!("Visible sprites: {}", self.visible));
+        ui.label(format!("Culled sprites: {}", self.culled));
+        if total > 0 {
+            ui.label(format!(
+                "Culled: {:.1}%",
+                self.culled as f64 / total as f64 * 100.0
+            ));
+        }
+        ui.add_space(15.0);
+        ui.label(format!("Static sprites: {}", self.static_sprites));
+        ui.label(format!("Static bundles drawn: {}", self.static_bundles));
+    }
+}
+
+impl CullingDebugWindow {
+    pub fn set_counts(&mut self, visible: usize, culled: usize) {
+        self.visible = visible;
+        self.culled = culled;
+    }
+
+    pub fn set_static_counts(&mut self, sprites: usize, bundles: usize) {
+        self.static_sprites = sprites;
+        self.static_bundles = bundles;
+    }
+}
@@ -0,0 +1,185 @@
+use super::EguiWindow;
+use crate::render_engine::display::{DisplaySettings, Tonemapping, WindowMode};
+use crate::ui::MenuCategory;
+use egui::{Color32, Ui};
+use wgpu::{PresentMode, TextureFormat};
+
+/// Edits a copy of the display settings, `update_render_engine` applies it when the user presses
+/// apply
+#[derive(Default)]
+pub struct DisplaySettingsWindow {
+    /// `None` until the current settings have been set
+    settings: Option<DisplaySettings>,
+    icon: String,
+    supported_present_modes: Vec<PresentMode>,
+    supported_surface_formats: Vec<TextureFormat>,
+    supported_msaa_samples: Vec<u32>,
+    requested: Option<DisplaySettings>,
+    error: Option<String>,
+}
+
+impl EguiWindow for DisplaySettingsWindow {
+    fn title(&self) -> &'static str {
+        "Display"
+    }
+
+    fn menu_category(&self) -> MenuCategory {
+        MenuCategory::Debug
+    }
+
+    fn draw(&mut self, ui: &mut Ui) {
+        puffin::profile_function!("DisplaySettingsWindow");
+        let Self {
+            settings,
+            icon,
+            supported_present_modes,
+            supported_surface_formats,
+            supported_msaa_samples,
+            requested,
+            error,
+        } = self;
+        let settings = match settings {
+            Some(s) => s,
+            None => {
+                ui.label("Waiting for the render engine");
+                return;
+            }
+        };
+
+        egui::ComboBox::from_label("Present mode")
+            .selected_text(format!("{:?}", settings.present_mode))
+            .show_ui(ui, |ui| {
+                for mode in supported_present_modes.iter() {
+                    ui.selectable_value(&mut settings.present_mode, *mode, format!("{:?}", mode));
+                }
+            });
+        ui.label(present_mode_description(settings.present_mode));
+        egui::ComboBox::from_label("MSAA samples")
+            .selected_text(msaa_label(settings.msaa_samples))
+            .show_ui(ui, |ui| {
+                for samples in supported_msaa_samples.iter() {
+                    ui.selectable_value(&mut settings.msaa_samples, *samples, msaa_label(*samples));
+                }
+            });
+        ui.add_space(15.0);
+
+        egui::ComboBox::from_label("Surface format")
+            .selected_text(format!("{:?}", settings.surface_format))
+            .show_ui(ui, |ui| {
+                for format in supported_surface_formats.iter() {
+                    ui.selectable_value(
+                        &mut settings.surface_format,
+                        *format,
+                        format!("{:?}", format),
+                    );
+                }
+            });
+        ui.checkbox(&mut settings.color.hdr, "HDR scene (Rgba16Float)");
+        egui::ComboBox::from_label("Tonemapping")
+            .selected_text(format!("{:?}", settings.color.tonemapping))
+            .show_ui(ui, |ui| {
+                for tonemapping in [Tonemapping::None, Tonemapping::Reinhard, Tonemapping::Aces] {
+                    ui.selectable_value(
+                        &mut settings.color.tonemapping,
+                        tonemapping,
+                        format!("{:?}", tonemapping),
+                    );
+                }
+            });
+        ui.add(
+            egui::Slider::new(&mut settings.color.exposure, 0.1..=8.0)
+                .logarithmic(true)
+                .text("Exposure"),
+        );
+        ui.add_space(15.0);
+
+        egui::ComboBox::from_label("Window mode")
+            .selected_text(format!("{:?}", settings.window_mode))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut settings.window_mode, WindowMode::Windowed, "Windowed");
+                ui.selectable_value(
+                    &mut settings.window_mode,
+                    WindowMode::Borderless,
+                    "Borderless",
+                );
+                ui.selectable_value(
+                    &mut settings.window_mode,
+                    WindowMode::Fullscreen,
+                    "Fullscreen",
+                );
+            });
+        ui.horizontal(|ui| {
+            ui.label("Size");
+            ui.add(egui::DragValue::new(&mut settings.size.width).clamp_range(1..=16384));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut settings.size.height).clamp_range(1..=16384));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Title");
+            ui.text_edit_singleline(&mut settings.title);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Icon asset");
+            ui.text_edit_singleline(icon);
+        });
+        ui.add_space(15.0);
+
+        if ui.button("Apply").clicked() {
+            let mut settings = settings.clone();
+            settings.icon = Some(icon.trim().to_string()).filter(|i| !i.is_empty());
+            *requested = Some(settings);
+        }
+        if let Some(error) = error {
+            ui.colored_label(Color32::RED, error);
+        }
+    }
+}
+
+impl DisplaySettingsWindow {
+    /// the settings of the engine, they only replace the edited copy when nothing is being edited
+    pub fn set_current(
+        &mut self,
+        settings: &DisplaySettings,
+        supported_present_modes: &[PresentMode],
+        supported_surface_formats: &[TextureFormat],
+        supported_msaa_samples: &[u32],
+    ) {
+        if self.settings.is_none() {
+            self.icon = settings.icon.clone().unwrap_or_default();
+            self.settings = Some(settings.clone());
+        }
+        self.supported_present_modes = supported_present_modes.to_vec();
+        self.supported_surface_formats = supported_surface_formats.to_vec();
+        self.supported_msaa_samples = supported_msaa_samples.to_vec();
+    }
+
+    pub fn take_requested(&mut self) -> Option<DisplaySettings> {
+        self.requested.take()
+    }
+
+    /// shows the error of the last apply, if it failed the edited settings are kept so they can
+    /// be fixed
+    pub fn set_apply_result(&mut self, result: anyhow::Result<()>) {
+        self.error = result.err().map(|e| e.to_string());
+        if self.error.is_none() {
+            // pick up the settings as the engine has them
+            self.settings = None;
+        }
+    }
+}
+
+fn present_mode_description(mode: PresentMode) -> &'static str {
+    match mode {
+        PresentMode::Fifo => "Waits for VBlank before presenting, caps the framerate to the refresh rate, no tearing should occur",
+        PresentMode::Mailbox => "Waits for VBlank before presenting, but multiple frames may be submitted before the VBlank occurs, does not cap the framerate, no tearing should occur",
+        PresentMode::Immediate => "Does not wait for VBlank before presenting, does not cap the framerate, but visible tearing may occur",
+    }
+}
+
+fn msaa_label(samples: u32) -> String {
+    if samples == 1 {
+        "Off".to_string()
+    } else {
+        format!("{}x", samples)
+    }
+}
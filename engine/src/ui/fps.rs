@@ -1,7 +1,9 @@
 use super::EguiWindow;
+use crate::render_engine::frame_limiter::{BackgroundMode, FrameLimiterSettings, RedrawMode};
 use crate::ui::MenuCategory;
 use egui::Ui;
 use std::collections::VecDeque;
+use std::time::Duration;
 use wgpu::PresentMode;
 
 const MAX_FRAMETIME_SAMPLES: usize = 60;
@@ -11,6 +13,12 @@ pub struct DebugFrametimeWindow {
     avg_frametime: f64,
     frametimes: VecDeque<f64>,
     present_mode: PresentMode,
+    /// `None` until the settings of the engine have been set
+    limiter: Option<FrameLimiterSettings>,
+    requested_limiter: Option<FrameLimiterSettings>,
+    idle_time: Duration,
+    /// the GPU time of every timed pass, empty without timestamp queries
+    gpu_times: Vec<(&'static str, Duration)>,
 }
 
 impl EguiWindow for DebugFrametimeWindow {
@@ -44,14 +52,27 @@ impl EguiWindow for DebugFrametimeWindow {
             }
         ));
         ui.add_space(15.0);
-        egui::ComboBox::from_label("Select a wgpu present mode")
-            .selected_text(format!("{:?}", self.present_mode))
-            .show_ui(ui, |ui| {
-                ui.selectable_value(&mut self.present_mode, PresentMode::Fifo, "Fifo");
-                ui.selectable_value(&mut self.present_mode, PresentMode::Mailbox, "Mailbox");
-                ui.selectable_value(&mut self.present_mode, PresentMode::Immediate, "Immediate");
-            });
-        ui.label(present_mode_description(self.present_mode));
+        ui.label(format!("Present mode: {:?}", self.present_mode));
+        ui.label(format!(
+            "Idle before the last frame: {:.2}ms",
+            self.idle_time.as_secs_f64() * 1000.0
+        ));
+
+        if !self.gpu_times.is_empty() {
+            ui.add_space(15.0);
+            let total: Duration = self.gpu_times.iter().map(|(_, time)| *time).sum();
+            ui.label(format!("GPU: {:.2}ms", total.as_secs_f64() * 1000.0));
+            for (name, time) in self.gpu_times.iter() {
+                ui.label(format!("  {}: {:.2}ms", name, time.as_secs_f64() * 1000.0));
+            }
+        }
+
+        if let Some(limiter) = &mut self.limiter {
+            ui.add_space(15.0);
+            if draw_limiter_settings(ui, limiter) {
+                self.requested_limiter = Some(*limiter);
+            }
+        }
     }
 }
 
@@ -61,6 +82,10 @@ impl Default for DebugFrametimeWindow {
             avg_frametime: 0.0,
             frametimes: VecDeque::new(),
             present_mode: PresentMode::Fifo,
+            limiter: None,
+            requested_limiter: None,
+            idle_time: Duration::new(0, 0),
+            gpu_times: Vec::new(),
         }
     }
 }
@@ -82,12 +107,89 @@ impl DebugFrametimeWindow {
     pub fn present_mode(&self) -> PresentMode {
         self.present_mode
     }
-}
 
-fn present_mode_description(mode: PresentMode) -> &'static str {
-    match mode {
-        PresentMode::Fifo => "Waits for VBlank before presenting, caps the framerate to the refresh rate, no tearing should occur",
-        PresentMode::Mailbox => "Waits for VBlank before presenting, but multiple frames may be submitted before the VBlank occurs, does not cap the framerate, no tearing should occur",
-        PresentMode::Immediate => "Does not wait for VBlank before presenting, does not cap the framerate, but visible tearing may occur",
+    pub fn set_frame_limiter(&mut self, settings: FrameLimiterSettings, idle_time: Duration) {
+        if self.requested_limiter.is_none() {
+            self.limiter = Some(settings);
+        }
+        self.idle_time = idle_time;
     }
+
+    pub fn take_requested_frame_limiter(&mut self) -> Option<FrameLimiterSettings> {
+        self.requested_limiter.take()
+    }
+
+    /// the GPU times of the passes of a recent frame, they're read back a few frames late
+    pub fn set_gpu_times(&mut self, times: &[(&'static str, Duration)]) {
+        self.gpu_times = times.to_vec();
+    }
+
+    /// the present mode of the surface, it's picked in the display settings window
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.present_mode = present_mode;
+    }
+}
+
+/// returns whether any of the settings changed
+fn draw_limiter_settings(ui: &mut Ui, settings: &mut FrameLimiterSettings) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let mut limited = settings.target_fps.is_some();
+        if ui.checkbox(&mut limited, "Limit FPS").changed() {
+            settings.target_fps = if limited { Some(60.0) } else { None };
+            changed = true;
+        }
+        if let Some(fps) = &mut settings.target_fps {
+            changed |= ui
+                .add(egui::DragValue::new(fps).clamp_range(1.0..=1000.0))
+                .changed();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        let before = settings.background;
+        egui::ComboBox::from_label("Unfocused")
+            .selected_text(match settings.background {
+                BackgroundMode::Continue => "Continue",
+                BackgroundMode::Throttle(_) => "Throttle",
+                BackgroundMode::Pause => "Pause",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(
+                    &mut settings.background,
+                    BackgroundMode::Continue,
+                    "Continue",
+                );
+                if ui
+                    .selectable_label(
+                        matches!(settings.background, BackgroundMode::Throttle(_)),
+                        "Throttle",
+                    )
+                    .clicked()
+                {
+                    settings.background = BackgroundMode::Throttle(30.0);
+                }
+                ui.selectable_value(&mut settings.background, BackgroundMode::Pause, "Pause");
+            });
+        if let BackgroundMode::Throttle(fps) = &mut settings.background {
+            ui.add(egui::DragValue::new(fps).clamp_range(1.0..=1000.0));
+        }
+        changed |= before != settings.background;
+    });
+
+    let before = settings.redraw_mode;
+    egui::ComboBox::from_label("Redraw")
+        .selected_text(format!("{:?}", settings.redraw_mode))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(
+                &mut settings.redraw_mode,
+                RedrawMode::Continuous,
+                "Continuous",
+            );
+            ui.selectable_value(&mut settings.redraw_mode, RedrawMode::Reactive, "Reactive");
+        });
+    changed |= before != settings.redraw_mode;
+
+    changed
 }
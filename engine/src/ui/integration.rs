@@ -137,6 +137,11 @@ impl EguiIntegration {
         self.scale_factor = scale_factor;
     }
 
+    /// the textures registered with the old render pass are gone, they have to be registered again
+    pub fn recreate_render_pass(&mut self, device: &Device, surface_config: &SurfaceConfiguration) {
+        self.render_pass = egui_wgpu_backend::RenderPass::new(device, surface_config.format, 1);
+    }
+
     pub fn render_pass_mut(&mut self) -> &mut egui_wgpu_backend::RenderPass {
         &mut self.render_pass
     }
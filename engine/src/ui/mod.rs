@@ -1,11 +1,17 @@
+use crate::ui::api_trace::ApiTraceWindow;
 use crate::ui::cache::CacheDebugUi;
+use crate::ui::culling::CullingDebugWindow;
+use crate::ui::display::DisplaySettingsWindow;
 use crate::ui::fps::DebugFrametimeWindow;
 use crate::ui::profiler::PuffinProfilerWindow;
 use crate::ui::scheduler::SchedulerWorkerThreadWindow;
 use egui::{CtxRef, Ui};
 use epi::Frame;
 
+mod api_trace;
 mod cache;
+mod culling;
+mod display;
 mod fps;
 pub mod integration;
 mod profiler;
@@ -33,6 +39,12 @@ pub struct DebugUi {
     scheduler_window: SchedulerWorkerThreadWindow,
     show_profile_window: bool,
     profile_window: PuffinProfilerWindow,
+    show_culling_window: bool,
+    culling_window: CullingDebugWindow,
+    show_display_window: bool,
+    display_window: DisplaySettingsWindow,
+    show_api_trace_window: bool,
+    api_trace_window: ApiTraceWindow,
 }
 
 impl epi::App for DebugUi {
@@ -53,11 +65,14 @@ impl epi::App for DebugUi {
                 ui.menu_button("Debug", |ui| {
                     ui.checkbox(&mut self.show_cache_window, "Texture Cache");
                     ui.checkbox(&mut self.show_scheduler_window, "Scheduler");
+                    ui.checkbox(&mut self.show_display_window, "Display settings");
+                    ui.checkbox(&mut self.show_api_trace_window, "wgpu trace");
                 });
 
                 ui.menu_button("Preformance", |ui| {
-                    ui.checkbox(&mut self.show_fps_window, "FPS & Present mode");
+                    ui.checkbox(&mut self.show_fps_window, "FPS");
                     ui.checkbox(&mut self.show_profile_window, "Puffin profiler");
+                    ui.checkbox(&mut self.show_culling_window, "Culling");
                 });
             });
         });
@@ -85,6 +100,24 @@ impl epi::App for DebugUi {
                 self.profile_window.draw(ui);
             });
         }
+
+        if self.show_culling_window {
+            egui::Window::new(self.culling_window.title()).show(ctx, |ui| {
+                self.culling_window.draw(ui);
+            });
+        }
+
+        if self.show_display_window {
+            egui::Window::new(self.display_window.title()).show(ctx, |ui| {
+                self.display_window.draw(ui);
+            });
+        }
+
+        if self.show_api_trace_window {
+            egui::Window::new(self.api_trace_window.title()).show(ctx, |ui| {
+                self.api_trace_window.draw(ui);
+            });
+        }
     }
 }
 
@@ -100,4 +133,16 @@ impl DebugUi {
     pub fn cache_window_mut(&mut self) -> &mut CacheDebugUi {
         &mut self.cache_window
     }
+
+    pub fn culling_window_mut(&mut self) -> &mut CullingDebugWindow {
+        &mut self.culling_window
+    }
+
+    pub fn display_window_mut(&mut self) -> &mut DisplaySettingsWindow {
+        &mut self.display_window
+    }
+
+    pub fn api_trace_window_mut(&mut self) -> &mut ApiTraceWindow {
+        &mut self.api_trace_window
+    }
 }
@@ -0,0 +1,55 @@
+struct CameraUniform {
+    proj: mat4x4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] tex_coords: vec2<f32>;
+};
+
+struct InstanceInput {
+    // xyz = position, w = rotation
+    [[location(2)]] position: vec4<f32>;
+    [[location(3)]] size: vec4<f32>;
+    [[location(4)]] color: vec4<f32>;
+    // xy = offset, zw = scale
+    [[location(5)]] uv_rect: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] tex_coords: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let c = cos(instance.position.w);
+    let s = sin(instance.position.w);
+    let scaled = model.position * instance.size.xy;
+    let rotated = vec2<f32>(scaled.x * c - scaled.y * s, scaled.x * s + scaled.y * c);
+
+    var out: VertexOutput;
+    out.tex_coords = instance.uv_rect.xy + model.tex_coords * instance.uv_rect.zw;
+    out.color = instance.color;
+    out.clip_position = camera.proj * vec4<f32>(rotated + instance.position.xy, 0.0, 1.0);
+    // same as in sprite.wgsl, keeps the depth within the 0-1 range
+    out.clip_position.z = abs(instance.position.z) / 10000.0;
+    return out;
+}
+
+[[group(1), binding(0)]]
+var t_texture: texture_2d<f32>;
+[[group(1), binding(1)]]
+var t_sampler: sampler;
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    return textureSample(t_texture, t_sampler, in.tex_coords) * in.color;
+}
@@ -0,0 +1,156 @@
+struct LightingUniform {
+    inv_view_proj: mat4x4<f32>;
+    ambient: vec4<f32>;
+    light_count: u32;
+    edge_count: u32;
+};
+
+// kind: 0 = point, 1 = spot, 2 = directional
+struct Light {
+    // xy = position, z = radius, w = kind
+    position: vec4<f32>;
+    // rgb = color, a = intensity
+    color: vec4<f32>;
+    // xyz = direction, w = height
+    direction: vec4<f32>;
+    // x = cos(inner angle), y = cos(outer angle)
+    cone: vec4<f32>;
+};
+
+struct LightBuffer {
+    lights: array<Light>;
+};
+
+struct Edge {
+    // xy = start, zw = end
+    points: vec4<f32>;
+    // x = softness, 0 for hard shadows
+    params: vec4<f32>;
+};
+
+struct EdgeBuffer {
+    edges: array<Edge>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> lighting: LightingUniform;
+[[group(0), binding(1)]]
+var<storage, read> light_buffer: LightBuffer;
+[[group(0), binding(2)]]
+var<storage, read> edge_buffer: EdgeBuffer;
+[[group(0), binding(3)]]
+var t_normal: texture_2d<f32>;
+[[group(0), binding(4)]]
+var s_normal: sampler;
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+// draws a single triangle covering the entire screen
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(index & 1u) * 4 - 1);
+    let y = f32(i32(index >> 1u) * 4 - 1);
+    out.clip_position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = vec2<f32>((x + 1.0) * 0.5, (1.0 - y) * 0.5);
+    return out;
+}
+
+fn segments_intersect(p0: vec2<f32>, p1: vec2<f32>, q0: vec2<f32>, q1: vec2<f32>) -> bool {
+    let r = p1 - p0;
+    let s = q1 - q0;
+    let denom = r.x * s.y - r.y * s.x;
+    if (abs(denom) < 0.00001) {
+        return false;
+    }
+
+    let d = q0 - p0;
+    let t = (d.x * s.y - d.y * s.x) / denom;
+    let u = (d.x * r.y - d.y * r.x) / denom;
+    return t > 0.0 && t < 1.0 && u >= 0.0 && u <= 1.0;
+}
+
+// returns 1.0 if the light fully reaches the fragment, 0.0 if it is fully blocked
+fn visibility(frag: vec2<f32>, light: vec2<f32>) -> f32 {
+    var visible = 1.0;
+    let dir = normalize(light - frag);
+    let perp = vec2<f32>(-dir.y, dir.x);
+
+    for (var i: u32 = 0u; i < lighting.edge_count; i = i + 1u) {
+        let edge = edge_buffer.edges[i];
+        let a = edge.points.xy;
+        let b = edge.points.zw;
+        let softness = edge.params.x;
+
+        if (softness <= 0.0) {
+            if (segments_intersect(frag, light, a, b)) {
+                return 0.0;
+            }
+        } else {
+            // sample a few points across the light's penumbra to fake an area light
+            var blocked = 0.0;
+            for (var s: i32 = -2; s <= 2; s = s + 1) {
+                let sample_pos = light + perp * (f32(s) * 0.5 * softness);
+                if (segments_intersect(frag, sample_pos, a, b)) {
+                    blocked = blocked + 1.0;
+                }
+            }
+            visible = visible * (1.0 - blocked / 5.0);
+        }
+    }
+
+    return visible;
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let ndc = vec2<f32>(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0);
+    let world = lighting.inv_view_proj * vec4<f32>(ndc, 0.0, 1.0);
+    let frag = world.xy / world.w;
+    let normal = normalize(textureSample(t_normal, s_normal, in.uv).rgb * 2.0 - 1.0);
+
+    var total = lighting.ambient.rgb;
+
+    for (var i: u32 = 0u; i < lighting.light_count; i = i + 1u) {
+        let light = light_buffer.lights[i];
+        let kind = u32(light.position.w);
+        var to_light: vec3<f32>;
+        var attenuation = 1.0;
+        var shadow_target: vec2<f32>;
+
+        if (kind == 2u) {
+            to_light = normalize(-light.direction.xyz);
+            // directional lights have no position, so shadows are traced towards a point far away
+            shadow_target = frag + normalize(-light.direction.xy + vec2<f32>(0.00001)) * 10000.0;
+        } else {
+            let delta = light.position.xy - frag;
+            let dist = length(delta);
+            let radius = light.position.z;
+            if (dist >= radius) {
+                continue;
+            }
+
+            attenuation = 1.0 - smoothStep(0.0, radius, dist);
+            to_light = normalize(vec3<f32>(delta, light.direction.w));
+            shadow_target = light.position.xy;
+
+            if (kind == 1u) {
+                let cos_angle = dot(normalize(-delta), normalize(light.direction.xy));
+                attenuation = attenuation * smoothStep(light.cone.y, light.cone.x, cos_angle);
+            }
+        }
+
+        if (attenuation <= 0.0) {
+            continue;
+        }
+
+        let diffuse = max(dot(normal, to_light), 0.0);
+        let shadow = visibility(frag, shadow_target);
+        total = total + light.color.rgb * light.color.a * diffuse * attenuation * shadow;
+    }
+
+    return vec4<f32>(total, 1.0);
+}
@@ -0,0 +1,48 @@
+struct CameraUniform {
+    proj: mat4x4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> camera: CameraUniform;
+
+struct PushConstants {
+    model: mat4x4<f32>;
+    z: f32;
+};
+
+var<push_constant> push_constant: PushConstants;
+
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] tex_coords: vec2<f32>;
+    [[location(2)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] tex_coords: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main(
+    model: VertexInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.color = model.color;
+    out.clip_position = camera.proj * push_constant.model * vec4<f32>(model.position.xy, 0.0, 1.0);
+    // same as in sprite.wgsl, keeps the depth within the 0-1 range
+    out.clip_position.z = abs(push_constant.z) / 10000.0;
+    return out;
+}
+
+[[group(1), binding(0)]]
+var t_texture: texture_2d<f32>;
+[[group(1), binding(1)]]
+var t_sampler: sampler;
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    return textureSample(t_texture, t_sampler, in.tex_coords) * in.color;
+}
@@ -0,0 +1,58 @@
+struct CameraUniform {
+    proj: mat4x4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> camera: CameraUniform;
+
+struct PushConstants {
+    model: mat4x4<f32>;
+    z: f32;
+};
+
+var<push_constant> push_constant: PushConstants;
+
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] tex_coords: vec2<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] tex_coords: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main(
+    model: VertexInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.clip_position = camera.proj * push_constant.model * vec4<f32>(model.position.xy, 0.0, 1.0);
+    out.clip_position.z = abs(push_constant.z) / 10000.0;
+    return out;
+}
+
+// the colour texture of the sprite, only used for its alpha channel
+[[group(1), binding(0)]]
+var t_color: texture_2d<f32>;
+[[group(1), binding(1)]]
+var s_color: sampler;
+
+[[group(2), binding(0)]]
+var t_normal: texture_2d<f32>;
+[[group(2), binding(1)]]
+var s_normal: sampler;
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let color = textureSample(t_color, s_color, in.tex_coords);
+    // normal maps are uploaded as sRGB textures, so undo the conversion the sampler did
+    let normal = pow(textureSample(t_normal, s_normal, in.tex_coords).rgb, vec3<f32>(1.0 / 2.2));
+
+    if (color.a < 0.5) {
+        discard;
+    }
+
+    return vec4<f32>(normal, 1.0);
+}
@@ -0,0 +1,54 @@
+struct CameraUniform {
+    proj: mat4x4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> camera: CameraUniform;
+
+struct PushConstants {
+    model: mat4x4<f32>;
+    z: f32;
+    row: u32;
+};
+
+var<push_constant> push_constant: PushConstants;
+
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] tex_coords: vec2<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] tex_coords: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main(
+    model: VertexInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.clip_position = camera.proj * push_constant.model * vec4<f32>(model.position.xy, 0.0, 1.0);
+    out.clip_position.z = abs(push_constant.z) / 10000.0;
+    return out;
+}
+
+// R8Unorm, the index of a pixel is its value * 255
+[[group(1), binding(0)]]
+var t_index: texture_2d<f32>;
+[[group(1), binding(1)]]
+var s_index: sampler;
+// every row is a palette
+[[group(2), binding(0)]]
+var t_palette: texture_2d<f32>;
+[[group(2), binding(1)]]
+var s_palette: sampler;
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let index = i32(round(textureSample(t_index, s_index, in.tex_coords).r * 255.0));
+    let size = textureDimensions(t_palette);
+    let row = min(i32(push_constant.row), size.y - 1);
+    return textureLoad(t_palette, vec2<i32>(min(index, size.x - 1), row), 0);
+}
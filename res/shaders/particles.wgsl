@@ -0,0 +1,110 @@
+// keep in sync with render_engine::particles
+let CURVE_SAMPLES: f32 = 16.0;
+
+struct Particle {
+    // xy = position, z = age, w = lifetime
+    position: vec4<f32>;
+    // xy = velocity, z = z layer, w = 1.0 when alive
+    velocity: vec4<f32>;
+};
+
+struct SpriteInstance {
+    position: vec4<f32>;
+    size: vec4<f32>;
+    color: vec4<f32>;
+    uv_rect: vec4<f32>;
+};
+
+struct EmitterUniform {
+    // xy = acceleration, z = delta time in seconds, w = amount of particles
+    acceleration: vec4<f32>;
+    // x = columns, y = rows, z = frames per second, w = frame count
+    animation: vec4<f32>;
+    color_curve: array<vec4<f32>, 16>;
+    // 16 samples, packed 4 per vector
+    size_curve: array<vec4<f32>, 4>;
+};
+
+struct ParticleBuffer {
+    particles: array<Particle>;
+};
+
+struct InstanceBuffer {
+    instances: array<SpriteInstance>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> emitter: EmitterUniform;
+[[group(0), binding(1)]]
+var<storage, read_write> particle_buffer: ParticleBuffer;
+[[group(0), binding(2)]]
+var<storage, read_write> instance_buffer: InstanceBuffer;
+
+fn sample_color(t: f32) -> vec4<f32> {
+    let x = clamp(t, 0.0, 1.0) * (CURVE_SAMPLES - 1.0);
+    let i = u32(floor(x));
+    let j = min(i + 1u, u32(CURVE_SAMPLES) - 1u);
+    return mix(emitter.color_curve[i], emitter.color_curve[j], fract(x));
+}
+
+fn size_sample(i: u32) -> f32 {
+    let v = emitter.size_curve[i / 4u];
+    let c = i % 4u;
+    if (c == 0u) {
+        return v.x;
+    } else if (c == 1u) {
+        return v.y;
+    } else if (c == 2u) {
+        return v.z;
+    }
+    return v.w;
+}
+
+fn sample_size(t: f32) -> f32 {
+    let x = clamp(t, 0.0, 1.0) * (CURVE_SAMPLES - 1.0);
+    let i = u32(floor(x));
+    let j = min(i + 1u, u32(CURVE_SAMPLES) - 1u);
+    return mix(size_sample(i), size_sample(j), fract(x));
+}
+
+[[stage(compute), workgroup_size(64)]]
+fn cs_main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    let index = id.x;
+    if (index >= u32(emitter.acceleration.w)) {
+        return;
+    }
+
+    var particle = particle_buffer.particles[index];
+    var instance: SpriteInstance;
+    instance.size = vec4<f32>(0.0);
+
+    if (particle.velocity.w > 0.0) {
+        let dt = emitter.acceleration.z;
+        particle.position.z = particle.position.z + dt;
+
+        if (particle.position.z >= particle.position.w) {
+            particle.velocity.w = 0.0;
+        } else {
+            particle.velocity = vec4<f32>(particle.velocity.xy + emitter.acceleration.xy * dt, particle.velocity.zw);
+            particle.position = vec4<f32>(particle.position.xy + particle.velocity.xy * dt, particle.position.zw);
+
+            let t = particle.position.z / particle.position.w;
+            let size = sample_size(t);
+            let frame = floor(particle.position.z * emitter.animation.z) % max(emitter.animation.w, 1.0);
+            let scale = vec2<f32>(1.0 / emitter.animation.x, 1.0 / emitter.animation.y);
+
+            instance.position = vec4<f32>(particle.position.xy, particle.velocity.z, 0.0);
+            instance.size = vec4<f32>(size, size, 0.0, 0.0);
+            instance.color = sample_color(t);
+            instance.uv_rect = vec4<f32>(
+                (frame % emitter.animation.x) * scale.x,
+                floor(frame / emitter.animation.x) * scale.y,
+                scale.x,
+                scale.y,
+            );
+        }
+    }
+
+    particle_buffer.particles[index] = particle;
+    instance_buffer.instances[index] = instance;
+}
@@ -0,0 +1,50 @@
+struct CameraUniform {
+    proj: mat4x4<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> camera: CameraUniform;
+
+struct PushConstants {
+    model: mat4x4<f32>;
+    z: f32;
+    cutoff: f32;
+};
+
+var<push_constant> push_constant: PushConstants;
+
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] tex_coords: vec2<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] tex_coords: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main(
+    model: VertexInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.clip_position = camera.proj * push_constant.model * vec4<f32>(model.position.xy, 0.0, 1.0);
+    out.clip_position.z = abs(push_constant.z) / 10000.0;
+    return out;
+}
+
+[[group(1), binding(0)]]
+var t_texture: texture_2d<f32>;
+[[group(1), binding(1)]]
+var t_sampler: sampler;
+
+// the color isn't written, only the stencil of the fragments that aren't discarded
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let color = textureSample(t_texture, t_sampler, in.tex_coords);
+    if (color.a <= push_constant.cutoff) {
+        discard;
+    }
+    return color;
+}
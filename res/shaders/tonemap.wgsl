@@ -0,0 +1,57 @@
+struct TonemapUniform {
+    exposure: f32;
+    // 0 = none, 1 = reinhard, 2 = aces
+    mode: u32;
+    // 1 when the surface isn't sRGB, so the colors have to be encoded here
+    encode_srgb: u32;
+    padding: u32;
+};
+
+[[group(0), binding(0)]]
+var<uniform> tonemap: TonemapUniform;
+[[group(0), binding(1)]]
+var t_scene: texture_2d<f32>;
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+};
+
+// draws a single triangle covering the entire screen
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(index & 1u) * 4 - 1);
+    let y = f32(i32(index >> 1u) * 4 - 1);
+    out.clip_position = vec4<f32>(x, y, 0.0, 1.0);
+    return out;
+}
+
+// the fit of the ACES curve by Krzysztof Narkowicz
+fn aces(c: vec3<f32>) -> vec3<f32> {
+    return (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14);
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    // the scene has the same size as the surface, so every pixel reads its own texel
+    let scene = textureLoad(t_scene, vec2<i32>(in.clip_position.xy), 0);
+    var color = max(scene.rgb * tonemap.exposure, vec3<f32>(0.0));
+
+    if (tonemap.mode == 1u) {
+        color = color / (color + 1.0);
+    } else if (tonemap.mode == 2u) {
+        color = aces(color);
+    }
+    color = clamp(color, vec3<f32>(0.0), vec3<f32>(1.0));
+
+    if (tonemap.encode_srgb == 1u) {
+        color = linear_to_srgb(color);
+    }
+    return vec4<f32>(color, scene.a);
+}